@@ -0,0 +1,40 @@
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    // Record the target triple, it is only available to build scripts.
+    println!(
+        "cargo:rustc-env=PREFLIGIT_BUILD_TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+
+    commit_info();
+}
+
+/// Record the commit hash of the build, if building from a git checkout.
+fn commit_info() {
+    let git_dir = Path::new(".git");
+    if !git_dir.exists() {
+        return;
+    }
+
+    // Rebuild when `HEAD` moves, either by switching branches or by committing.
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Ok(head) = std::fs::read_to_string(git_dir.join("HEAD")) {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{reference}");
+        }
+    }
+
+    let Ok(output) = Command::new("git")
+        .args(["rev-parse", "--short=9", "HEAD"])
+        .output()
+    else {
+        return;
+    };
+    if !output.status.success() {
+        return;
+    }
+    let hash = String::from_utf8_lossy(&output.stdout);
+    println!("cargo:rustc-env=PREFLIGIT_COMMIT_HASH={}", hash.trim());
+}
@@ -0,0 +1,273 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use fancy_regex::Regex;
+use futures::StreamExt;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::config::{self, ConfigRemoteRepo, ConfigRepo, MANIFEST_FILE};
+use crate::fs::Simplified;
+use crate::git;
+use crate::hook::Project;
+use crate::printer::Printer;
+
+static REV_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(\s+)rev:(\s*)(['"]?)([^\s#'"]+)(['"]?)(.*?)(\r?\n)?$"#)
+        .expect("Invalid rev line regex")
+});
+
+static PRERELEASE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\d[.\-_]?(alpha|beta|rc|pre|preview|dev|a|b)[.\-_]?\d*$")
+        .expect("Invalid prerelease regex")
+});
+
+/// The revision a repo is updated to.
+struct Revision {
+    rev: String,
+    /// The tag the `rev` sha was resolved from, if frozen.
+    frozen: Option<String>,
+}
+
+pub(crate) async fn autoupdate(
+    config: Option<PathBuf>,
+    bleeding_edge: bool,
+    freeze: bool,
+    repos: Vec<String>,
+    jobs: usize,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let project = Project::from_config_file(config)?;
+    let config_file = project.config_file().to_path_buf();
+
+    let remotes: Vec<_> = project
+        .config()
+        .repos
+        .iter()
+        .filter_map(|repo| match repo {
+            ConfigRepo::Remote(repo) => Some(repo),
+            _ => None,
+        })
+        .collect();
+
+    let jobs = if jobs == 0 {
+        std::thread::available_parallelism().map_or(1, std::num::NonZero::get)
+    } else {
+        jobs
+    };
+
+    let mut updates = futures::stream::iter(remotes.iter().map(|&repo| {
+        let selected = repos.is_empty() || repos.iter().any(|r| r == repo.repo.as_str());
+        async move {
+            if selected {
+                Some(update_repo(repo, bleeding_edge, freeze).await)
+            } else {
+                None
+            }
+        }
+    }))
+    .buffered(jobs);
+
+    let mut failed = false;
+    let mut revisions = Vec::with_capacity(remotes.len());
+    let mut remotes_iter = remotes.iter();
+    while let Some(update) = updates.next().await {
+        let repo = remotes_iter.next().expect("one update per repo");
+        let Some(update) = update else {
+            revisions.push(None);
+            continue;
+        };
+
+        let prefix = format!("[{}]", git::mask_url(repo.repo.as_str()));
+        match update {
+            Ok(revision) if revision.rev == repo.rev => {
+                writeln!(printer.stdout(), "{} already up to date!", prefix.cyan())?;
+                revisions.push(None);
+            }
+            Ok(revision) => {
+                let target = match &revision.frozen {
+                    Some(tag) => format!("{tag} (frozen)"),
+                    None => revision.rev.clone(),
+                };
+                writeln!(
+                    printer.stdout(),
+                    "{} updating {} -> {}",
+                    prefix.cyan(),
+                    repo.rev,
+                    target
+                )?;
+                revisions.push(Some(revision));
+            }
+            Err(err) => {
+                failed = true;
+                writeln!(printer.stdout(), "{} -> {err:#}", prefix.cyan())?;
+                revisions.push(None);
+            }
+        }
+    }
+
+    if revisions.iter().any(Option::is_some) {
+        write_new_config(&config_file, &revisions)?;
+    }
+
+    if failed {
+        Ok(ExitStatus::Failure)
+    } else {
+        Ok(ExitStatus::Success)
+    }
+}
+
+/// Find the revision to update the repo to, and check that the configured hooks still exist in it.
+async fn update_repo(
+    repo: &ConfigRemoteRepo,
+    bleeding_edge: bool,
+    freeze: bool,
+) -> Result<Revision> {
+    let temp = tempfile::tempdir()?;
+    let path = temp.path();
+
+    git::fetch_head_and_tags(repo.repo.as_str(), path).await?;
+
+    let rev = if bleeding_edge {
+        match git::describe_tag(path, "FETCH_HEAD", true).await? {
+            Some(tag) => tag,
+            None => git::rev_parse(path, "FETCH_HEAD").await?,
+        }
+    } else {
+        match latest_stable_tag(path).await? {
+            Some(tag) => tag,
+            None => git::rev_parse(path, "FETCH_HEAD").await?,
+        }
+    };
+
+    let revision = if freeze {
+        let sha = git::rev_parse(path, &rev).await?;
+        if sha == rev {
+            Revision { rev, frozen: None }
+        } else {
+            Revision {
+                rev: sha,
+                frozen: Some(rev),
+            }
+        }
+    } else {
+        Revision { rev, frozen: None }
+    };
+
+    check_hooks_exist(repo, path, &revision.rev).await?;
+
+    Ok(revision)
+}
+
+/// Find the latest tag reachable from `FETCH_HEAD` that is not a prerelease.
+async fn latest_stable_tag(path: &Path) -> Result<Option<String>> {
+    let mut rev = "FETCH_HEAD".to_string();
+    while let Some(tag) = git::describe_tag(path, &rev, false).await? {
+        let tags: Vec<_> = git::get_tags_points_at(path, &format!("{tag}^{{commit}}"))
+            .await?
+            .into_iter()
+            .filter(|t| !is_prerelease(t))
+            .collect();
+        // Prefer a tag that looks like a version, if several point at the same commit.
+        if let Some(tag) = tags.iter().find(|t| t.contains('.')).or(tags.first()) {
+            return Ok(Some(tag.clone()));
+        }
+        // Only prereleases point at this commit, look further back in history.
+        rev = format!("{tag}^");
+    }
+    Ok(None)
+}
+
+/// Whether a tag looks like a prerelease version, like `v2.0.0-rc.1`, `2.0.0b1` or `1.0.dev0`.
+fn is_prerelease(tag: &str) -> bool {
+    PRERELEASE_RE.is_match(tag).unwrap_or(false)
+}
+
+async fn check_hooks_exist(repo: &ConfigRemoteRepo, path: &Path, rev: &str) -> Result<()> {
+    git::checkout(path, rev).await?;
+
+    let manifest_dir = match &repo.path {
+        Some(subdir) => path.join(subdir),
+        None => path.to_path_buf(),
+    };
+    let manifest = config::read_manifest(&manifest_dir.join(MANIFEST_FILE))?;
+    let missing: Vec<_> = repo
+        .hooks
+        .iter()
+        .filter(|hook| !manifest.hooks.iter().any(|h| h.id == hook.id))
+        .map(|hook| hook.id.as_str())
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "Cannot update because the update target is missing these hooks: {}",
+            missing.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Rewrite the `rev` of updated repos in place, leaving the rest of the file untouched.
+fn write_new_config(path: &Path, revisions: &[Option<Revision>]) -> Result<()> {
+    let content = fs_err::read_to_string(path)?;
+    let mut lines: Vec<_> = content
+        .split_inclusive('\n')
+        .map(ToString::to_string)
+        .collect();
+
+    let rev_lines: Vec<_> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| REV_LINE_RE.is_match(line).unwrap_or(false))
+        .map(|(idx, _)| idx)
+        .collect();
+    if rev_lines.len() != revisions.len() {
+        anyhow::bail!(
+            "Cannot find the `rev` line of every remote repo in `{}`, please update it manually",
+            path.user_display()
+        );
+    }
+
+    for (idx, revision) in rev_lines.into_iter().zip(revisions) {
+        let Some(revision) = revision else {
+            continue;
+        };
+        let caps = REV_LINE_RE
+            .captures(&lines[idx])?
+            .context("`rev` line no longer matches")?;
+        let group = |i| caps.get(i).map_or("", |m| m.as_str());
+
+        let quote = match group(3) {
+            "" if needs_quote(&revision.rev) => "'",
+            quote => quote,
+        };
+        let comment = if let Some(tag) = &revision.frozen {
+            format!("  # frozen: {tag}")
+        } else if group(6).trim_start().starts_with("# frozen:") {
+            String::new()
+        } else {
+            group(6).to_string()
+        };
+
+        lines[idx] = format!(
+            "{}rev:{}{quote}{}{quote}{comment}{}",
+            group(1),
+            group(2),
+            revision.rev,
+            group(7)
+        );
+    }
+
+    fs_err::write(path, lines.concat())?;
+    Ok(())
+}
+
+/// Whether a bare scalar would not be read back as the same string, e.g. `1.0` or `true`.
+fn needs_quote(rev: &str) -> bool {
+    !matches!(
+        serde_yaml::from_str::<serde_yaml::Value>(rev),
+        Ok(serde_yaml::Value::String(s)) if s == rev
+    )
+}
@@ -0,0 +1,145 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+
+use crate::cli::run::install_hooks;
+use crate::cli::{ExitStatus, PythonInstaller};
+use crate::fs::Simplified;
+use crate::git;
+use crate::hook::Project;
+use crate::languages::InstallOptions;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Verify the cached repos and the environments of the hooks in the config, and optionally
+/// repair the corrupt ones by preparing them again.
+pub(crate) async fn cache_verify(
+    config: Option<PathBuf>,
+    repair: bool,
+    python_installer: Option<PythonInstaller>,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    if !store.path().exists() {
+        writeln!(printer.stdout(), "Nothing to verify")?;
+        return Ok(ExitStatus::Success);
+    }
+    let store = store.init()?;
+    let _lock = store.lock_async().await?;
+
+    let mut corrupt = 0;
+
+    let entries = store.entries()?;
+    for (repo, rev, path) in &entries {
+        let path = Path::new(path);
+        let Err(reason) = verify_repo(repo, rev, path).await else {
+            continue;
+        };
+        corrupt += 1;
+        writeln!(
+            printer.stdout(),
+            "{} repo `{}` at `{}`: {reason}",
+            "Corrupt".red().bold(),
+            format!("{repo}@{rev}").cyan(),
+            path.user_display(),
+        )?;
+        if repair {
+            store.remove_entry(repo, rev, path)?;
+        }
+    }
+
+    // Environments are only known through the hooks, so check the ones of the current config.
+    let mut environments = 0;
+    if let Ok(config_file) = Project::find_config_file(config) {
+        let mut project = Project::new(config_file)?;
+        let hooks = match project.init_hooks(&store, printer).await {
+            Ok(hooks) => hooks,
+            // A corrupt repo may fail to load, it's already reported above.
+            Err(err) if corrupt > 0 => {
+                writeln!(
+                    printer.stdout(),
+                    "Skipped verifying environments, failed to load the hooks: {err}"
+                )?;
+                vec![]
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for hook in hooks
+            .iter()
+            .filter(|hook| hook.environment_dir().is_some() && hook.installed())
+            .unique_by(|hook| hook.install_key())
+        {
+            environments += 1;
+            let Err(err) = hook.language.check_health(hook).await else {
+                continue;
+            };
+            let env_dir = hook
+                .environment_dir()
+                .expect("environment dir should be set");
+            corrupt += 1;
+            writeln!(
+                printer.stdout(),
+                "{} environment of hook `{}` at `{}`: {err}",
+                "Corrupt".red().bold(),
+                hook.id.cyan(),
+                env_dir.user_display(),
+            )?;
+            if repair {
+                fs_err::remove_dir_all(&env_dir)?;
+            }
+        }
+
+        if repair && corrupt > 0 {
+            install_hooks(&hooks, InstallOptions { python_installer }, None, printer).await?;
+        }
+    }
+
+    if corrupt == 0 {
+        writeln!(
+            printer.stdout(),
+            "Verified {} repos and {environments} environments, no corrupt entries found",
+            entries.len(),
+        )?;
+        Ok(ExitStatus::Success)
+    } else if repair {
+        writeln!(printer.stdout(), "Repaired {corrupt} corrupt entries")?;
+        Ok(ExitStatus::Success)
+    } else {
+        writeln!(
+            printer.stdout(),
+            "Found {corrupt} corrupt entries, run with `{}` to repair them",
+            "--repair".cyan()
+        )?;
+        Ok(ExitStatus::Failure)
+    }
+}
+
+/// Check that a cached repo exists, and that a cloned repo is a valid git repo checked out at `rev`.
+async fn verify_repo(repo: &str, rev: &str, path: &Path) -> Result<(), String> {
+    if !path.is_dir() {
+        return Err("the directory is missing".to_string());
+    }
+    // Local repos are placeholder directories, not clones.
+    if repo == "local" || repo.starts_with("local:") {
+        return Ok(());
+    }
+    if !path.join(".git").exists() {
+        return Err("not a git repository".to_string());
+    }
+
+    let head = git::rev_parse(path, "HEAD")
+        .await
+        .map_err(|err| format!("failed to resolve `HEAD` ({err})"))?;
+    // Only the commit of `rev` is fetched, so branch or tag names may not resolve in the clone.
+    if let Ok(expected) = git::rev_parse(path, rev).await {
+        if head != expected {
+            return Err(format!("checked out at `{head}` instead of `{rev}`"));
+        }
+    }
+
+    Ok(())
+}
@@ -7,8 +7,9 @@ use crate::cli::ExitStatus;
 use crate::fs::Simplified;
 use crate::printer::Printer;
 use crate::store::Store;
+use crate::warn_user;
 
-pub(crate) fn clean(printer: Printer) -> Result<ExitStatus> {
+pub(crate) async fn clean(printer: Printer) -> Result<ExitStatus> {
     let store = Store::from_settings()?;
 
     if !store.path().exists() {
@@ -16,6 +17,36 @@ pub(crate) fn clean(printer: Printer) -> Result<ExitStatus> {
         return Ok(ExitStatus::Success);
     }
 
+    // Wait for other processes using the store, then remove everything but the lock file.
+    let lock = store.lock_async().await?;
+    let mut failed = 0;
+    for entry in fs_err::read_dir(store.path())? {
+        let entry = entry?;
+        if entry.file_name() == ".lock" {
+            continue;
+        }
+        let path = entry.path();
+        let result = if entry.file_type()?.is_dir() {
+            fs_err::remove_dir_all(&path)
+        } else {
+            fs_err::remove_file(&path)
+        };
+        if let Err(err) = result {
+            failed += 1;
+            warn_user!("{err}");
+        }
+    }
+    drop(lock);
+
+    if failed > 0 {
+        writeln!(
+            printer.stdout(),
+            "Partially cleaned `{}`, failed to remove {failed} entries",
+            store.path().user_display().cyan()
+        )?;
+        return Ok(ExitStatus::Failure);
+    }
+
     fs_err::remove_dir_all(store.path())?;
     writeln!(
         printer.stdout(),
@@ -0,0 +1,228 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+
+use crate::cli::run::{config_not_staged, install_hooks};
+use crate::cli::{plural, ExitStatus, PythonInstaller};
+use crate::fs::Simplified;
+use crate::git;
+use crate::hook::{CachedHook, Project};
+use crate::languages::InstallOptions;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Diagnose the store, the config and the hook environments, and optionally fix the problems
+/// that are safe to fix.
+pub(crate) async fn doctor(
+    config: Option<PathBuf>,
+    fix: bool,
+    yes: bool,
+    python_installer: Option<PythonInstaller>,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut problems = 0;
+    let mut fixed = 0;
+
+    // The store.
+    let store = Store::from_settings()?;
+    let store_display = store.path().user_display().to_string();
+    let mut store_ready = false;
+    if !store.path().exists() {
+        problems += 1;
+        report_problem(
+            printer,
+            &format!("the store `{store_display}` does not exist"),
+        )?;
+        if fix && confirm("Create the store?", yes, printer)? {
+            fs_err::create_dir_all(store.path())?;
+            report_fixed(printer, &format!("created the store `{store_display}`"))?;
+            fixed += 1;
+            store_ready = true;
+        }
+    } else if let Err(err) = tempfile::tempfile_in(store.path()) {
+        problems += 1;
+        report_problem(
+            printer,
+            &format!("the store `{store_display}` is not writable ({err})"),
+        )?;
+    } else {
+        report_ok(printer, &format!("store `{store_display}`"))?;
+        store_ready = true;
+    }
+
+    // The config.
+    let mut project = None;
+    match Project::find_config_file(config) {
+        Err(err) => {
+            problems += 1;
+            report_problem(
+                printer,
+                &format!("{err}, run `{}` to create one", "sample-config".cyan()),
+            )?;
+        }
+        Ok(config_file) => {
+            let config_display = config_file.user_display().to_string();
+            match Project::new(config_file.clone()) {
+                Err(err) => {
+                    problems += 1;
+                    report_problem(printer, &format!("the config is invalid: {err}"))?;
+                }
+                Ok(loaded) => {
+                    project = Some(loaded);
+                    if config_not_staged(&config_file).await? {
+                        problems += 1;
+                        report_problem(
+                            printer,
+                            &format!("the config `{config_display}` has unstaged changes"),
+                        )?;
+                        if fix && confirm(&format!("Stage `{config_display}`?"), yes, printer)? {
+                            git::git_cmd("git add")?
+                                .arg("add")
+                                .arg("--")
+                                .arg(&config_file)
+                                .check(true)
+                                .output()
+                                .await?;
+                            report_fixed(printer, &format!("staged `{config_display}`"))?;
+                            fixed += 1;
+                        }
+                    } else {
+                        report_ok(printer, &format!("config `{config_display}`"))?;
+                    }
+                }
+            }
+        }
+    }
+
+    // The environments of the hooks, only known through a loaded config.
+    if let (Some(project), true) = (project, store_ready) {
+        let store = store.init()?;
+        // Only reinstalling the environments changes the store.
+        let _lock = if fix {
+            Some(store.lock_async().await?)
+        } else {
+            None
+        };
+
+        // The hooks of the repos not in the store yet have no installed environments to check.
+        match project.cached_hooks(&store) {
+            Err(err) => {
+                problems += 1;
+                report_problem(printer, &format!("failed to load the hooks: {err}"))?;
+            }
+            Ok(hooks) => {
+                let mut environments = 0;
+                let mut unhealthy = 0;
+                let mut to_reinstall = Vec::new();
+                for hook in hooks
+                    .iter()
+                    .filter_map(|hook| match hook {
+                        CachedHook::Resolved(hook) => Some(hook.as_ref()),
+                        CachedHook::Unprepared(_) | CachedHook::Unresolved { .. } => None,
+                    })
+                    .filter(|hook| hook.environment_dir().is_some() && hook.installed())
+                    .unique_by(|hook| hook.install_key())
+                {
+                    environments += 1;
+                    let Err(err) = hook.language.check_health(hook).await else {
+                        continue;
+                    };
+                    unhealthy += 1;
+                    report_problem(
+                        printer,
+                        &format!("the environment of hook `{}` is broken: {err}", hook.id),
+                    )?;
+                    if fix
+                        && confirm(
+                            &format!("Reinstall the environment of hook `{}`?", hook.id),
+                            yes,
+                            printer,
+                        )?
+                    {
+                        let env_dir = hook
+                            .environment_dir()
+                            .expect("environment dir should be set");
+                        fs_err::remove_dir_all(&env_dir)?;
+                        to_reinstall.push(hook.clone());
+                    }
+                }
+                problems += unhealthy;
+
+                if unhealthy == 0 {
+                    report_ok(printer, &plural(environments, "installed environment"))?;
+                }
+                if !to_reinstall.is_empty() {
+                    install_hooks(
+                        &to_reinstall,
+                        InstallOptions { python_installer },
+                        None,
+                        printer,
+                    )
+                    .await?;
+                    for hook in &to_reinstall {
+                        report_fixed(
+                            printer,
+                            &format!("reinstalled the environment of hook `{}`", hook.id),
+                        )?;
+                    }
+                    fixed += to_reinstall.len();
+                }
+            }
+        }
+    }
+
+    if problems == 0 {
+        writeln!(printer.stdout(), "No problems found")?;
+        Ok(ExitStatus::Success)
+    } else if fixed == problems {
+        writeln!(printer.stdout(), "Fixed {}", plural(fixed, "problem"))?;
+        Ok(ExitStatus::Success)
+    } else if fix {
+        writeln!(
+            printer.stdout(),
+            "Fixed {fixed} of {}, the others need to be fixed manually",
+            plural(problems, "problem")
+        )?;
+        Ok(ExitStatus::Failure)
+    } else {
+        writeln!(
+            printer.stdout(),
+            "Found {}, run with `{}` to fix the safe ones",
+            plural(problems, "problem"),
+            "--fix".cyan()
+        )?;
+        Ok(ExitStatus::Failure)
+    }
+}
+
+fn report_ok(printer: Printer, message: &str) -> Result<()> {
+    writeln!(printer.stdout(), "{} {message}", "Ok".green().bold())?;
+    Ok(())
+}
+
+fn report_problem(printer: Printer, message: &str) -> Result<()> {
+    writeln!(printer.stdout(), "{} {message}", "Problem".red().bold())?;
+    Ok(())
+}
+
+fn report_fixed(printer: Printer, message: &str) -> Result<()> {
+    writeln!(printer.stdout(), "{} {message}", "Fixed".green().bold())?;
+    Ok(())
+}
+
+/// Ask the user to confirm a fix on stdin, unless `yes` is set.
+fn confirm(prompt: &str, yes: bool, printer: Printer) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    write!(printer.stderr(), "{prompt} [y/N] ")?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
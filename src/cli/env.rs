@@ -0,0 +1,103 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::cli::ExitStatus;
+use crate::fs::Simplified;
+use crate::hook::{Hook, Project};
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Show the resolved environment of a hook, without running it.
+pub(crate) async fn env_info(
+    config: Option<PathBuf>,
+    hook_id: String,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    let _lock = store.lock_async().await?;
+    let hooks = project.init_hooks(&store, printer).await?;
+
+    let hooks: Vec<_> = hooks
+        .into_iter()
+        .filter(|h| h.id == hook_id || h.alias == hook_id)
+        .collect();
+    if hooks.is_empty() {
+        writeln!(
+            printer.stderr(),
+            "No hook found for id `{}`",
+            hook_id.cyan()
+        )?;
+        return Ok(ExitStatus::Failure);
+    }
+
+    for (i, hook) in hooks.iter().enumerate() {
+        if i > 0 {
+            writeln!(printer.stdout())?;
+        }
+        write_info(hook, printer).await?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+async fn write_info(hook: &Hook, printer: Printer) -> Result<()> {
+    let mut stdout = printer.stdout();
+
+    writeln!(stdout, "{}: {}", "id".bold(), hook.id)?;
+    writeln!(stdout, "{}: {}", "repo".bold(), hook.repo())?;
+    writeln!(stdout, "{}: {}", "language".bold(), hook.language)?;
+    writeln!(
+        stdout,
+        "{}: {}",
+        "language_version".bold(),
+        hook.language_version
+    )?;
+
+    let Some(env_dir) = hook.environment_dir() else {
+        writeln!(stdout, "{}: none", "environment".bold())?;
+        return Ok(());
+    };
+    writeln!(
+        stdout,
+        "{}: {}",
+        "environment".bold(),
+        env_dir.user_display()
+    )?;
+
+    if hook.additional_dependencies.is_empty() {
+        writeln!(stdout, "{}: none", "additional_dependencies".bold())?;
+    } else {
+        writeln!(stdout, "{}:", "additional_dependencies".bold())?;
+        for dep in &hook.additional_dependencies {
+            writeln!(stdout, "  - {dep}")?;
+        }
+    }
+
+    if !hook.installed() {
+        writeln!(stdout, "{}: {}", "installed".bold(), "no".yellow())?;
+        return Ok(());
+    }
+    writeln!(stdout, "{}: {}", "installed".bold(), "yes".green())?;
+
+    let installed = hook.language.installed_dependencies(&env_dir)?;
+    if installed.is_empty() {
+        writeln!(stdout, "{}: none", "installed_dependencies".bold())?;
+    } else {
+        writeln!(stdout, "{}:", "installed_dependencies".bold())?;
+        for dep in &installed {
+            writeln!(stdout, "  - {dep}")?;
+        }
+    }
+
+    match hook.language.check_health(hook).await {
+        Ok(()) => writeln!(stdout, "{}: {}", "health".bold(), "ok".green())?,
+        Err(err) => writeln!(stdout, "{}: {} ({err})", "health".bold(), "unhealthy".red())?,
+    }
+
+    Ok(())
+}
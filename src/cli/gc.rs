@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use tracing::debug;
+
+use crate::cli::ExitStatus;
+use crate::config::{read_config, ConfigRepo};
+use crate::fs::Simplified;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Remove the cached repos, with the environments installed in them, that are not used by any
+/// config file recorded in the store.
+pub(crate) async fn gc(printer: Printer) -> Result<ExitStatus> {
+    let store = Store::from_settings()?;
+    if !store.path().exists() {
+        writeln!(printer.stdout(), "Nothing to clean")?;
+        return Ok(ExitStatus::Success);
+    }
+    let store = store.init()?;
+    // Repos are cloned and environments installed while holding the store lock, so nothing
+    // in progress is removed.
+    let _lock = store.lock_async().await?;
+
+    // The `(repo, rev)` of the remote repos, and whether any local repo is used.
+    let mut used = HashSet::new();
+    let mut local_used = false;
+    for config_path in store.configs()? {
+        if !config_path.try_exists()? {
+            debug!(path = %config_path.display(), "Forgetting removed config");
+            store.remove_config(&config_path)?;
+            continue;
+        }
+        let config = match read_config(&config_path) {
+            Ok(config) => config,
+            Err(err) => {
+                debug!(path = %config_path.display(), "Skipping invalid config: {err}");
+                continue;
+            }
+        };
+        for repo in config.repos {
+            match repo {
+                ConfigRepo::Remote(repo) => {
+                    used.insert((repo.repo.to_string(), repo.rev));
+                }
+                ConfigRepo::Local(_) => local_used = true,
+                ConfigRepo::Meta(_) => {}
+            }
+        }
+    }
+
+    let mut removed = 0;
+    let mut reclaimed = 0;
+
+    let entries = store.entries()?;
+    for (repo, rev, path) in &entries {
+        // Repos with additional dependencies are named like `repo:dep1,dep2`.
+        let is_used = if repo == "local" || repo.starts_with("local:") {
+            local_used
+        } else {
+            used.iter().any(|(url, used_rev)| {
+                used_rev == rev
+                    && (repo == url
+                        || repo
+                            .strip_prefix(url.as_str())
+                            .is_some_and(|deps| deps.starts_with(':')))
+            })
+        };
+        if is_used {
+            continue;
+        }
+
+        let path = Path::new(path);
+        debug!(repo, rev, path = %path.user_display(), "Removing unused repo");
+        reclaimed += dir_size(path);
+        store.remove_entry(repo, rev, path)?;
+        removed += 1;
+    }
+
+    // Directories left behind by an interrupted clone are not in the database.
+    let known: HashSet<_> = entries.iter().map(|(_, _, path)| Path::new(path)).collect();
+    for entry in fs_err::read_dir(store.path())? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir()
+            && entry.file_name().to_string_lossy().starts_with("repo")
+            && !known.contains(path.as_path())
+        {
+            debug!(path = %path.user_display(), "Removing untracked repo");
+            reclaimed += dir_size(&path);
+            fs_err::remove_dir_all(&path)?;
+            removed += 1;
+        }
+    }
+
+    writeln!(
+        printer.stdout(),
+        "Removed {} unused repos, reclaimed {}",
+        removed.cyan(),
+        human_size(reclaimed).cyan()
+    )?;
+
+    Ok(ExitStatus::Success)
+}
+
+/// The total size of the files in a directory, not following symlinks.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs_err::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.path().symlink_metadata().ok()?;
+            Some(if metadata.is_dir() {
+                dir_size(&entry.path())
+            } else {
+                metadata.len()
+            })
+        })
+        .sum()
+}
+
+/// Format a size in bytes like `1.5 MiB`.
+#[allow(clippy::cast_precision_loss)]
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{size:.1} {unit}")
+}
@@ -1,10 +1,14 @@
 use anyhow::Result;
 use std::ffi::OsString;
+use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::cli::{self, ExitStatus, RunArgs};
+use crate::cli::{self, run::resolve_push_refs, ExitStatus, PythonInstaller, RunArgs, RunOptions};
 use crate::config::HookType;
+use crate::git;
 use crate::printer::Printer;
+use crate::store::Store;
 use anstream::eprintln;
 
 pub(crate) async fn hook_impl(
@@ -13,6 +17,7 @@ pub(crate) async fn hook_impl(
     _hook_dir: PathBuf,
     skip_on_missing_config: bool,
     args: Vec<OsString>,
+    python_installer: Option<PythonInstaller>,
     printer: Printer,
 ) -> Result<ExitStatus> {
     // TODO: run in legacy mode
@@ -40,22 +45,67 @@ pub(crate) async fn hook_impl(
         return Ok(ExitStatus::Failure);
     }
 
-    let run_args = to_run_args(hook_type, &args);
+    if let Some(reason) = std::env::var_os("PREFLIGIT_ALLOW_SKIP") {
+        return record_bypass(hook_type, &reason.to_string_lossy()).await;
+    }
+
+    let mut run_args = to_run_args(hook_type, &args);
+
+    if matches!(hook_type, HookType::PrePush) {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        let remote_name = run_args.extra.remote_name.as_deref().unwrap_or("origin");
+        let Some(push) = resolve_push_refs(&input, remote_name).await? else {
+            return Ok(ExitStatus::Success);
+        };
+        run_args.from_ref = push.from_ref;
+        run_args.to_ref = push.to_ref;
+        run_args.all_files = push.all_files;
+        run_args.extra.local_branch = Some(push.local_branch);
+        run_args.extra.remote_branch = Some(push.remote_branch);
+    }
 
-    cli::run(
+    let options = RunOptions {
         config,
-        run_args.hook_id,
-        Some(hook_type.into()),
-        run_args.from_ref,
-        run_args.to_ref,
-        run_args.all_files,
-        vec![],
-        false,
-        run_args.extra,
-        false,
-        printer,
-    )
-    .await
+        hook_id: run_args.hook_id,
+        hook_stage: Some(hook_type.into()),
+        from_ref: run_args.from_ref,
+        to_ref: run_args.to_ref,
+        all_files: run_args.all_files,
+        python_installer,
+        extra_args: run_args.extra,
+        ..Default::default()
+    };
+    cli::run(options, printer).await
+}
+
+/// Skip the hooks, recording who skipped them, when and why in the store.
+async fn record_bypass(hook_type: HookType, reason: &str) -> Result<ExitStatus> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        eprintln!("`PREFLIGIT_ALLOW_SKIP` must be set to the reason for skipping the hooks");
+        return Ok(ExitStatus::Failure);
+    }
+
+    let who = git::get_user_ident()
+        .await
+        .unwrap_or_else(|_| "unknown".to_string());
+    let when = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let repo = git::get_root().await?;
+
+    let store = Store::from_settings()?.init()?;
+    let mut log = fs_err::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(store.bypass_log_path())?;
+    writeln!(
+        log,
+        "{when}\t{who}\t{}\t{hook_type}\t{reason}",
+        repo.display()
+    )?;
+
+    eprintln!("Skipping {hook_type} hooks: {reason}");
+    Ok(ExitStatus::Success)
 }
 
 fn to_run_args(hook_type: HookType, args: &[OsString]) -> RunArgs {
@@ -65,7 +115,6 @@ fn to_run_args(hook_type: HookType, args: &[OsString]) -> RunArgs {
         HookType::PrePush => {
             run_args.extra.remote_name = Some(args[0].to_string_lossy().into_owned());
             run_args.extra.remote_url = Some(args[1].to_string_lossy().into_owned());
-            // TODO: implement pre-push
         }
         HookType::CommitMsg => {
             run_args.extra.commit_msg_filename = Some(PathBuf::from(&args[0]));
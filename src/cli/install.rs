@@ -3,18 +3,22 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use clap::ValueEnum;
 use indoc::indoc;
 use owo_colors::OwoColorize;
 use same_file::is_same_file;
 
 use crate::cli::run;
-use crate::cli::{ExitStatus, HookType};
+use crate::cli::{ExitStatus, HookType, PythonInstaller};
+use crate::config::{ConfigRepo, ConfigWire, Stage};
 use crate::fs::Simplified;
 use crate::git;
 use crate::git::git_cmd;
 use crate::hook::Project;
+use crate::languages::InstallOptions;
 use crate::printer::Printer;
 use crate::store::Store;
+use crate::warn_user;
 
 pub(crate) async fn install(
     config: Option<PathBuf>,
@@ -22,6 +26,7 @@ pub(crate) async fn install(
     install_hooks: bool,
     overwrite: bool,
     allow_missing_config: bool,
+    python_installer: Option<PythonInstaller>,
     printer: Printer,
     git_dir: Option<&Path>,
 ) -> Result<ExitStatus> {
@@ -48,6 +53,9 @@ pub(crate) async fn install(
 
     let project = Project::from_config_file(config);
     let config_file = project.as_ref().ok().map(Project::config_file);
+    if let Ok(project) = &project {
+        check_hook_stages(project.config(), &hook_types);
+    }
     for hook_type in hook_types {
         install_hook_script(
             config_file,
@@ -65,7 +73,7 @@ pub(crate) async fn install(
         let _lock = store.lock_async().await?;
 
         let hooks = project.init_hooks(&store, printer).await?;
-        run::install_hooks(&hooks, printer).await?;
+        run::install_hooks(&hooks, InstallOptions { python_installer }, None, printer).await?;
     }
 
     Ok(ExitStatus::Success)
@@ -94,6 +102,63 @@ fn get_hook_types(config_file: Option<PathBuf>, hook_types: Vec<HookType>) -> Ve
     hook_types
 }
 
+/// Warn about installed hook types that no hook runs at, and hook stages that are not installed.
+///
+/// Only the stages set in the config are checked, a hook without `stages` may run at any stage.
+fn check_hook_stages(config: &ConfigWire, hook_types: &[HookType]) {
+    let stages: Vec<(&str, Option<&[Stage]>)> = config
+        .repos
+        .iter()
+        .flat_map(|repo| -> Vec<(&str, Option<&[Stage]>)> {
+            match repo {
+                ConfigRepo::Remote(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|hook| (hook.id.as_str(), hook.stages.as_deref()))
+                    .collect(),
+                ConfigRepo::Local(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|hook| (hook.id.as_str(), hook.stages.as_deref()))
+                    .collect(),
+                // Meta hooks may run at any stage.
+                ConfigRepo::Meta(_) => vec![("", None)],
+            }
+        })
+        .map(|(id, stages)| (id, stages.or(config.default_stages.as_deref())))
+        .collect();
+
+    for &hook_type in hook_types {
+        let stage = Stage::from(hook_type);
+        let used = stages
+            .iter()
+            .any(|(_, stages)| stages.map_or(true, |stages| stages.contains(&stage)));
+        if !used {
+            warn_user!(
+                "No hook in the config runs at the `{stage}` stage, the installed `{hook_type}` hook will do nothing"
+            );
+        }
+    }
+
+    for &hook_type in HookType::value_variants() {
+        if hook_types.contains(&hook_type) {
+            continue;
+        }
+        let stage = Stage::from(hook_type);
+        let hooks: Vec<_> = stages
+            .iter()
+            .filter(|(_, stages)| stages.is_some_and(|stages| stages.contains(&stage)))
+            .map(|(id, _)| format!("`{id}`"))
+            .collect();
+        if !hooks.is_empty() {
+            warn_user!(
+                "The `{hook_type}` hook is not installed, these hooks at the `{stage}` stage will not run: {}",
+                hooks.join(", ")
+            );
+        }
+    }
+}
+
 fn install_hook_script(
     config_file: Option<&Path>,
     hook_type: HookType,
@@ -203,8 +268,8 @@ pub(crate) async fn uninstall(
     hook_types: Vec<HookType>,
     printer: Printer,
 ) -> Result<ExitStatus> {
+    let hooks_path = git::get_git_common_dir().await?.join("hooks");
     for hook_type in get_hook_types(config, hook_types) {
-        let hooks_path = git::get_git_common_dir().await?.join("hooks");
         let hook_path = hooks_path.join(hook_type.as_str());
         let legacy_path = hooks_path.join(format!("{}.legacy", hook_type.as_str()));
 
@@ -255,6 +320,7 @@ pub(crate) async fn init_template_dir(
         false,
         true,
         !requires_config,
+        None,
         printer,
         Some(&directory),
     )
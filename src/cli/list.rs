@@ -0,0 +1,109 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::cli::{ExitStatus, ListFormat};
+use crate::hook::{CachedHook, Project};
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// A hook, as listed by `list --format json`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum ListedHook<'a> {
+    Resolved {
+        id: &'a str,
+        name: &'a str,
+        repo: String,
+        language: &'a str,
+        /// The `language_version` after the config defaults, like `default`, not the version
+        /// of an installed toolchain, which isn't known without installing the hook.
+        configured_language_version: &'a str,
+        stages: Vec<String>,
+        files: Option<&'a str>,
+        exclude: Vec<&'a str>,
+        files_glob: bool,
+        exclude_glob: bool,
+        /// The directory `files` and `exclude` are relative to, with `files_relative`.
+        files_prefix: Option<&'a str>,
+        types: &'a [String],
+        types_or: &'a [String],
+        exclude_types: &'a [String],
+    },
+    /// The hook of a remote repo that isn't cloned yet.
+    Unresolved { id: &'a str, repo: &'a str },
+}
+
+impl<'a> From<&'a CachedHook> for ListedHook<'a> {
+    fn from(hook: &'a CachedHook) -> Self {
+        match hook {
+            CachedHook::Resolved(hook) | CachedHook::Unprepared(hook) => Self::Resolved {
+                id: &hook.id,
+                name: &hook.name,
+                repo: hook.repo().to_string(),
+                language: hook.language.as_str(),
+                configured_language_version: &hook.language_version,
+                stages: hook.stages.iter().map(ToString::to_string).collect(),
+                files: hook.files.as_deref(),
+                exclude: hook
+                    .exclude
+                    .as_ref()
+                    .map(|exclude| exclude.patterns())
+                    .unwrap_or_default(),
+                files_glob: hook.files_glob,
+                exclude_glob: hook.exclude_glob,
+                files_prefix: hook.files_prefix.as_deref(),
+                types: &hook.types,
+                types_or: &hook.types_or,
+                exclude_types: &hook.exclude_types,
+            },
+            CachedHook::Unresolved { id, repo } => Self::Unresolved { id, repo },
+        }
+    }
+}
+
+/// List the hooks of the config, with their names and descriptions.
+pub(crate) async fn list(
+    config: Option<PathBuf>,
+    format: ListFormat,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let mut project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+
+    if format == ListFormat::Json {
+        let hooks = project.cached_hooks(&store)?;
+        let listed = hooks.iter().map(ListedHook::from).collect::<Vec<_>>();
+        writeln!(
+            printer.stdout(),
+            "{}",
+            serde_json::to_string_pretty(&listed)?
+        )?;
+        return Ok(ExitStatus::Success);
+    }
+
+    let _lock = store.lock_async().await?;
+    let hooks = project.init_hooks(&store, printer).await?;
+
+    let mut stdout = printer.stdout();
+    for hook in &hooks {
+        if hook.name == hook.id {
+            writeln!(stdout, "{}", hook.id.cyan())?;
+        } else {
+            writeln!(stdout, "{} ({})", hook.id.cyan(), hook.name)?;
+        }
+        if let Some(version) = hook.unsupported_version() {
+            writeln!(stdout, "  {}: {version}", "warning".yellow().bold())?;
+        }
+        if let Some(description) = hook.description.as_deref().map(str::trim) {
+            if !description.is_empty() {
+                writeln!(stdout, "  {}", description.dimmed())?;
+            }
+        }
+    }
+
+    Ok(ExitStatus::Success)
+}
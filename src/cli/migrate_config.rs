@@ -0,0 +1,142 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use fancy_regex::{Captures, Regex};
+
+use crate::cli::ExitStatus;
+use crate::fs::Simplified;
+use crate::hook::Project;
+use crate::printer::Printer;
+
+static SHA_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\n\s+)sha:").expect("Invalid sha regex"));
+
+/// A `stages` or `default_stages` key, with the rest of the line.
+static STAGES_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\s*(?:-\s+)?(?:default_)?stages:\s*)(.*?)(\r?\n)?$")
+        .expect("Invalid stages regex")
+});
+
+/// An item of a block list, with only a scalar.
+static ITEM_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(\s*-\s+)(['"]?)([\w-]+)(['"]?)(\s*(?:#.*)?)(\r?\n)?$"#)
+        .expect("Invalid item regex")
+});
+
+/// An item of a flow list, like `[commit, push]`.
+static FLOW_ITEM_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(['"]?)([\w-]+)(['"]?)(?=\s*[,\]])"#).expect("Invalid flow item regex")
+});
+
+/// Migrate the config file to the current schema, keeping comments and formatting.
+pub(crate) fn migrate_config(config: Option<PathBuf>, printer: Printer) -> Result<ExitStatus> {
+    let config_file = Project::find_config_file(config)?;
+    let original = fs_err::read_to_string(&config_file)?;
+    serde_yaml::from_str::<serde_yaml::Value>(&original)
+        .with_context(|| format!("Failed to parse `{}`", config_file.user_display()))?;
+
+    let mut contents = migrate_map(&original)?;
+    contents = SHA_RE.replace_all(&contents, "${1}rev:").into_owned();
+    contents = migrate_stages(&contents);
+
+    if contents == original {
+        writeln!(printer.stdout(), "Configuration is already migrated.")?;
+    } else {
+        fs_err::write(&config_file, &contents)?;
+        writeln!(printer.stdout(), "Configuration has been migrated.")?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+/// Wrap a config that is a bare list of repos under `repos:`.
+fn migrate_map(contents: &str) -> Result<String> {
+    let value: serde_yaml::Value = serde_yaml::from_str(contents)?;
+    if !value.is_sequence() {
+        return Ok(contents.to_string());
+    }
+
+    // Keep the leading comments and blank lines above `repos:`.
+    let header_len = contents
+        .split_inclusive('\n')
+        .take_while(|line| {
+            line.starts_with('#') || line.starts_with("---") || line.trim().is_empty()
+        })
+        .map(str::len)
+        .sum();
+    let (header, rest) = contents.split_at(header_len);
+
+    // A list in the default flow style is already valid under `repos:`, otherwise indent it.
+    let trial = format!("{header}repos:\n{rest}");
+    if serde_yaml::from_str::<serde_yaml::Value>(&trial).is_ok_and(|value| value.is_mapping()) {
+        return Ok(trial);
+    }
+    let indented = rest
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.trim_end_matches(['\r', '\n']).is_empty() {
+                line.to_string()
+            } else {
+                format!("    {line}")
+            }
+        })
+        .collect::<String>();
+    Ok(format!("{header}repos:\n{indented}"))
+}
+
+/// The current name of a deprecated stage.
+fn migrate_stage(stage: &str) -> &str {
+    match stage {
+        "commit" => "pre-commit",
+        "merge-commit" => "pre-merge-commit",
+        "push" => "pre-push",
+        stage => stage,
+    }
+}
+
+/// Rename the deprecated stages in `stages` and `default_stages`, in flow or block lists.
+fn migrate_stages(contents: &str) -> String {
+    let mut migrated = String::with_capacity(contents.len());
+    let mut in_block = false;
+    for line in contents.split_inclusive('\n') {
+        if in_block {
+            if ITEM_RE.is_match(line).unwrap_or(false) {
+                migrated.push_str(&ITEM_RE.replace(line, |caps: &Captures| {
+                    format!(
+                        "{}{}{}{}{}{}",
+                        &caps[1],
+                        &caps[2],
+                        migrate_stage(&caps[3]),
+                        &caps[4],
+                        &caps[5],
+                        caps.get(6).map_or("", |m| m.as_str())
+                    )
+                }));
+                continue;
+            }
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                in_block = false;
+            }
+        }
+
+        if let Ok(Some(caps)) = STAGES_RE.captures(line) {
+            let value = &caps[2];
+            if value.is_empty() || value.starts_with('#') {
+                in_block = true;
+            } else if value.starts_with('[') {
+                let value = FLOW_ITEM_RE.replace_all(value, |caps: &Captures| {
+                    format!("{}{}{}", &caps[1], migrate_stage(&caps[2]), &caps[3])
+                });
+                migrated.push_str(&caps[1]);
+                migrated.push_str(&value);
+                migrated.push_str(caps.get(3).map_or("", |m| m.as_str()));
+                continue;
+            }
+        }
+        migrated.push_str(line);
+    }
+    migrated
+}
@@ -1,4 +1,5 @@
 use std::ffi::OsString;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::process::ExitCode;
 
@@ -6,21 +7,40 @@ use clap::{ArgAction, Args, Parser, Subcommand};
 
 use crate::config::{HookType, Stage};
 
+mod autoupdate;
+mod cache;
 mod clean;
+mod doctor;
+mod env;
+mod gc;
 mod hook_impl;
 mod install;
+mod list;
+mod migrate_config;
+mod reporter;
 mod run;
 mod sample_config;
 mod self_update;
+mod try_repo;
 mod validate;
+mod version;
 
+pub(crate) use autoupdate::autoupdate;
+pub(crate) use cache::cache_verify;
 pub(crate) use clean::clean;
+pub(crate) use doctor::doctor;
+pub(crate) use env::env_info;
+pub(crate) use gc::gc;
 pub(crate) use hook_impl::hook_impl;
 pub(crate) use install::{init_template_dir, install, uninstall};
-pub(crate) use run::run;
+pub(crate) use list::list;
+pub(crate) use migrate_config::migrate_config;
+pub(crate) use run::{print_resolved_revs, run, RunOptions};
 pub(crate) use sample_config::sample_config;
 pub(crate) use self_update::self_update;
+pub(crate) use try_repo::try_repo;
 pub(crate) use validate::{validate_configs, validate_manifest};
+pub(crate) use version::version;
 
 #[derive(Copy, Clone)]
 pub(crate) enum ExitStatus {
@@ -52,6 +72,15 @@ impl From<ExitStatus> for ExitCode {
     }
 }
 
+/// `count` followed by `noun`, in plural unless `count` is 1.
+pub(crate) fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
 #[derive(Debug, Copy, Clone, clap::ValueEnum)]
 pub enum ColorChoice {
     /// Enables colored output only when the output is going to a terminal or TTY with support.
@@ -74,6 +103,32 @@ impl From<ColorChoice> for anstream::ColorChoice {
     }
 }
 
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output.
+    #[default]
+    Text,
+
+    /// Also emit GitHub Actions workflow annotations for failed hooks.
+    Github,
+
+    /// A JSON array of the results of the hooks, instead of the human-readable output.
+    Json,
+
+    /// Only the counts of the hook results and the failed hooks, set by `--summary-only`.
+    #[value(skip)]
+    Summary,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum PythonInstaller {
+    /// Create environments and install dependencies with `uv`.
+    Uv,
+
+    /// Create environments with `venv` and install dependencies with `pip`.
+    Pip,
+}
+
 #[derive(Parser)]
 #[command(
     name = "prefligit",
@@ -99,23 +154,39 @@ pub(crate) struct Cli {
     pub(crate) globals: GlobalArgs,
 }
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Parser)]
 #[command(next_help_heading = "Global options", next_display_order = 1000)]
 #[command(disable_help_flag = true, disable_version_flag = true)]
 pub(crate) struct GlobalArgs {
+    /// Run as if prefligit was started in the given directory, like `git -C`.
+    ///
+    /// Relative paths, including the config file and the files to run on, are resolved from it.
+    #[arg(global = true, short = 'C', long, value_name = "DIR")]
+    pub(crate) working_dir: Option<PathBuf>,
+
     /// Path to alternate config file.
     #[arg(global = true, short, long, value_parser)]
     pub(crate) config: Option<PathBuf>,
 
     /// Whether to use color in output.
-    #[arg(
-        global = true,
-        long,
-        value_enum,
-        env = "PRE_COMMIT_COLOR",
-        default_value_t = ColorChoice::Auto,
-    )]
-    pub(crate) color: ColorChoice,
+    ///
+    /// Takes precedence over the `NO_COLOR` and `FORCE_COLOR` environment variables.
+    /// Defaults to `auto`.
+    #[arg(global = true, long, value_enum, env = "PRE_COMMIT_COLOR")]
+    pub(crate) color: Option<ColorChoice>,
+
+    /// Disable colors in output.
+    ///
+    /// Equivalent to `--color never`, and takes precedence over `--color`.
+    #[arg(global = true, long)]
+    pub(crate) no_color: bool,
+
+    /// The installer for the environments of `python` hooks.
+    ///
+    /// Defaults to `uv`, falling back to `pip` when `uv` isn't available.
+    #[arg(global = true, long, value_enum, env = "PREFLIGIT_PYTHON_INSTALLER")]
+    pub(crate) python_installer: Option<PythonInstaller>,
 
     /// Display the concise help for this command.
     #[arg(global = true, short, long, action = clap::ArgAction::HelpShort)]
@@ -146,6 +217,28 @@ pub(crate) struct GlobalArgs {
     pub show_settings: bool,
 }
 
+impl GlobalArgs {
+    /// Resolve the color choice for output.
+    ///
+    /// `--color`, `--no-color` and `PRE_COMMIT_COLOR` take precedence over `NO_COLOR`, which takes
+    /// precedence over `FORCE_COLOR`. Otherwise, color is enabled only when writing to a terminal.
+    pub(crate) fn color_choice(&self) -> ColorChoice {
+        if self.no_color {
+            return ColorChoice::Never;
+        }
+        if let Some(color) = self.color {
+            return color;
+        }
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            ColorChoice::Never
+        } else if std::env::var_os("FORCE_COLOR").is_some_and(|v| !v.is_empty()) {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Auto
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub(crate) enum Command {
     /// Install the git prefligit hook.
@@ -162,19 +255,29 @@ pub(crate) enum Command {
     /// Validate `.pre-commit-hooks.yaml` files.
     ValidateManifest(ValidateManifestArgs),
     /// Produce a sample `.pre-commit-config.yaml` file.
-    SampleConfig,
+    SampleConfig(SampleConfigArgs),
+    /// Migrate a config file in a deprecated format to the current schema.
+    ///
+    /// A bare list of repos is moved under `repos:`, `sha` is renamed to `rev` and deprecated
+    /// stage names are renamed, keeping comments and ordering.
+    MigrateConfig,
     /// Auto-update pre-commit config to the latest repos' versions.
     #[command(name = "autoupdate")]
     AutoUpdate(AutoUpdateArgs),
     /// Clean unused cached repos.
+    ///
+    /// Repos are kept if they are used by a config file that prefligit has run with and that
+    /// still exists.
     GC,
     /// Clean out pre-commit files.
     Clean,
     /// Install hook script in a directory intended for use with `git config init.templateDir`.
     #[command(name = "init-templatedir")]
     InitTemplateDir(InitTemplateDirArgs),
-    /// Try the pre-commit hooks in the current repo.
-    TryRepo(Box<RunArgs>),
+    /// Try the hooks of a repo without adding it to the config.
+    ///
+    /// A local repo is tried with the state of its working tree, including uncommitted changes.
+    TryRepo(Box<TryRepoArgs>),
 
     /// The implementation of the `pre-commit` hook.
     #[command(hide = true)]
@@ -184,8 +287,30 @@ pub(crate) enum Command {
     #[command(name = "self")]
     Self_(SelfNamespace),
 
+    /// List the hooks of the config, with their descriptions.
+    List(ListArgs),
+
+    /// Inspect hook environments.
+    Env(EnvNamespace),
+
+    /// Manage the cached repos and environments.
+    Cache(CacheNamespace),
+
+    /// Diagnose the store, the config and the hook environments.
+    ///
+    /// With `--fix`, the safe fixes are applied after a confirmation: creating a missing store,
+    /// staging an unstaged config and reinstalling broken environments.
+    Doctor(DoctorArgs),
+
+    /// Display prefligit's version.
+    ///
+    /// With `--verbose`, also display the git commit, the build target and the store directory.
+    Version,
+
     /// Generate shell completion scripts.
-    #[command(hide = true)]
+    ///
+    /// For example, add `eval "$(prefligit completions bash)"` to `~/.bashrc`.
+    #[command(name = "completions", alias = "generate-shell-completion")]
     GenerateShellCompletion(GenerateShellCompletionArgs),
 }
 
@@ -239,9 +364,24 @@ pub(crate) struct RunExtraArgs {
     pub(crate) is_squash_merge: bool,
     #[arg(long, hide = true)]
     pub(crate) rewrite_command: Option<String>,
+    /// The directory the paths read by `--files -` are relative to, the original working
+    /// directory when the other paths are rebased onto the repo root.
+    #[arg(skip)]
+    pub(crate) files_dir: Option<PathBuf>,
+    /// A file containing the pre-push ref lines, in the format git passes on stdin to the
+    /// `pre-push` hook. The files to run on are computed from the pushed refs.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["all_files", "files", "from_ref", "to_ref"])]
+    pub(crate) push_refs_file: Option<PathBuf>,
+    /// Ask hooks to report problems without modifying files, e.g. in CI.
+    ///
+    /// This sets `PRE_COMMIT_CHECK_ONLY=1` for the hooks. It is a soft protocol: fixer hooks
+    /// that support a check-only mode should honor it, other hooks are unaffected.
+    #[arg(long)]
+    pub(crate) check_only: bool,
 }
 
 #[derive(Debug, Clone, Default, Args)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct RunArgs {
     /// The hook ID to run.
     #[arg(value_name = "HOOK")]
@@ -250,6 +390,9 @@ pub(crate) struct RunArgs {
     #[arg(short, long, conflicts_with_all = ["files", "from_ref", "to_ref"])]
     pub(crate) all_files: bool,
     /// Specific filenames to run hooks on.
+    ///
+    /// Globs like `src/**/*.py` are expanded against the files of the working tree, so they
+    /// work the same in every shell. With `-`, a newline-delimited list is read from stdin.
     #[arg(long, conflicts_with_all = ["all_files", "from_ref", "to_ref"])]
     pub(crate) files: Vec<PathBuf>,
     /// The original ref in a `from_ref...to_ref` diff expression.
@@ -261,11 +404,124 @@ pub(crate) struct RunArgs {
     #[arg(short = 'o', long, alias = "origin", requires = "from_ref")]
     pub(crate) to_ref: Option<String>,
     /// The stage during which the hook is fired.
+    ///
+    /// Defaults to `pre-commit`, so hooks of other stages like `manual` are skipped.
     #[arg(long)]
     pub(crate) hook_stage: Option<Stage>,
-    /// When hooks fail, run `git diff` directly afterward.
+    /// When hooks fail and some of them modified files, run `git diff` directly afterward.
     #[arg(long)]
     pub(crate) show_diff_on_failure: bool,
+    /// The number of context lines in the diff of `--show-diff-on-failure`.
+    #[arg(long, value_name = "N", requires = "show_diff_on_failure")]
+    pub(crate) diff_context: Option<u32>,
+    /// Whether to use color in the diff of `--show-diff-on-failure`.
+    /// Defaults to the `--color` of the output.
+    #[arg(long, value_name = "WHEN", requires = "show_diff_on_failure")]
+    pub(crate) diff_color: Option<ColorChoice>,
+    /// The format of the output.
+    ///
+    /// With `github`, failed hooks are also reported as GitHub Actions annotations,
+    /// with a location for each `file:line: message` diagnostic of the hook.
+    /// With `json`, the id, name, stage, status, matched files, exit code, duration,
+    /// whether files were modified and output of each hook are printed as a JSON array.
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) output_format: OutputFormat,
+    /// Only print the number of passed, failed and skipped hooks, and the ids of the failed hooks.
+    #[arg(long, conflicts_with = "output_format")]
+    pub(crate) summary_only: bool,
+    /// Show skipped hooks in the output (the default).
+    #[arg(long, overrides_with = "hide_skipped")]
+    pub(crate) show_skipped: bool,
+    /// Hide skipped hooks from the output.
+    #[arg(long, overrides_with = "show_skipped")]
+    pub(crate) hide_skipped: bool,
+    /// Fail the hooks that modify files, even if they exit 0 (the default).
+    #[arg(long, overrides_with = "no_fail_on_modified")]
+    pub(crate) fail_on_modified: bool,
+    /// Pass the hooks that modify files but otherwise exit 0, for flows that re-stage the fixes.
+    #[arg(long, overrides_with = "fail_on_modified")]
+    pub(crate) no_fail_on_modified: bool,
+    /// Only run the hooks that didn't pass in the last run.
+    /// All hooks are run if there is no previous run.
+    #[arg(long)]
+    pub(crate) retry_failed: bool,
+    /// Install the environments of all hooks before running any of them.
+    /// Installation failures exit with the internal error code instead of the hook failure code.
+    #[arg(long, alias = "install-hooks")]
+    pub(crate) install_hooks_first: bool,
+    /// Re-run the hooks that may modify files until a pass leaves the files untouched, for at
+    /// most this many passes. Useful when the output of one fixer triggers another.
+    ///
+    /// The run still fails if hooks modified files in any pass.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) fixer_passes: Option<u16>,
+    /// Let a failed `fail_fast` hook only stop the remaining hooks of its own repo,
+    /// the hooks of other repos keep running.
+    ///
+    /// Applies to both the top-level `fail_fast` and the `fail_fast` of each hook.
+    #[arg(long)]
+    pub(crate) keep_going_per_repo: bool,
+    /// Only run the hooks whose definition changed since the last commit of the config file.
+    ///
+    /// Useful when iterating on the config. A change to a top-level setting changes all hooks.
+    #[arg(long)]
+    pub(crate) only_changed_hooks: bool,
+    /// Run every selected hook, ignoring the results of previous runs and the incremental
+    /// selection of `--retry-failed` and `--only-changed-hooks`.
+    #[arg(long, alias = "no-cache")]
+    pub(crate) rerun: bool,
+    /// Run hooks outside a git repo, without the index, stashing or staging checks (experimental).
+    ///
+    /// Hooks run on the `--files`, with directories walked for files, or on all files under the
+    /// current directory. Files modified by hooks are detected by their content.
+    #[arg(
+        long,
+        conflicts_with_all = ["from_ref", "to_ref", "only_changed_hooks", "show_diff_on_failure", "push_refs_file"]
+    )]
+    pub(crate) experimental_no_git: bool,
+    /// Write a JSON profile of the time spent cloning repos, installing environments,
+    /// collecting files and running each hook to this path.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) profile: Option<PathBuf>,
+    /// Stop running hooks after this many hooks have failed.
+    ///
+    /// Unlike `fail_fast`, which stops at the first failure. Defaults to no limit.
+    #[arg(long, value_name = "N")]
+    pub(crate) bail_after: Option<NonZeroUsize>,
+    /// Stop running hooks as soon as a hook errors, e.g. its executable can't be spawned or its
+    /// environment is broken.
+    ///
+    /// Hooks that run and fail don't stop the run. Without it, errored hooks are reported like
+    /// failed hooks and the run continues.
+    #[arg(long)]
+    pub(crate) fail_fast_on_error: bool,
+    /// The maximum number of read-only hooks to run at once.
+    ///
+    /// Consecutive hooks marked `read_only` run concurrently, with their output printed in order.
+    /// `read_only` is a prefligit extension to the config and manifest, so it's opt-in: the other
+    /// hooks, which may modify files, run one at a time.
+    /// Defaults to the available parallelism, `1` runs all hooks one at a time.
+    #[arg(short, long, value_name = "N")]
+    pub(crate) jobs: Option<NonZeroUsize>,
+    /// The maximum number of containers to run at once for `docker` and `docker_image` hooks.
+    /// Other hooks are not affected.
+    #[arg(long, value_name = "N", env = "PREFLIGIT_DOCKER_JOBS")]
+    pub(crate) docker_jobs: Option<NonZeroUsize>,
+    /// Skip files larger than this size in bytes.
+    /// Overrides `max_file_size` in the config file.
+    #[arg(long, value_name = "BYTES")]
+    pub(crate) max_file_size: Option<u64>,
+    /// Explain how a hook would be selected and run, step by step, without running it.
+    ///
+    /// Shows the stage and `SKIP` checks, the files left after `files`, `exclude` and the types,
+    /// and the command the hook would run.
+    #[arg(long, value_name = "HOOK", conflicts_with = "hook_id")]
+    pub(crate) explain: Option<String>,
+    /// Print the `rev` of each remote repo and the commit it resolves to, without running hooks.
+    ///
+    /// Repos already in the cache are resolved offline, others are cloned first.
+    #[arg(long)]
+    pub(crate) print_resolved_revs: bool,
 
     #[command(flatten)]
     pub(crate) extra: RunExtraArgs,
@@ -273,7 +529,7 @@ pub(crate) struct RunArgs {
 
 #[derive(Debug, Args)]
 pub(crate) struct ValidateConfigArgs {
-    /// The path to the configuration file.
+    /// The path to the configuration file, or `-` to read from stdin.
     #[arg(value_name = "CONFIG")]
     pub(crate) configs: Vec<PathBuf>,
 }
@@ -285,14 +541,28 @@ pub(crate) struct ValidateManifestArgs {
     pub(crate) manifests: Vec<PathBuf>,
 }
 
+#[derive(Debug, Args)]
+pub(crate) struct SampleConfigArgs {
+    /// Write the sample config to this file instead of stdout.
+    /// An existing file is not overwritten.
+    #[arg(short, long, value_name = "PATH")]
+    pub(crate) file: Option<PathBuf>,
+}
+
 #[derive(Debug, Args)]
 pub(crate) struct AutoUpdateArgs {
-    #[arg(long, default_value_t = true)]
+    /// Update to the bleeding edge of the default branch instead of the latest tagged version.
+    ///
+    /// Without it, prerelease tags like `v2.0.0-rc.1` are skipped.
+    #[arg(long)]
     pub(crate) bleeding_edge: bool,
+    /// Store "frozen" hashes in `rev` instead of tag names.
     #[arg(long)]
     pub(crate) freeze: bool,
+    /// Only update this repository. May be specified multiple times.
     #[arg(long)]
-    pub(crate) repo: Option<String>,
+    pub(crate) repo: Vec<String>,
+    /// Number of threads to use, `0` to use all available cores.
     #[arg(short, long, default_value_t = 1)]
     pub(crate) jobs: usize,
 }
@@ -309,6 +579,78 @@ pub(crate) struct HookImplArgs {
     pub(crate) args: Vec<OsString>,
 }
 
+#[derive(Debug, Args)]
+pub(crate) struct EnvNamespace {
+    #[command(subcommand)]
+    pub(crate) command: EnvCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum EnvCommand {
+    /// Show the environment of a hook, without running it.
+    Info(EnvInfoArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct EnvInfoArgs {
+    /// The hook ID (or alias) to show.
+    #[arg(value_name = "HOOK")]
+    pub(crate) hook_id: String,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CacheNamespace {
+    #[command(subcommand)]
+    pub(crate) command: CacheCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum CacheCommand {
+    /// Check that the cached repos are valid clones at the expected revs, and that the
+    /// environments of the hooks in the config are healthy.
+    Verify(CacheVerifyArgs),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct CacheVerifyArgs {
+    /// Repair the corrupt entries, by cloning the repos and installing the environments again.
+    #[arg(long)]
+    pub(crate) repair: bool,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// The ids, names and descriptions of the hooks.
+    #[default]
+    Text,
+
+    /// A JSON array of the hooks, with their resolved filters.
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ListArgs {
+    /// The format of the output.
+    ///
+    /// With `json`, the id, name, repo, language, language version, stages and the resolved
+    /// `files`, `exclude`, `types`, `types_or` and `exclude_types` of each hook are printed.
+    /// Remote repos are not cloned, the hooks of the repos that aren't cached yet are
+    /// listed as unresolved.
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) format: ListFormat,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct DoctorArgs {
+    /// Fix the problems that are safe to fix.
+    #[arg(long)]
+    pub(crate) fix: bool,
+
+    /// Don't ask for confirmation before each fix.
+    #[arg(long, short, requires = "fix")]
+    pub(crate) yes: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct SelfNamespace {
     #[command(subcommand)]
@@ -333,6 +675,17 @@ pub struct SelfUpdateArgs {
     pub token: Option<String>,
 }
 
+#[derive(Debug, Args)]
+pub(crate) struct TryRepoArgs {
+    /// The repo to try, a URL or a local path.
+    pub(crate) repo: String,
+    /// The revision to try, defaults to `HEAD` of the repo.
+    #[arg(long, alias = "ref")]
+    pub(crate) rev: Option<String>,
+    #[command(flatten)]
+    pub(crate) run_args: RunArgs,
+}
+
 #[derive(Debug, Args)]
 pub(crate) struct GenerateShellCompletionArgs {
     /// The shell to generate the completion script for
@@ -0,0 +1,85 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use owo_colors::OwoColorize;
+
+use crate::hook::Hook;
+use crate::printer::{self, Printer};
+
+/// Reports the progress of installing hook environments.
+///
+/// On a terminal, each installation is shown as a spinner with the repo name, and the spinners
+/// collapse into a single summary line when done. Otherwise, a line is printed for each repo.
+pub(crate) struct HookInstallReporter {
+    printer: Printer,
+    progress: Option<MultiProgress>,
+}
+
+impl HookInstallReporter {
+    pub(crate) fn new(printer: Printer) -> Self {
+        let target = printer.target();
+        let progress = (!target.is_hidden()).then(|| MultiProgress::with_draw_target(target));
+        if let Some(progress) = &progress {
+            printer::set_progress(Some(progress.clone()));
+        }
+        Self { printer, progress }
+    }
+
+    /// Report that the environment of a hook started installing.
+    pub(crate) fn on_install_start(&self, hook: &Hook) -> anyhow::Result<Option<ProgressBar>> {
+        let Some(progress) = &self.progress else {
+            writeln!(
+                self.printer.stdout(),
+                "Installing environment for {}",
+                hook.repo(),
+            )?;
+            return Ok(None);
+        };
+
+        let spinner = progress.add(ProgressBar::new_spinner());
+        spinner.set_style(ProgressStyle::with_template("{spinner:.dim} {msg}")?);
+        spinner.set_message(format!("Installing environment for {}", hook.repo().cyan()));
+        spinner.enable_steady_tick(Duration::from_millis(100));
+        Ok(Some(spinner))
+    }
+
+    /// Report that the environment of a hook finished installing.
+    pub(crate) fn on_install_complete(&self, spinner: Option<ProgressBar>) {
+        if let (Some(progress), Some(spinner)) = (&self.progress, spinner) {
+            spinner.finish_and_clear();
+            progress.remove(&spinner);
+        }
+    }
+
+    /// Report that all environments are installed.
+    pub(crate) fn on_complete(&self, installed: usize, elapsed: Duration) -> anyhow::Result<()> {
+        let Some(progress) = &self.progress else {
+            return Ok(());
+        };
+        progress.clear()?;
+        printer::set_progress(None);
+
+        if installed > 0 {
+            writeln!(
+                self.printer.stderr(),
+                "Installed {} in {}",
+                format!(
+                    "{installed} environment{}",
+                    if installed == 1 { "" } else { "s" }
+                )
+                .bold(),
+                format!("{:.2}s", elapsed.as_secs_f64()).dimmed()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for HookInstallReporter {
+    fn drop(&mut self) {
+        if self.progress.is_some() {
+            printer::set_progress(None);
+        }
+    }
+}
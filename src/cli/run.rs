@@ -1,38 +1,98 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
+use globset::GlobBuilder;
 use itertools::Itertools;
 use owo_colors::OwoColorize;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use tracing::{debug, trace};
 
-use crate::cli::{ExitStatus, RunExtraArgs};
-use crate::config::Stage;
-use crate::fs::{normalize_path, Simplified};
+use crate::cli::reporter::HookInstallReporter;
+use crate::cli::{plural, ExitStatus, OutputFormat, PythonInstaller, RunExtraArgs};
+use crate::config::{ConfigRepo, Stage};
+use crate::fs::{self, normalize_path, Simplified, CWD};
 use crate::git;
 use crate::hook::{Hook, Project};
-use crate::printer::Printer;
-use crate::run::{run_hooks, FilenameFilter, WorkTreeKeeper};
+use crate::languages::InstallOptions;
+use crate::printer::{Mode, Printer};
+use crate::profile::{self, FilesTiming, InstallTiming, Profile};
+use crate::run::{
+    explain_hook, run_hooks, DiffOptions, FilenameFilter, HookRunOptions, WorkTreeKeeper,
+};
 use crate::store::Store;
+use crate::warn_user;
+
+/// The options of a run, from the `run` arguments or the git hook of a stage.
+#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct RunOptions<'a> {
+    pub(crate) config: Option<PathBuf>,
+    pub(crate) hook_id: Option<String>,
+    pub(crate) hook_stage: Option<Stage>,
+    pub(crate) from_ref: Option<String>,
+    pub(crate) to_ref: Option<String>,
+    pub(crate) all_files: bool,
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) no_git: bool,
+    pub(crate) show_diff_on_failure: Option<DiffOptions>,
+    pub(crate) output_format: OutputFormat,
+    pub(crate) hide_skipped: bool,
+    pub(crate) no_fail_on_modified: bool,
+    pub(crate) keep_going_per_repo: bool,
+    pub(crate) retry_failed: bool,
+    pub(crate) only_changed_hooks: bool,
+    pub(crate) install_hooks_first: bool,
+    pub(crate) fixer_passes: Option<u16>,
+    pub(crate) jobs: Option<NonZeroUsize>,
+    pub(crate) bail_after: Option<NonZeroUsize>,
+    pub(crate) fail_fast_on_error: bool,
+    pub(crate) docker_jobs: Option<NonZeroUsize>,
+    pub(crate) python_installer: Option<PythonInstaller>,
+    pub(crate) max_file_size: Option<u64>,
+    pub(crate) explain: Option<String>,
+    pub(crate) extra_args: RunExtraArgs,
+    pub(crate) profile: Option<&'a RefCell<Profile>>,
+    pub(crate) verbose: bool,
+}
+
+pub(crate) async fn run(options: RunOptions<'_>, printer: Printer) -> Result<ExitStatus> {
+    let RunOptions {
+        config,
+        hook_id,
+        hook_stage,
+        mut from_ref,
+        mut to_ref,
+        mut all_files,
+        files,
+        no_git,
+        show_diff_on_failure,
+        output_format,
+        hide_skipped,
+        no_fail_on_modified,
+        keep_going_per_repo,
+        retry_failed,
+        only_changed_hooks,
+        install_hooks_first,
+        fixer_passes,
+        jobs,
+        bail_after,
+        fail_fast_on_error,
+        docker_jobs,
+        python_installer,
+        max_file_size,
+        explain,
+        mut extra_args,
+        profile,
+        verbose,
+    } = options;
 
-#[allow(clippy::too_many_arguments)]
-pub(crate) async fn run(
-    config: Option<PathBuf>,
-    hook_id: Option<String>,
-    hook_stage: Option<Stage>,
-    from_ref: Option<String>,
-    to_ref: Option<String>,
-    all_files: bool,
-    files: Vec<PathBuf>,
-    show_diff_on_failure: bool,
-    extra_args: RunExtraArgs,
-    verbose: bool,
-    printer: Printer,
-) -> Result<ExitStatus> {
     // Prevent recursive post-checkout hooks.
     if matches!(hook_stage, Some(Stage::PostCheckout))
         && std::env::var_os("_PRE_COMMIT_SKIP_POST_CHECKOUT").is_some()
@@ -40,7 +100,41 @@ pub(crate) async fn run(
         return Ok(ExitStatus::Success);
     }
 
-    let should_stash = !all_files && files.is_empty();
+    if let Some(path) = extra_args.push_refs_file.take() {
+        let input = fs_err::read_to_string(&path)?;
+        let remote_name = extra_args.remote_name.as_deref().unwrap_or("origin");
+        let Some(push) = resolve_push_refs(&input, remote_name).await? else {
+            debug!("Nothing to push, skipping");
+            return Ok(ExitStatus::Success);
+        };
+        from_ref = push.from_ref;
+        to_ref = push.to_ref;
+        all_files = push.all_files;
+        extra_args.local_branch = Some(push.local_branch);
+        extra_args.remote_branch = Some(push.remote_branch);
+    }
+
+    if !all_files {
+        let missing = missing_stage_args(hook_stage, from_ref.is_some(), &extra_args);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "`--hook-stage {}` requires {}, or `--all-files`",
+                hook_stage.expect("stage args are only required for a stage"),
+                missing.join(", ")
+            );
+        }
+    }
+
+    // Pushing a new branch, the remote side is the all-zeros sha which can't be diffed against.
+    if let (Some(from), Some(to)) = (&from_ref, &to_ref) {
+        if git::is_zero_sha(from) && !no_git {
+            let remote_name = extra_args.remote_name.as_deref().unwrap_or("origin");
+            from_ref = Some(new_branch_base(to, remote_name).await?);
+        }
+    }
+
+    // Explaining a hook doesn't touch the working tree.
+    let should_stash = !all_files && files.is_empty() && !no_git && explain.is_none();
 
     // Check if we have unresolved merge conflict files and fail fast.
     if should_stash && git::has_unmerged_paths().await? {
@@ -64,14 +158,33 @@ pub(crate) async fn run(
     // Set env vars for hooks.
     let env_vars = fill_envs(from_ref.as_ref(), to_ref.as_ref(), &extra_args);
 
+    // The JSON report and the summary replace the human-readable output, including the
+    // progress of cloning repos and installing environments.
+    let install_printer = if matches!(output_format, OutputFormat::Json | OutputFormat::Summary) {
+        printer.with_mode(Mode::Quiet)
+    } else {
+        printer
+    };
+
     let mut project = Project::new(config_file)?;
     let store = Store::from_settings()?.init()?;
 
     let lock = store.lock_async().await?;
-    let hooks = project.init_hooks(&store, printer).await?;
+    let start = Instant::now();
+    let install_options = InstallOptions { python_installer };
+    let all_hooks = project.init_hooks(&store, install_printer).await?;
+    profile::record(profile, |profile| {
+        profile.clone = start.elapsed().as_secs_f64();
+    });
+
+    if install_hooks_first && explain.is_none() {
+        install_hooks(&all_hooks, install_options, profile, install_printer)
+            .await
+            .context("Failed to install hook environments")?;
+    }
 
-    let hooks: Vec<_> = hooks
-        .into_iter()
+    let mut hooks: Vec<_> = all_hooks
+        .iter()
         .filter(|h| {
             if let Some(ref hook) = hook_id {
                 &h.id == hook || &h.alias == hook
@@ -79,15 +192,16 @@ pub(crate) async fn run(
                 true
             }
         })
-        .filter(|h| {
-            if let Some(stage) = hook_stage {
-                h.stages.contains(&stage)
-            } else {
-                true
-            }
-        })
+        // Like in `pre-commit`, hooks of the `pre-commit` stage are run by default.
+        .filter(|h| h.stages.contains(&hook_stage.unwrap_or(Stage::PreCommit)))
+        .cloned()
         .collect();
 
+    // Fail before running any hook if one of them needs a newer pre-commit.
+    for hook in &hooks {
+        hook.check_version()?;
+    }
+
     if hooks.is_empty() && hook_id.is_some() {
         if let Some(hook_stage) = hook_stage {
             writeln!(
@@ -106,6 +220,23 @@ pub(crate) async fn run(
         return Ok(ExitStatus::Failure);
     }
 
+    // Only run the hooks that didn't pass in the last run.
+    if retry_failed {
+        let passed = store.passed_hooks(&CWD)?;
+        hooks.retain(|h| !passed.contains(&h.id));
+        debug!("Retrying {} hook(s) that didn't pass", hooks.len());
+    }
+
+    // Only run the hooks whose definition changed since the last commit.
+    if only_changed_hooks {
+        let changed = changed_hooks(project.config_file()).await?;
+        hooks.retain(|h| changed.contains(&h.idx()));
+        debug!(
+            "Running {} hook(s) changed since the last commit",
+            hooks.len()
+        );
+    }
+
     let skips = get_skips();
     let to_run = hooks
         .iter()
@@ -117,7 +248,20 @@ pub(crate) async fn run(
         "Hooks going to run: {:?}",
         to_run.iter().map(|h| &h.id).collect::<Vec<_>>()
     );
-    install_hooks(&to_run, printer).await?;
+    // The environments referenced by `use_env_of` need to be installed too.
+    let env_of: Vec<_> = to_run
+        .iter()
+        .filter_map(|h| h.use_env_of.as_deref())
+        .collect();
+    let to_install: Vec<_> = all_hooks
+        .iter()
+        .filter(|h| env_of.contains(&h.id.as_str()))
+        .chain(to_run.iter())
+        .cloned()
+        .collect();
+    if explain.is_none() {
+        install_hooks(&to_install, install_options, profile, install_printer).await?;
+    }
     drop(lock);
 
     // Clear any unstaged changes from the git working directory.
@@ -126,50 +270,128 @@ pub(crate) async fn run(
         _guard = Some(WorkTreeKeeper::clean(&store).await?);
     }
 
+    let start = Instant::now();
+    let explicit_files = !files.is_empty();
     let mut filenames = all_filenames(
+        no_git,
         hook_stage,
         from_ref,
         to_ref,
         all_files,
         files,
+        extra_args.files_dir.as_deref(),
         extra_args.commit_msg_filename.as_ref(),
     )
     .await?;
     for filename in &mut filenames {
         normalize_path(filename);
     }
+    // Pass each file once, in a stable order, so the hook output is reproducible.
+    filenames.sort_unstable();
+    filenames.dedup();
 
     let filter = FilenameFilter::new(
         project.config().files.as_deref(),
-        project.config().exclude.as_deref(),
-    )?;
+        project.config().exclude.as_ref(),
+        project.config().files_glob.unwrap_or(false),
+    )?
+    .with_prefix(
+        project
+            .config()
+            .files_prefix(project.config_file())
+            .as_deref(),
+    );
     let filenames = filenames
         .into_par_iter()
         .filter(|filename| filter.filter(filename))
-        .filter(|filename| {
-            // Ignore not existing files.
-            std::fs::symlink_metadata(filename)
-                .map(|m| m.file_type().is_file())
-                .unwrap_or(false)
-        })
+        // Ignore not existing files. Symlinks, directories and special files are kept,
+        // the `types` of each hook decide whether it wants them.
+        .filter(|filename| std::fs::symlink_metadata(filename).is_ok())
         .collect::<Vec<_>>();
 
+    let filenames = if let Some(max_file_size) = max_file_size.or(project.config().max_file_size) {
+        skip_large_files(filenames, max_file_size)
+    } else {
+        filenames
+    };
+
     trace!("Files after filtered: {}", filenames.len());
+    profile::record(profile, |profile| {
+        profile.files = FilesTiming {
+            count: filenames.len(),
+            duration: start.elapsed().as_secs_f64(),
+        };
+    });
+
+    if let Some(explain) = explain {
+        let Some(hook) = all_hooks
+            .iter()
+            .find(|h| h.id == explain || h.alias == explain)
+        else {
+            writeln!(
+                printer.stderr(),
+                "No hook found for id `{}`",
+                explain.cyan()
+            )?;
+            return Ok(ExitStatus::Failure);
+        };
+        let skipped = skips.contains(&hook.id) || skips.contains(&hook.alias);
+        explain_hook(
+            hook,
+            &filenames,
+            hook_stage.unwrap_or(Stage::PreCommit),
+            skipped,
+            printer,
+        )?;
+        return Ok(ExitStatus::Success);
+    }
 
-    run_hooks(
-        &hooks,
-        &skips,
-        filenames,
-        env_vars,
-        project.config().fail_fast.unwrap_or(false),
+    // `git diff` only sees the modifications of tracked files, the untracked files passed by
+    // `--files` are checked by their content.
+    let untracked_files = if explicit_files && !no_git {
+        let tracked = git::get_all_files()
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        filenames
+            .iter()
+            .filter(|filename| !tracked.contains(*filename))
+            .cloned()
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    let options = HookRunOptions {
+        project_hooks: &all_hooks,
+        stage: hook_stage.unwrap_or(Stage::PreCommit),
+        fail_fast: project.config().fail_fast.unwrap_or(false),
+        keep_going_per_repo,
         show_diff_on_failure,
+        output_format,
+        hide_skipped,
+        fail_on_modified: !no_fail_on_modified,
+        fixer_passes: fixer_passes.map(usize::from),
+        jobs: jobs.map_or_else(
+            || std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            NonZeroUsize::get,
+        ),
+        bail_after,
+        fail_fast_on_error,
+        no_git,
+        untracked_files: &untracked_files,
+        docker_jobs,
+        profile,
         verbose,
-        printer,
-    )
-    .await
+    };
+    let (status, results) =
+        run_hooks(&hooks, &skips, filenames, env_vars, options, printer).await?;
+    store.save_run_results(&CWD, &results)?;
+
+    Ok(status)
 }
 
-async fn config_not_staged(config: &Path) -> Result<bool> {
+pub(crate) async fn config_not_staged(config: &Path) -> Result<bool> {
     let status = git::git_cmd("git diff")?
         .arg("diff")
         .arg("--quiet") // Implies --exit-code
@@ -181,7 +403,8 @@ async fn config_not_staged(config: &Path) -> Result<bool> {
         .status()
         .await?;
 
-    Ok(!status.success())
+    // Other failures, like a config outside the repo, are not about staging.
+    Ok(status.code() == Some(1))
 }
 
 fn fill_envs(
@@ -233,10 +456,32 @@ fn fill_envs(
     if let Some(ref command) = args.rewrite_command {
         env.insert("PRE_COMMIT_REWRITE_COMMAND", command.clone());
     }
+    if args.check_only {
+        env.insert("PRE_COMMIT_CHECK_ONLY", "1".into());
+    }
 
     env
 }
 
+/// Exclude files larger than `max_file_size` bytes, and warn about them.
+fn skip_large_files(filenames: Vec<String>, max_file_size: u64) -> Vec<String> {
+    let (filenames, skipped): (Vec<_>, Vec<_>) = filenames.into_iter().partition(|filename| {
+        // Keep files we can't stat, the hooks will report them if they matter.
+        fs_err::metadata(filename).map_or(true, |m| m.len() <= max_file_size)
+    });
+
+    if !skipped.is_empty() {
+        warn_user!(
+            "Skipped {} larger than {} bytes:\n{}",
+            plural(skipped.len(), "file"),
+            max_file_size,
+            skipped.iter().map(|f| format!("  {f}")).join("\n")
+        );
+    }
+
+    filenames
+}
+
 fn get_skips() -> Vec<String> {
     match std::env::var_os("SKIP") {
         Some(s) if !s.is_empty() => s
@@ -252,13 +497,27 @@ fn get_skips() -> Vec<String> {
 /// Get all filenames to run hooks on.
 #[allow(clippy::too_many_arguments)]
 async fn all_filenames(
+    no_git: bool,
     hook_stage: Option<Stage>,
     from_ref: Option<String>,
     to_ref: Option<String>,
     all_files: bool,
     files: Vec<PathBuf>,
+    files_dir: Option<&Path>,
     commit_msg_filename: Option<&PathBuf>,
 ) -> Result<Vec<String>> {
+    let files = expand_files(files, files_dir)?;
+    if no_git {
+        let files = walk_files(files)?;
+        debug!("Files found without git: {}", files.len());
+        return Ok(files);
+    }
+    if hook_stage == Some(Stage::PostCommit) && files.is_empty() && !all_files {
+        // `post-commit` runs on the files of the commit just made.
+        let files = git::get_commit_files("HEAD").await?;
+        debug!("Files in the last commit: {}", files.len());
+        return Ok(files);
+    }
     if hook_stage.is_some_and(|stage| !stage.operate_on_files()) {
         return Ok(vec![]);
     }
@@ -302,12 +561,187 @@ async fn all_filenames(
     Ok(files)
 }
 
-async fn install_hook(hook: &Hook, env_dir: PathBuf, printer: Printer) -> Result<()> {
-    writeln!(
-        printer.stdout(),
-        "Installing environment for {}",
-        hook.repo(),
-    )?;
+/// Expand the `--files`: `-` reads a newline-delimited list from stdin, and a glob that isn't
+/// an existing path is matched against the files of the working tree, like a shell would.
+///
+/// The paths read from stdin are relative to `files_dir` if set, and rebased onto the
+/// current directory like the other `--files`.
+fn expand_files(files: Vec<PathBuf>, files_dir: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::with_capacity(files.len());
+    let mut all_files = None;
+    for file in files {
+        if file == Path::new("-") {
+            let list = std::io::read_to_string(std::io::stdin())
+                .context("Failed to read the file list from stdin")?;
+            let cwd = std::env::current_dir()?;
+            for line in list.lines().map(str::trim_end) {
+                if line.is_empty() {
+                    continue;
+                }
+                let path = match files_dir {
+                    Some(dir) => fs::relative_to(fs::clean_path(&dir.join(line)), &cwd)?,
+                    None => PathBuf::from(line),
+                };
+                expanded.push(path);
+            }
+            continue;
+        }
+
+        let pattern = file.to_string_lossy();
+        if !pattern.contains(['*', '?', '[']) || file.symlink_metadata().is_ok() {
+            expanded.push(file);
+            continue;
+        }
+        let pattern = pattern.strip_prefix("./").unwrap_or(&pattern);
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid glob pattern in `--files`: `{pattern}`"))?
+            .compile_matcher();
+        let all_files = match &mut all_files {
+            Some(all_files) => all_files,
+            None => all_files.insert(walk_files(vec![])?),
+        };
+        let before = expanded.len();
+        expanded.extend(
+            all_files
+                .iter()
+                .filter(|file| glob.is_match(file.as_str()))
+                .map(PathBuf::from),
+        );
+        if expanded.len() == before {
+            warn_user!("The pattern `{pattern}` in `--files` does not match any files");
+        }
+    }
+    Ok(expanded)
+}
+
+/// The arguments the git hook of `stage` passes that are missing, without which the files to run
+/// on can't be computed.
+fn missing_stage_args(
+    stage: Option<Stage>,
+    has_refs: bool,
+    extra_args: &RunExtraArgs,
+) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    match stage {
+        Some(Stage::PostCheckout) => {
+            if !has_refs {
+                missing.extend(["`--from-ref`", "`--to-ref`"]);
+            }
+            if extra_args.checkout_type.is_none() {
+                missing.push("`--checkout-type`");
+            }
+        }
+        Some(Stage::PostRewrite) if extra_args.rewrite_command.is_none() => {
+            missing.push("`--rewrite-command`");
+        }
+        _ => {}
+    }
+    missing
+}
+
+/// Walk `paths`, or the current directory if empty, for files. `.git` directories are skipped.
+fn walk_files(paths: Vec<PathBuf>) -> Result<Vec<String>> {
+    let mut stack = if paths.is_empty() {
+        vec![PathBuf::new()]
+    } else {
+        paths
+    };
+
+    let mut files = Vec::new();
+    while let Some(path) = stack.pop() {
+        let is_dir = path.as_os_str().is_empty()
+            || fs_err::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir());
+        if !is_dir {
+            files.push(path.to_string_lossy().to_string());
+            continue;
+        }
+
+        let dir = if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            path.as_path()
+        };
+        for entry in fs_err::read_dir(dir)? {
+            let name = entry?.file_name();
+            if name != ".git" {
+                stack.push(path.join(name));
+            }
+        }
+    }
+    files.sort_unstable();
+
+    Ok(files)
+}
+
+/// Get the hooks whose definition changed from the config file of the last commit,
+/// as the index of the repo in the config and of the hook in the repo, see [`Hook::idx`].
+///
+/// All hooks are changed if the config file isn't committed, or if a top-level setting,
+/// which applies to all hooks, changed.
+async fn changed_hooks(config_file: &Path) -> Result<HashSet<(usize, usize)>> {
+    let current: serde_yaml::Value = serde_yaml::from_str(&fs_err::read_to_string(config_file)?)?;
+    let path = fs::relative_to(std::path::absolute(config_file)?, &*CWD)?;
+    let previous = git::get_file_at_rev("HEAD", &path)
+        .await?
+        .and_then(|content| serde_yaml::from_str::<serde_yaml::Value>(&content).ok());
+
+    let current_hooks = hook_definitions(&current);
+    let Some(previous) = previous.filter(|previous| settings(previous) == settings(&current))
+    else {
+        return Ok(current_hooks.into_iter().map(|(key, _)| key).collect());
+    };
+    let previous_hooks = hook_definitions(&previous);
+
+    Ok(current_hooks
+        .into_iter()
+        .filter(|hook| !previous_hooks.contains(hook))
+        .map(|(key, _)| key)
+        .collect())
+}
+
+/// The top-level settings of a config, everything but `repos`.
+fn settings(config: &serde_yaml::Value) -> serde_yaml::Value {
+    let mut settings = config.clone();
+    if let Some(mapping) = settings.as_mapping_mut() {
+        mapping.remove("repos");
+    }
+    settings
+}
+
+/// A hook definition, keyed on the index of the repo and of the hook in the repo.
+type HookDefinition<'a> = ((usize, usize), (serde_yaml::Value, &'a serde_yaml::Value));
+
+/// The hook definitions of a config.
+///
+/// A definition includes its repo, without the other hooks, so changing the `rev` changes all
+/// the hooks of the repo.
+fn hook_definitions(config: &serde_yaml::Value) -> Vec<HookDefinition<'_>> {
+    let mut definitions = Vec::new();
+    let repos = config.get("repos").and_then(serde_yaml::Value::as_sequence);
+    for (repo_idx, repo) in repos.into_iter().flatten().enumerate() {
+        let mut repo_def = repo.clone();
+        if let Some(mapping) = repo_def.as_mapping_mut() {
+            mapping.remove("hooks");
+        }
+
+        let hooks = repo.get("hooks").and_then(serde_yaml::Value::as_sequence);
+        for (hook_idx, hook) in hooks.into_iter().flatten().enumerate() {
+            definitions.push(((repo_idx, hook_idx), (repo_def.clone(), hook)));
+        }
+    }
+    definitions
+}
+
+async fn install_hook(
+    hook: &Hook,
+    env_dir: PathBuf,
+    options: InstallOptions,
+    profile: Option<&RefCell<Profile>>,
+    reporter: &HookInstallReporter,
+) -> Result<()> {
+    let progress = reporter.on_install_start(hook)?;
     debug!(%hook, target = %env_dir.display(), "Install environment");
 
     if env_dir.try_exists()? {
@@ -318,28 +752,154 @@ async fn install_hook(hook: &Hook, env_dir: PathBuf, printer: Printer) -> Result
         fs_err::remove_dir_all(&env_dir)?;
     }
 
-    hook.language.install(hook).await?;
+    let start = Instant::now();
+    hook.language.install(hook, options).await?;
     hook.mark_installed()?;
+    profile::record(profile, |profile| {
+        profile.install.push(InstallTiming {
+            hook: hook.to_string(),
+            duration: start.elapsed().as_secs_f64(),
+        });
+    });
+    reporter.on_install_complete(progress);
 
     Ok(())
 }
 
-// TODO: progress bar
-pub async fn install_hooks(hooks: &[Hook], printer: Printer) -> Result<()> {
+pub async fn install_hooks(
+    hooks: &[Hook],
+    options: InstallOptions,
+    profile: Option<&RefCell<Profile>>,
+    printer: Printer,
+) -> Result<()> {
     let to_install = hooks
         .iter()
         .filter(|&hook| !hook.installed())
         .unique_by(|&hook| hook.install_key());
 
+    let start = Instant::now();
+    let reporter = HookInstallReporter::new(printer);
+
     let mut tasks = FuturesUnordered::new();
     for hook in to_install {
         if let Some(env_dir) = hook.environment_dir() {
-            tasks.push(async move { install_hook(hook, env_dir, printer).await });
+            let reporter = &reporter;
+            tasks
+                .push(async move { install_hook(hook, env_dir, options, profile, reporter).await });
         }
     }
+    let mut installed = 0;
     while let Some(result) = tasks.next().await {
         result?;
+        installed += 1;
     }
 
+    reporter.on_complete(installed, start.elapsed())?;
+
     Ok(())
 }
+
+/// Print the `rev` of each remote repo and the commit it resolves to in the cloned repo.
+pub(crate) async fn print_resolved_revs(
+    config: Option<PathBuf>,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    let project = Project::from_config_file(config)?;
+    let store = Store::from_settings()?.init()?;
+    let _lock = store.lock_async().await?;
+
+    for repo in &project.config().repos {
+        let ConfigRepo::Remote(repo) = repo else {
+            continue;
+        };
+        let path = store.prepare_remote_repo(repo, &[], printer).await?;
+        let sha = git::rev_parse(&path, "HEAD").await?;
+        writeln!(
+            printer.stdout(),
+            "{}: {} -> {sha}",
+            git::mask_url(repo.repo.as_str()),
+            repo.rev
+        )?;
+    }
+
+    Ok(ExitStatus::Success)
+}
+
+/// The commit a new branch pushed to `remote_name` is based on: the merge base with the
+/// remote default branch, or the empty tree if there is none.
+async fn new_branch_base(to_ref: &str, remote_name: &str) -> Result<String> {
+    let remote_head = format!("refs/remotes/{remote_name}/HEAD");
+    if let Some(base) = git::get_merge_base(&remote_head, to_ref).await? {
+        debug!("Using merge base `{base}` with `{remote_head}` for the new branch");
+        return Ok(base);
+    }
+    debug!("No merge base with `{remote_head}`, diffing against the empty tree");
+    Ok(git::EMPTY_TREE.to_string())
+}
+
+/// The refs to run `pre-push` hooks on, resolved from the pre-push ref lines.
+pub(crate) struct PushRefs {
+    pub(crate) from_ref: Option<String>,
+    pub(crate) to_ref: Option<String>,
+    pub(crate) all_files: bool,
+    pub(crate) local_branch: String,
+    pub(crate) remote_branch: String,
+}
+
+/// Resolve the refs to run on from the lines git passes to the `pre-push` hook,
+/// in the format `<local ref> <local sha> <remote ref> <remote sha>`.
+///
+/// Returns `None` if there is nothing to push (e.g. only branch deletions).
+pub(crate) async fn resolve_push_refs(input: &str, remote_name: &str) -> Result<Option<PushRefs>> {
+    for line in input.lines() {
+        let parts = line.rsplitn(4, ' ').collect::<Vec<_>>();
+        let [remote_sha, remote_branch, local_sha, local_branch] = parts[..] else {
+            anyhow::bail!("Invalid pre-push ref line: `{line}`");
+        };
+
+        // Deleting a remote branch, nothing to check.
+        if git::is_zero_sha(local_sha) {
+            continue;
+        }
+
+        if !git::is_zero_sha(remote_sha) && git::rev_exists(remote_sha).await? {
+            return Ok(Some(PushRefs {
+                from_ref: Some(remote_sha.to_string()),
+                to_ref: Some(local_sha.to_string()),
+                all_files: false,
+                local_branch: local_branch.to_string(),
+                remote_branch: remote_branch.to_string(),
+            }));
+        }
+
+        // The remote doesn't have the branch (or we don't know its commit),
+        // check the commits that are not on the remote yet.
+        let ancestors = git::get_ancestors_not_in_remote(local_sha, remote_name).await?;
+        let Some(first_ancestor) = ancestors.first() else {
+            continue;
+        };
+
+        let roots = git::get_root_commits(local_sha).await?;
+        let push = if roots.contains(first_ancestor) {
+            // Pushing the whole history, including the root commit.
+            PushRefs {
+                from_ref: None,
+                to_ref: None,
+                all_files: true,
+                local_branch: local_branch.to_string(),
+                remote_branch: remote_branch.to_string(),
+            }
+        } else {
+            PushRefs {
+                from_ref: Some(git::get_parent_commit(first_ancestor).await?),
+                to_ref: Some(local_sha.to_string()),
+                all_files: false,
+                local_branch: local_branch.to_string(),
+                remote_branch: remote_branch.to_string(),
+            }
+        };
+        return Ok(Some(push));
+    }
+
+    Ok(None)
+}
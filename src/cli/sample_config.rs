@@ -1,4 +1,11 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
 use crate::cli::ExitStatus;
+use crate::fs::Simplified;
+use crate::printer::Printer;
 
 static SAMPLE_CONFIG: &str = "\
 # See https://pre-commit.com for more information
@@ -14,7 +21,26 @@ repos:
 ";
 
 #[allow(clippy::print_stdout)]
-pub(crate) fn sample_config() -> ExitStatus {
-    print!("{SAMPLE_CONFIG}");
-    ExitStatus::Success
+pub(crate) fn sample_config(file: Option<PathBuf>, printer: Printer) -> Result<ExitStatus> {
+    let Some(file) = file else {
+        print!("{SAMPLE_CONFIG}");
+        return Ok(ExitStatus::Success);
+    };
+
+    if file.exists() {
+        writeln!(
+            printer.stderr(),
+            "`{}` already exists, not overwriting it",
+            file.user_display()
+        )?;
+        return Ok(ExitStatus::Failure);
+    }
+    fs_err::write(&file, SAMPLE_CONFIG)?;
+    writeln!(
+        printer.stdout(),
+        "Sample config written to `{}`",
+        file.user_display()
+    )?;
+
+    Ok(ExitStatus::Success)
 }
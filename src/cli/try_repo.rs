@@ -0,0 +1,160 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use serde_yaml::Mapping;
+use tempfile::TempDir;
+use url::Url;
+
+use crate::config::{read_manifest, ConfigRemoteRepo, CONFIG_FILE, MANIFEST_FILE};
+use crate::git;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Write an ephemeral config that uses the hooks of `repo`, returning its path and the temporary
+/// directory that holds it (and the shadow repo of a dirty local repo) for the duration of the run.
+///
+/// All hooks of the repo manifest are used, or only `hook_id` if given.
+pub(crate) async fn try_repo(
+    repo: String,
+    rev: Option<String>,
+    hook_id: Option<&str>,
+    printer: Printer,
+) -> Result<(PathBuf, TempDir)> {
+    let temp = tempfile::tempdir()?;
+
+    let local = Path::new(&repo);
+    let (url, rev) = if local.is_dir() {
+        let local = std::path::absolute(local)?;
+        match rev {
+            Some(rev) => (file_url(&local)?, rev),
+            None if is_dirty(&local).await? => {
+                let shadow = temp.path().join("shadow-repo");
+                let rev = commit_working_tree(&local, &shadow).await?;
+                (file_url(&shadow)?, rev)
+            }
+            None => (file_url(&local)?, git::rev_parse(&local, "HEAD").await?),
+        }
+    } else {
+        let url = Url::parse(&repo).with_context(|| format!("Invalid repo `{repo}`"))?;
+        let rev = match rev {
+            Some(rev) => rev,
+            None => git::get_remote_head(url.as_str()).await?,
+        };
+        (url, rev)
+    };
+
+    // Clone the repo into the store to read its manifest, the run reuses the clone.
+    let store = Store::from_settings()?.init()?;
+    let repo_config = ConfigRemoteRepo {
+        repo: url.to_string(),
+        rev,
+        path: None,
+        hooks: vec![],
+    };
+    let path = {
+        let _lock = store.lock_async().await?;
+        store
+            .prepare_remote_repo(&repo_config, &[], printer)
+            .await?
+    };
+    let manifest = read_manifest(&path.join(MANIFEST_FILE))?;
+
+    let hooks: Vec<_> = manifest
+        .hooks
+        .iter()
+        .map(|hook| hook.id.as_str())
+        .filter(|id| hook_id.is_none_or(|hook_id| hook_id == *id))
+        .collect();
+    if hooks.is_empty() {
+        match hook_id {
+            Some(hook_id) => anyhow::bail!("Hook `{hook_id}` not found in `{repo}`"),
+            None => anyhow::bail!("No hooks found in `{repo}`"),
+        }
+    }
+
+    let hooks: Vec<_> = hooks
+        .into_iter()
+        .map(|id| Mapping::from_iter([("id".into(), id.into())]))
+        .collect();
+    let repo_entry = Mapping::from_iter([
+        ("repo".into(), repo_config.repo.as_str().into()),
+        ("rev".into(), repo_config.rev.as_str().into()),
+        ("hooks".into(), hooks.into()),
+    ]);
+    let config = Mapping::from_iter([("repos".into(), vec![repo_entry].into())]);
+    let config = serde_yaml::to_string(&config)?;
+
+    let separator = "=".repeat(79);
+    writeln!(printer.stdout(), "{}", separator.dimmed())?;
+    writeln!(printer.stdout(), "Using config:")?;
+    writeln!(printer.stdout(), "{}", separator.dimmed())?;
+    write!(printer.stdout(), "{config}")?;
+    writeln!(printer.stdout(), "{}", separator.dimmed())?;
+
+    let config_file = temp.path().join(CONFIG_FILE);
+    fs_err::write(&config_file, config)?;
+
+    Ok((config_file, temp))
+}
+
+fn file_url(path: &Path) -> Result<Url> {
+    Url::from_file_path(path)
+        .map_err(|()| anyhow::anyhow!("Invalid repo path `{}`", path.display()))
+}
+
+/// Whether the working tree of the repo has changes, including untracked files.
+async fn is_dirty(repo: &Path) -> Result<bool> {
+    let output = git::git_cmd("git status")?
+        .current_dir(repo)
+        .arg("status")
+        .arg("--porcelain")
+        .check(true)
+        .output()
+        .await?;
+    Ok(!output.stdout.is_empty())
+}
+
+/// Clone `repo` into `shadow`, and commit the state of the working tree of `repo` there,
+/// returning the commit sha.
+async fn commit_working_tree(repo: &Path, shadow: &Path) -> Result<String> {
+    git::git_cmd("clone shadow repo")?
+        .arg("clone")
+        .arg("--quiet")
+        .arg(repo)
+        .arg(shadow)
+        .check(true)
+        .output()
+        .await?;
+
+    // Stage the working tree of `repo` into the index of the shadow repo.
+    git::git_cmd("add working tree")?
+        .current_dir(shadow)
+        .arg("--work-tree")
+        .arg(repo)
+        .arg("add")
+        .arg("--all")
+        .check(true)
+        .output()
+        .await?;
+
+    git::git_cmd("commit working tree")?
+        .current_dir(shadow)
+        .arg("-c")
+        .arg("user.name=prefligit")
+        .arg("-c")
+        .arg("user.email=prefligit@localhost")
+        .arg("commit")
+        .arg("--quiet")
+        .arg("--no-verify")
+        .arg("--no-gpg-sign")
+        .arg("--allow-empty")
+        .arg("--message")
+        .arg("Temporary commit by prefligit try-repo")
+        .check(true)
+        .output()
+        .await?;
+
+    Ok(git::rev_parse(shadow, "HEAD").await?)
+}
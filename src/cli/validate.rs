@@ -1,23 +1,66 @@
 use std::error::Error;
 use std::iter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anstream::eprintln;
 use owo_colors::OwoColorize;
 
 use crate::cli::ExitStatus;
-use crate::config::{read_config, read_manifest};
+use crate::config::{
+    check_version, parse_config, read_config, read_manifest, ConfigRepo, ConfigWire,
+    Error as ConfigError,
+};
+use crate::warn_user;
+
+/// Read the config from `path`, or from stdin if `path` is `-`.
+fn read_config_or_stdin(path: &Path) -> Result<ConfigWire, ConfigError> {
+    if path != Path::new("-") {
+        return read_config(path);
+    }
+    let content = std::io::read_to_string(std::io::stdin())?;
+    parse_config(&content, "<stdin>")
+}
+
+/// Warn about the hooks that require a newer pre-commit than prefligit is compatible with.
+///
+/// They are only rejected when run, so the rest of the config can still be used.
+fn warn_unsupported_hooks<'a>(hooks: impl IntoIterator<Item = (&'a str, Option<&'a String>)>) {
+    for (id, required) in hooks {
+        if let Some(Err(version)) = required.map(|required| check_version(required)) {
+            warn_user!("Hook `{id}` {version}");
+        }
+    }
+}
 
 pub(crate) fn validate_configs(configs: Vec<PathBuf>) -> ExitStatus {
     let mut status = ExitStatus::Success;
 
     for config in configs {
-        if let Err(err) = read_config(&config) {
-            eprintln!("{}: {}", "error".red().bold(), err);
-            for source in iter::successors(err.source(), |&err| err.source()) {
-                eprintln!("  {}: {}", "caused by".red().bold(), source);
+        match read_config_or_stdin(&config) {
+            Ok(config) => {
+                for repo in &config.repos {
+                    match repo {
+                        ConfigRepo::Remote(repo) => warn_unsupported_hooks(
+                            repo.hooks
+                                .iter()
+                                .map(|h| (h.id.as_str(), h.minimum_pre_commit_version.as_ref())),
+                        ),
+                        ConfigRepo::Local(repo) => warn_unsupported_hooks(
+                            repo.hooks
+                                .iter()
+                                .map(|h| (h.id.as_str(), h.minimum_pre_commit_version.as_ref())),
+                        ),
+                        ConfigRepo::Meta(_) => {}
+                    }
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {}", "error".red().bold(), err);
+                for source in iter::successors(err.source(), |&err| err.source()) {
+                    eprintln!("  {}: {}", "caused by".red().bold(), source);
+                }
+                status = ExitStatus::Failure;
             }
-            status = ExitStatus::Failure;
         }
     }
 
@@ -28,12 +71,20 @@ pub(crate) fn validate_manifest(configs: Vec<PathBuf>) -> ExitStatus {
     let mut status = ExitStatus::Success;
 
     for config in configs {
-        if let Err(err) = read_manifest(&config) {
-            eprintln!("{}: {}", "error".red().bold(), err);
-            for source in iter::successors(err.source(), |&err| err.source()) {
-                eprintln!("  {}: {}", "caused by".red().bold(), source);
+        match read_manifest(&config) {
+            Ok(manifest) => warn_unsupported_hooks(
+                manifest
+                    .hooks
+                    .iter()
+                    .map(|h| (h.id.as_str(), h.minimum_pre_commit_version.as_ref())),
+            ),
+            Err(err) => {
+                eprintln!("{}: {}", "error".red().bold(), err);
+                for source in iter::successors(err.source(), |&err| err.source()) {
+                    eprintln!("  {}: {}", "caused by".red().bold(), source);
+                }
+                status = ExitStatus::Failure;
             }
-            status = ExitStatus::Failure;
         }
     }
 
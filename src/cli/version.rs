@@ -0,0 +1,29 @@
+use std::fmt::Write;
+
+use crate::cli::ExitStatus;
+use crate::printer::Printer;
+use crate::store::Store;
+
+/// Display the version of prefligit, with build and store information in verbose mode.
+pub(crate) fn version(verbose: bool, printer: Printer) -> anyhow::Result<ExitStatus> {
+    writeln!(printer.stdout(), "prefligit {}", env!("CARGO_PKG_VERSION"))?;
+    if !verbose {
+        return Ok(ExitStatus::Success);
+    }
+
+    writeln!(
+        printer.stdout(),
+        "commit: {}",
+        option_env!("PREFLIGIT_COMMIT_HASH").unwrap_or("unknown")
+    )?;
+    writeln!(
+        printer.stdout(),
+        "target: {}",
+        env!("PREFLIGIT_BUILD_TARGET")
+    )?;
+
+    let store = Store::from_settings()?;
+    writeln!(printer.stdout(), "store: {}", store.path().display())?;
+
+    Ok(ExitStatus::Success)
+}
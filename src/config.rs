@@ -1,14 +1,16 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::ops::RangeInclusive;
-use std::path::Path;
+use std::path::{Component, Path};
 use std::str::FromStr;
 
 use anyhow::Result;
+use clap::ValueEnum;
 use serde::{Deserialize, Deserializer, Serialize};
 use url::Url;
 
-use crate::fs::Simplified;
+use crate::fs::{Simplified, CWD};
+use crate::warn_user_once;
 
 pub const CONFIG_FILE: &str = ".pre-commit-config.yaml";
 pub const MANIFEST_FILE: &str = ".pre-commit-hooks.yaml";
@@ -34,6 +36,8 @@ pub enum Language {
     Rust,
     Swift,
     Pygrep,
+    /// Experimental: `entry` is the https URL of a script to download, verified by the hook `checksum`.
+    RemoteScript,
     Script,
     System,
 }
@@ -59,6 +63,7 @@ impl Language {
             Self::Rust => "rust",
             Self::Swift => "swift",
             Self::Pygrep => "pygrep",
+            Self::RemoteScript => "remote_script",
             Self::Script => "script",
             Self::System => "system",
         }
@@ -71,7 +76,7 @@ impl Display for Language {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default, Deserialize, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
 #[serde(rename_all = "kebab-case")]
 pub enum HookType {
     CommitMsg,
@@ -126,9 +131,7 @@ impl Display for HookType {
     }
 }
 
-// TODO: warn on deprecated stages
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize, clap::ValueEnum)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
 pub enum Stage {
     Manual,
     CommitMsg,
@@ -136,11 +139,11 @@ pub enum Stage {
     PostCommit,
     PostMerge,
     PostRewrite,
-    #[serde(alias = "commit")]
+    #[value(alias = "commit")]
     PreCommit,
-    #[serde(alias = "merge-commit")]
+    #[value(alias = "merge-commit")]
     PreMergeCommit,
-    #[serde(alias = "push")]
+    #[value(alias = "push")]
     PrePush,
     PreRebase,
     PrepareCommitMsg,
@@ -181,6 +184,47 @@ impl Stage {
     }
 }
 
+impl<'de> Deserialize<'de> for Stage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        // Legacy names of the stages, from before they were named after the hook types.
+        let stage = match s.as_str() {
+            "commit" => Some(Self::PreCommit),
+            "merge-commit" => Some(Self::PreMergeCommit),
+            "push" => Some(Self::PrePush),
+            _ => None,
+        };
+        if let Some(stage) = stage {
+            warn_user_once!("Stage `{s}` is deprecated, use `{stage}` instead");
+            return Ok(stage);
+        }
+
+        Self::value_variants()
+            .iter()
+            .find(|stage| stage.as_str() == s)
+            .copied()
+            .ok_or_else(|| {
+                const NAMES: &[&str] = &[
+                    "manual",
+                    "commit-msg",
+                    "post-checkout",
+                    "post-commit",
+                    "post-merge",
+                    "post-rewrite",
+                    "pre-commit",
+                    "pre-merge-commit",
+                    "pre-push",
+                    "pre-rebase",
+                    "prepare-commit-msg",
+                ];
+                serde::de::Error::unknown_variant(&s, NAMES)
+            })
+    }
+}
+
 impl Display for Stage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.as_str())
@@ -204,7 +248,6 @@ impl Stage {
 // TODO: warn unexpected keys
 // TODO: warn deprecated stage
 // TODO: warn sensible regex
-// TODO: check minimum_pre_commit_version
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct ConfigWire {
@@ -219,36 +262,93 @@ pub struct ConfigWire {
     pub default_stages: Option<Vec<Stage>>,
     /// Global file include pattern.
     pub files: Option<String>,
-    /// Global file exclude pattern.
-    pub exclude: Option<String>,
+    /// Global file exclude pattern, or a list of patterns.
+    pub exclude: Option<ExcludePatterns>,
+    /// Interpret `files` and `exclude` as gitignore-style globs instead of regex.
+    /// Also used as the default for local hooks that don't set `files_glob`, and for the
+    /// patterns a remote hook sets in the config, not for the patterns of a manifest.
+    /// Default is false.
+    pub files_glob: Option<bool>,
+    /// Interpret `files` and `exclude` relative to the directory of the config file,
+    /// instead of the repository root. Files outside that directory match no pattern.
+    /// Default is false.
+    pub files_relative: Option<bool>,
     /// Set to true to have pre-commit stop running hooks after the first failure.
     /// Default is false.
     pub fail_fast: Option<bool>,
+    /// Skip files larger than this size in bytes.
+    pub max_file_size: Option<u64>,
+    /// Fail if the config requires a newer version of pre-commit than `PRE_COMMIT_VERSION`.
     pub minimum_pre_commit_version: Option<String>,
     /// Configuration for pre-commit.ci service.
     pub ci: Option<HashMap<String, serde_yaml::Value>>,
 }
 
+impl ConfigWire {
+    /// The directory of the config file relative to the repository root, like `sub/`,
+    /// if `files_relative` is set and the config is not at the root.
+    pub fn files_prefix(&self, config_path: &Path) -> Option<String> {
+        if self.files_relative != Some(true) {
+            return None;
+        }
+        let path = std::path::absolute(config_path).ok()?;
+        let dir = path.parent()?.strip_prefix(&*CWD).ok()?;
+        let mut prefix = dir
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if prefix.is_empty() {
+            return None;
+        }
+        prefix.push('/');
+        Some(prefix)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RepoLocation {
     Local,
     Meta,
-    Remote(Url),
+    /// The url of a remote repo, with `${VAR}` references kept unexpanded until it's fetched.
+    Remote(String),
 }
 
 impl FromStr for RepoLocation {
-    type Err = url::ParseError;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "local" => Ok(RepoLocation::Local),
             "meta" => Ok(RepoLocation::Meta),
-            _ => Url::parse(s).map(RepoLocation::Remote),
+            _ => Url::parse(s)
+                .map(|_| RepoLocation::Remote(s.to_string()))
+                .map_err(|err| err.to_string()),
         }
     }
 }
 
+/// Expand `${VAR}` references in `value`, returning the name of the first undefined variable.
+pub(crate) fn expand_env_vars(value: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let var = &rest[start + 2..start + 2 + len];
+        let Ok(val) = std::env::var(var) else {
+            return Err(var.to_string());
+        };
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&val);
+        rest = &rest[start + 3 + len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
 impl<'de> Deserialize<'de> for RepoLocation {
     fn deserialize<D>(deserializer: D) -> Result<RepoLocation, D::Error>
     where
@@ -293,8 +393,10 @@ pub struct ConfigRemoteHook {
     pub alias: Option<String>,
     /// Override the pattern of files to run on.
     pub files: Option<String>,
-    /// Override the pattern of files to exclude.
-    pub exclude: Option<String>,
+    /// Override the pattern of files to exclude, or a list of patterns.
+    pub exclude: Option<ExcludePatterns>,
+    /// Override whether `files` and `exclude` are gitignore-style globs.
+    pub files_glob: Option<bool>,
     /// Override the types of files to run on (AND).
     pub types: Option<Vec<String>>,
     /// Override the types of files to run on (OR).
@@ -303,8 +405,12 @@ pub struct ConfigRemoteHook {
     pub exclude_types: Option<Vec<String>>,
     /// Additional dependencies to install in the environment where the hook runs.
     pub additional_dependencies: Option<Vec<String>>,
-    /// Additional arguments to pass to the hook.
+    /// Override the arguments to pass to the hook.
+    /// Like pre-commit, they replace the `args` of the manifest, unless `append_args` is set.
     pub args: Option<Vec<String>>,
+    /// Append `args` to the `args` of the manifest, instead of replacing them.
+    /// Default is false.
+    pub append_args: Option<bool>,
     /// This hook will run even if there are no matching files.
     /// Default is false.
     pub always_run: Option<bool>,
@@ -314,6 +420,11 @@ pub struct ConfigRemoteHook {
     /// Append filenames that would be checked to the hook entry as arguments.
     /// Default is true.
     pub pass_filenames: Option<bool>,
+    /// Run the hook exactly once for the whole repo, at the repo root and without filenames,
+    /// regardless of the matched files. Implies `always_run`, `require_serial` and
+    /// `pass_filenames: false`.
+    /// Default is false.
+    pub repo_wide: Option<bool>,
     /// A description of the hook. For metadata only.
     pub description: Option<String>,
     /// Run the hook on a specific version of the language.
@@ -325,6 +436,8 @@ pub struct ConfigRemoteHook {
     /// This hook will execute using a single process instead of in parallel.
     /// Default is false.
     pub require_serial: Option<bool>,
+    /// Override whether the hook never modifies files.
+    pub read_only: Option<bool>,
     /// Select which git hook(s) to run for.
     /// Default all stages are selected.
     /// See <https://pre-commit.com/#confining-hooks-to-run-at-certain-stages>.
@@ -333,6 +446,9 @@ pub struct ConfigRemoteHook {
     /// Default is false.
     pub verbose: Option<bool>,
     pub minimum_pre_commit_version: Option<String>,
+    /// Additional environment variables to set for the hook, merged over the ones of the manifest.
+    /// Values can reference other variables from the environment like `${VAR}`.
+    pub env: Option<HashMap<String, String>>,
 }
 
 /// A local hook in the configuration file.
@@ -345,7 +461,7 @@ pub type ConfigLocalHook = ManifestHook;
 pub enum MetaHookID {
     CheckHooksApply,
     CheckUselessExcludes,
-    Identify,
+    Identity,
 }
 
 impl MetaHookID {
@@ -353,7 +469,7 @@ impl MetaHookID {
         match self {
             MetaHookID::CheckHooksApply => "check-hooks-apply",
             MetaHookID::CheckUselessExcludes => "check-useless-excludes",
-            MetaHookID::Identify => "identify",
+            MetaHookID::Identity => "identity",
         }
     }
 }
@@ -376,8 +492,11 @@ pub struct ConfigMetaHook {
 
 #[derive(Debug, Clone)]
 pub struct ConfigRemoteRepo {
-    pub repo: Url,
+    /// The repo url, may contain `${VAR}` references, see [`RepoLocation::Remote`].
+    pub repo: String,
     pub rev: String,
+    /// The subdirectory of the repo that contains the manifest, for repos with hooks not at the root.
+    pub path: Option<String>,
     pub hooks: Vec<ConfigRemoteHook>,
 }
 
@@ -393,6 +512,23 @@ pub struct ConfigMetaRepo {
     pub hooks: Vec<ConfigMetaHook>,
 }
 
+/// An `exclude` pattern, or a list of patterns where a file is excluded if any of them matches.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(untagged)]
+pub enum ExcludePatterns {
+    Single(String),
+    List(Vec<String>),
+}
+
+impl ExcludePatterns {
+    pub fn patterns(&self) -> Vec<&str> {
+        match self {
+            Self::Single(pattern) => vec![pattern.as_str()],
+            Self::List(patterns) => patterns.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ConfigRepo {
     Remote(ConfigRemoteRepo),
@@ -419,14 +555,27 @@ impl<'de> Deserialize<'de> for ConfigRepo {
                 #[derive(Deserialize)]
                 struct RemoteRepo {
                     rev: String,
+                    path: Option<String>,
                     hooks: Vec<ConfigRemoteHook>,
                 }
-                let RemoteRepo { rev, hooks } = RemoteRepo::deserialize(rest)
+                let RemoteRepo { rev, path, hooks } = RemoteRepo::deserialize(rest)
                     .map_err(|e| serde::de::Error::custom(format!("Invalid remote repo: {e}")))?;
 
+                if let Some(path) = &path {
+                    let valid = Path::new(path).components().all(|component| {
+                        matches!(component, Component::Normal(_) | Component::CurDir)
+                    });
+                    if !valid {
+                        return Err(serde::de::Error::custom(format!(
+                            "Invalid remote repo: `path` must be a relative path inside the repo, got `{path}`"
+                        )));
+                    }
+                }
+
                 Ok(ConfigRepo::Remote(ConfigRemoteRepo {
                     repo: url,
                     rev,
+                    path,
                     hooks,
                 }))
             }
@@ -470,8 +619,6 @@ impl ConfigRepo {
     }
 }
 
-// TODO: check minimum_pre_commit_version
-
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct ManifestHook {
@@ -487,9 +634,12 @@ pub struct ManifestHook {
     pub alias: Option<String>,
     /// The pattern of files to run on.
     pub files: Option<String>,
-    /// Exclude files that were matched by `files`.
+    /// Exclude files that were matched by `files`, a pattern or a list of patterns.
     /// Default is `$^`, which matches nothing.
-    pub exclude: Option<String>,
+    pub exclude: Option<ExcludePatterns>,
+    /// Interpret `files` and `exclude` as gitignore-style globs instead of regex.
+    /// Default to the top-level `files_glob`, or false.
+    pub files_glob: Option<bool>,
     /// List of file types to run on (AND).
     /// Default is `[file]`, which matches all files.
     pub types: Option<Vec<String>>,
@@ -512,6 +662,11 @@ pub struct ManifestHook {
     /// Append filenames that would be checked to the hook entry as arguments.
     /// Default is true.
     pub pass_filenames: Option<bool>,
+    /// Run the hook exactly once for the whole repo, at the repo root and without filenames,
+    /// regardless of the matched files. Implies `always_run`, `require_serial` and
+    /// `pass_filenames: false`.
+    /// Default is false.
+    pub repo_wide: Option<bool>,
     /// A description of the hook. For metadata only.
     pub description: Option<String>,
     /// Run the hook on a specific version of the language.
@@ -523,6 +678,10 @@ pub struct ManifestHook {
     /// This hook will execute using a single process instead of in parallel.
     /// Default is false.
     pub require_serial: Option<bool>,
+    /// The hook never modifies files, so it can run concurrently with the other read-only
+    /// hooks next to it, see `run --jobs`. A prefligit extension, unknown to pre-commit.
+    /// Default is false.
+    pub read_only: Option<bool>,
     /// Select which git hook(s) to run for.
     /// Default all stages are selected.
     /// See <https://pre-commit.com/#confining-hooks-to-run-at-certain-stages>.
@@ -531,6 +690,33 @@ pub struct ManifestHook {
     /// Default is false.
     pub verbose: Option<bool>,
     pub minimum_pre_commit_version: Option<String>,
+    /// Additional environment variables to set for the hook.
+    /// Values can reference other variables from the environment like `${VAR}`.
+    pub env: Option<HashMap<String, String>>,
+    /// Put the environment of another hook, referenced by id, on `PATH`.
+    /// Only supported for local `system` hooks.
+    pub use_env_of: Option<String>,
+    /// The SHA-256 checksum of the script downloaded from `entry`, as 64 hex characters.
+    /// Required by the `remote_script` language.
+    #[serde(default, deserialize_with = "deserialize_checksum")]
+    pub checksum: Option<String>,
+}
+
+/// Deserialize a SHA-256 checksum, rejecting anything but 64 hex characters, since it names
+/// the environment directory of the hook.
+fn deserialize_checksum<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(checksum) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(serde::de::Error::custom(format!(
+            "Invalid checksum `{checksum}`, expected a SHA-256 digest of 64 hex characters"
+        )));
+    }
+    Ok(Some(checksum))
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -553,6 +739,36 @@ pub enum Error {
 
     #[error("Invalid repo URL: {0}")]
     RepoUrl(#[from] url::ParseError),
+
+    #[error("`{name}` {version}")]
+    UnsupportedVersion {
+        name: String,
+        version: UnsupportedVersion,
+    },
+}
+
+/// A `minimum_pre_commit_version` newer than `PRE_COMMIT_VERSION`.
+#[derive(Debug, thiserror::Error)]
+#[error("requires pre-commit version `{0}`, but prefligit is compatible with pre-commit version `{PRE_COMMIT_VERSION}`")]
+pub struct UnsupportedVersion(pub String);
+
+/// The version of pre-commit that prefligit is compatible with.
+pub const PRE_COMMIT_VERSION: &str = "4.0.1";
+
+/// Fail if a `minimum_pre_commit_version` is not satisfied by `PRE_COMMIT_VERSION`.
+pub(crate) fn check_version(minimum: &str) -> Result<(), UnsupportedVersion> {
+    fn parts(version: &str) -> Vec<u64> {
+        version
+            .trim()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    if parts(minimum) <= parts(PRE_COMMIT_VERSION) {
+        Ok(())
+    } else {
+        Err(UnsupportedVersion(minimum.to_string()))
+    }
 }
 
 /// Read the configuration file from the given path.
@@ -564,8 +780,18 @@ pub fn read_config(path: &Path) -> Result<ConfigWire, Error> {
         }
         Err(e) => return Err(e.into()),
     };
-    let config = serde_yaml::from_str(&content)
-        .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+    parse_config(&content, &path.user_display().to_string())
+}
+
+/// Parse the configuration from `content`, with `name` as the file name in errors.
+pub fn parse_config(content: &str, name: &str) -> Result<ConfigWire, Error> {
+    let config: ConfigWire = from_yaml(content).map_err(|e| Error::Yaml(name.to_string(), e))?;
+    if let Some(required) = &config.minimum_pre_commit_version {
+        check_version(required).map_err(|version| Error::UnsupportedVersion {
+            name: name.to_string(),
+            version,
+        })?;
+    }
     Ok(config)
 }
 
@@ -573,11 +799,34 @@ pub fn read_config(path: &Path) -> Result<ConfigWire, Error> {
 /// Read the manifest file from the given path.
 pub fn read_manifest(path: &Path) -> Result<ManifestWire, Error> {
     let content = fs_err::read_to_string(path)?;
-    let manifest = serde_yaml::from_str(&content)
-        .map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
+    let manifest =
+        from_yaml(&content).map_err(|e| Error::Yaml(path.user_display().to_string(), e))?;
     Ok(manifest)
 }
 
+/// Deserialize YAML, resolving merge keys like `<<: *defaults`.
+///
+/// Resolving merge keys loses the location of errors, so it's only done if they are used.
+fn from_yaml<T: serde::de::DeserializeOwned>(content: &str) -> Result<T, serde_yaml::Error> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(content)?;
+    if !has_merge_key(&value) {
+        return serde_yaml::from_str(content);
+    }
+    value.apply_merge()?;
+    serde_yaml::from_value(value)
+}
+
+fn has_merge_key(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => mapping
+            .iter()
+            .any(|(key, value)| key.as_str() == Some("<<") || has_merge_key(value)),
+        serde_yaml::Value::Sequence(sequence) => sequence.iter().any(has_merge_key),
+        serde_yaml::Value::Tagged(tagged) => has_merge_key(&tagged.value),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -595,7 +844,7 @@ mod tests {
                     language: system
         "};
         let result = serde_yaml::from_str::<ConfigWire>(yaml);
-        insta::assert_debug_snapshot!(result, @r###"
+        insta::assert_debug_snapshot!(result, @r#"
         Ok(
             ConfigWire {
                 repos: [
@@ -611,6 +860,7 @@ mod tests {
                                     alias: None,
                                     files: None,
                                     exclude: None,
+                                    files_glob: None,
                                     types: None,
                                     types_or: None,
                                     exclude_types: None,
@@ -619,13 +869,18 @@ mod tests {
                                     always_run: None,
                                     fail_fast: None,
                                     pass_filenames: None,
+                                    repo_wide: None,
                                     description: None,
                                     language_version: None,
                                     log_file: None,
                                     require_serial: None,
+                                    read_only: None,
                                     stages: None,
                                     verbose: None,
                                     minimum_pre_commit_version: None,
+                                    env: None,
+                                    use_env_of: None,
+                                    checksum: None,
                                 },
                             ],
                         },
@@ -636,12 +891,15 @@ mod tests {
                 default_stages: None,
                 files: None,
                 exclude: None,
+                files_glob: None,
+                files_relative: None,
                 fail_fast: None,
+                max_file_size: None,
                 minimum_pre_commit_version: None,
                 ci: None,
             },
         )
-        "###);
+        "#);
 
         let yaml = indoc::indoc! {r"
             repos:
@@ -669,28 +927,15 @@ mod tests {
                   - id: typos
         "};
         let result = serde_yaml::from_str::<ConfigWire>(yaml);
-        insta::assert_debug_snapshot!(result, @r###"
+        insta::assert_debug_snapshot!(result, @r#"
         Ok(
             ConfigWire {
                 repos: [
                     Remote(
                         ConfigRemoteRepo {
-                            repo: Url {
-                                scheme: "https",
-                                cannot_be_a_base: false,
-                                username: "",
-                                password: None,
-                                host: Some(
-                                    Domain(
-                                        "github.com",
-                                    ),
-                                ),
-                                port: None,
-                                path: "/crate-ci/typos",
-                                query: None,
-                                fragment: None,
-                            },
+                            repo: "https://github.com/crate-ci/typos",
                             rev: "v1.0.0",
+                            path: None,
                             hooks: [
                                 ConfigRemoteHook {
                                     id: "typos",
@@ -700,21 +945,26 @@ mod tests {
                                     alias: None,
                                     files: None,
                                     exclude: None,
+                                    files_glob: None,
                                     types: None,
                                     types_or: None,
                                     exclude_types: None,
                                     additional_dependencies: None,
                                     args: None,
+                                    append_args: None,
                                     always_run: None,
                                     fail_fast: None,
                                     pass_filenames: None,
+                                    repo_wide: None,
                                     description: None,
                                     language_version: None,
                                     log_file: None,
                                     require_serial: None,
+                                    read_only: None,
                                     stages: None,
                                     verbose: None,
                                     minimum_pre_commit_version: None,
+                                    env: None,
                                 },
                             ],
                         },
@@ -725,12 +975,15 @@ mod tests {
                 default_stages: None,
                 files: None,
                 exclude: None,
+                files_glob: None,
+                files_relative: None,
                 fail_fast: None,
+                max_file_size: None,
                 minimum_pre_commit_version: None,
                 ci: None,
             },
         )
-        "###);
+        "#);
 
         let yaml = indoc::indoc! {r"
             repos:
@@ -792,7 +1045,7 @@ mod tests {
                     language: rust
         "};
         let result = serde_yaml::from_str::<ConfigWire>(yaml);
-        insta::assert_debug_snapshot!(result, @r###"
+        insta::assert_debug_snapshot!(result, @r#"
         Ok(
             ConfigWire {
                 repos: [
@@ -808,6 +1061,7 @@ mod tests {
                                     alias: None,
                                     files: None,
                                     exclude: None,
+                                    files_glob: None,
                                     types: None,
                                     types_or: None,
                                     exclude_types: None,
@@ -816,13 +1070,18 @@ mod tests {
                                     always_run: None,
                                     fail_fast: None,
                                     pass_filenames: None,
+                                    repo_wide: None,
                                     description: None,
                                     language_version: None,
                                     log_file: None,
                                     require_serial: None,
+                                    read_only: None,
                                     stages: None,
                                     verbose: None,
                                     minimum_pre_commit_version: None,
+                                    env: None,
+                                    use_env_of: None,
+                                    checksum: None,
                                 },
                             ],
                         },
@@ -833,12 +1092,15 @@ mod tests {
                 default_stages: None,
                 files: None,
                 exclude: None,
+                files_glob: None,
+                files_relative: None,
                 fail_fast: None,
+                max_file_size: None,
                 minimum_pre_commit_version: None,
                 ci: None,
             },
         )
-        "###);
+        "#);
     }
 
     #[test]
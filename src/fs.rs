@@ -21,7 +21,7 @@
 // SOFTWARE.
 
 use std::fmt::Display;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::LazyLock;
 
 use fs2::FileExt;
@@ -206,6 +206,31 @@ pub(crate) fn normalize_path(path: &mut str) {
     }
 }
 
+/// Lexically normalize a path, removing `.` components and resolving `..` components
+/// against their parent, without touching the filesystem.
+///
+/// `foo/./bar/../baz` -> `foo/baz`
+pub(crate) fn clean_path(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(cleaned.components().next_back(), Some(Component::Normal(_))) {
+                    cleaned.pop();
+                } else if !matches!(
+                    cleaned.components().next_back(),
+                    Some(Component::RootDir | Component::Prefix(_))
+                ) {
+                    cleaned.push(component);
+                }
+            }
+            _ => cleaned.push(component),
+        }
+    }
+    cleaned
+}
+
 /// Compute a path describing `path` relative to `base`.
 ///
 /// `lib/python/site-packages/foo/__init__.py` and `lib/python/site-packages` -> `foo/__init__.py`
@@ -3,8 +3,9 @@ use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
 use anyhow::Result;
-use tracing::warn;
+use tracing::{debug, warn};
 
+use crate::fs::normalize_path;
 use crate::process;
 use crate::process::Cmd;
 
@@ -16,8 +17,24 @@ pub enum Error {
     GitNotFound(#[from] which::Error),
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("Failed to clone `{url}` at `{rev}`")]
+    Clone {
+        url: String,
+        rev: String,
+        #[source]
+        source: Box<Error>,
+    },
+    #[error("Repo `{url}` references undefined environment variable `{var}`")]
+    UndefinedVariable { url: String, var: String },
+    #[error("Ref `{0}` is not found in this shallow clone, fetch it first, or fetch the full history with `git fetch --unshallow`")]
+    ShallowMissingRef(String),
+    #[error("Failed to find the common ancestor of `{old}` and `{new}` in this shallow clone, fetch the full history with `git fetch --unshallow`")]
+    ShallowNoMergeBase { old: String, new: String },
 }
 
+/// The hash of the empty tree, to diff against when there is no base commit.
+pub const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
 pub static GIT: LazyLock<Result<PathBuf, which::Error>> = LazyLock::new(|| which::which("git"));
 
 static GIT_ENV: LazyLock<Vec<(String, String)>> = LazyLock::new(|| {
@@ -76,19 +93,85 @@ pub async fn intent_to_add_files() -> Result<Vec<String>, Error> {
 }
 
 pub async fn get_changed_files(old: &str, new: &str) -> Result<Vec<String>, Error> {
-    let output = git_cmd("get changed files")?
-        .arg("diff")
+    let mut cmd = git_cmd(&format!("get changed files between `{old}` and `{new}`"))?;
+    cmd.arg("diff")
+        .arg("--name-only")
+        .arg("--diff-filter=ACMRT")
+        .arg("--no-ext-diff") // Disable external diff drivers
+        .arg("-z"); // Use NUL as line terminator
+
+    // The empty tree is not a commit, so it has no merge base.
+    if old == EMPTY_TREE {
+        cmd.arg(old).arg(new);
+    } else {
+        cmd.arg(format!("{old}...{new}"));
+    }
+    let output = cmd.check(true).output().await;
+
+    match output {
+        Ok(output) => Ok(zsplit(&output.stdout)),
+        // In a shallow clone (common in CI), the refs or their common ancestor are often missing.
+        Err(err @ process::Error::Status { .. }) if is_shallow().await? => {
+            for rev in [old, new] {
+                if !rev_exists(rev).await? {
+                    return Err(Error::ShallowMissingRef(rev.to_string()));
+                }
+            }
+            debug!("Failed to diff `{old}...{new}` in a shallow clone: {err}");
+            Err(Error::ShallowNoMergeBase {
+                old: old.to_string(),
+                new: new.to_string(),
+            })
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Check if the repository is a shallow clone.
+pub async fn is_shallow() -> Result<bool, Error> {
+    let output = git_cmd("check shallow repository")?
+        .arg("rev-parse")
+        .arg("--is-shallow-repository")
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "true")
+}
+
+/// Get the files added or modified by a commit.
+pub async fn get_commit_files(commit: &str) -> Result<Vec<String>, Error> {
+    let output = git_cmd(&format!("get files changed in `{commit}`"))?
+        .arg("diff-tree")
+        .arg("--root") // Include the files of the initial commit
+        .arg("--no-commit-id")
         .arg("--name-only")
+        .arg("-r")
         .arg("--diff-filter=ACMRT")
         .arg("--no-ext-diff") // Disable external diff drivers
         .arg("-z") // Use NUL as line terminator
-        .arg(format!("{old}...{new}"))
+        .arg(commit)
         .check(true)
         .output()
         .await?;
     Ok(zsplit(&output.stdout))
 }
 
+/// Get the content of a file at a revision, or `None` if the file doesn't exist there.
+pub async fn get_file_at_rev(rev: &str, path: &Path) -> Result<Option<String>, Error> {
+    let mut path = path.to_string_lossy().to_string();
+    normalize_path(&mut path);
+    let output = git_cmd(&format!("get `{path}` at `{rev}`"))?
+        .arg("show")
+        .arg(format!("{rev}:{path}"))
+        .check(false)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
 pub async fn get_all_files() -> Result<Vec<String>, Error> {
     let output = git_cmd("get git all files")?
         .arg("ls-files")
@@ -222,6 +305,20 @@ pub async fn write_tree() -> Result<String, Error> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// Get the `Name <email>` of the current git user.
+pub async fn get_user_ident() -> Result<String, Error> {
+    let output = git_cmd("get git user ident")?
+        .arg("var")
+        .arg("GIT_AUTHOR_IDENT")
+        .check(true)
+        .output()
+        .await?;
+    // The ident ends with the timestamp and timezone, e.g. `Name <email> 1700000000 +0000`.
+    let ident = String::from_utf8_lossy(&output.stdout);
+    let ident = ident.trim();
+    Ok(ident.rsplitn(3, ' ').nth(2).unwrap_or(ident).to_string())
+}
+
 /// Get the path of the top-level directory of the working tree.
 pub async fn get_root() -> Result<PathBuf, Error> {
     let output = git_cmd("get git root")?
@@ -254,8 +351,34 @@ pub async fn is_dirty(path: &Path) -> Result<bool, Error> {
     }
 }
 
+/// Mask the username and password of `url`, which may be a token, for output.
+pub fn mask_url(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.username().is_empty() && parsed.password().is_none() {
+        return url.to_string();
+    }
+    let _ = parsed.set_username("***");
+    if parsed.password().is_some() {
+        let _ = parsed.set_password(Some("***"));
+    }
+    parsed.to_string()
+}
+
+/// Expand `${VAR}` references in `url` from the environment, e.g. for a token.
+///
+/// Only the expanded url is passed to git for fetching, the template is what's stored in the
+/// remote of the clone, so the secret is never written to disk.
+fn expand_url(url: &str) -> Result<String, Error> {
+    crate::config::expand_env_vars(url).map_err(|var| Error::UndefinedVariable {
+        url: url.to_string(),
+        var,
+    })
+}
+
 async fn init_repo(url: &str, path: &Path) -> Result<(), Error> {
-    git_cmd("init git repo")?
+    git_cmd(&format!("init git repo at `{}`", path.display()))?
         .arg("init")
         .arg("--template=")
         .arg(path)
@@ -263,7 +386,7 @@ async fn init_repo(url: &str, path: &Path) -> Result<(), Error> {
         .output()
         .await?;
 
-    git_cmd("add git remote")?
+    git_cmd(&format!("add git remote `{}`", mask_url(url)))?
         .current_dir(path)
         .arg("remote")
         .arg("add")
@@ -276,20 +399,20 @@ async fn init_repo(url: &str, path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
-    git_cmd("git shallow clone")?
+async fn shallow_clone(url: &str, rev: &str, path: &Path) -> Result<(), Error> {
+    git_cmd(&format!("shallow fetch `{rev}`"))?
         .current_dir(path)
         .arg("-c")
         .arg("protocol.version=2")
         .arg("fetch")
-        .arg("origin")
+        .arg(url)
         .arg(rev)
         .arg("--depth=1")
         .check(true)
         .output()
         .await?;
 
-    git_cmd("git checkout")?
+    git_cmd(&format!("checkout `{rev}`"))?
         .current_dir(path)
         .arg("checkout")
         .arg("FETCH_HEAD")
@@ -297,7 +420,7 @@ async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
         .output()
         .await?;
 
-    git_cmd("update git submodules")?
+    git_cmd(&format!("update git submodules at `{rev}`"))?
         .current_dir(path)
         .arg("-c")
         .arg("protocol.version=2")
@@ -313,17 +436,18 @@ async fn shallow_clone(rev: &str, path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
-    git_cmd("git full clone")?
+async fn full_clone(url: &str, rev: &str, path: &Path) -> Result<(), Error> {
+    git_cmd("fetch all refs")?
         .current_dir(path)
         .arg("fetch")
-        .arg("origin")
+        .arg(url)
+        .arg("+refs/heads/*:refs/remotes/origin/*")
         .arg("--tags")
         .check(true)
         .output()
         .await?;
 
-    git_cmd("git checkout")?
+    git_cmd(&format!("checkout `{rev}`"))?
         .current_dir(path)
         .arg("checkout")
         .arg(rev)
@@ -331,7 +455,7 @@ async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
         .output()
         .await?;
 
-    git_cmd("update git submodules")?
+    git_cmd(&format!("update git submodules at `{rev}`"))?
         .current_dir(path)
         .arg("submodule")
         .arg("update")
@@ -345,14 +469,120 @@ async fn full_clone(rev: &str, path: &Path) -> Result<(), Error> {
 }
 
 pub async fn clone_repo(url: &str, rev: &str, path: &Path) -> Result<(), Error> {
+    let clone = async {
+        init_repo(url, path).await?;
+        let url = expand_url(url)?;
+
+        if let Err(err) = shallow_clone(&url, rev, path).await {
+            warn!(?err, "Failed to shallow clone, falling back to full clone");
+            full_clone(&url, rev, path).await
+        } else {
+            Ok(())
+        }
+    };
+
+    clone.await.map_err(|err| Error::Clone {
+        url: mask_url(url),
+        rev: rev.to_string(),
+        source: Box::new(err),
+    })
+}
+
+/// Initialize a repo at `path`, and fetch `HEAD` and all tags from `url`.
+pub async fn fetch_head_and_tags(url: &str, path: &Path) -> Result<(), Error> {
     init_repo(url, path).await?;
 
-    if let Err(err) = shallow_clone(rev, path).await {
-        warn!(?err, "Failed to shallow clone, falling back to full clone");
-        full_clone(rev, path).await
+    git_cmd(&format!("fetch `HEAD` and tags from `{}`", mask_url(url)))?
+        .current_dir(path)
+        .arg("-c")
+        .arg("protocol.version=2")
+        .arg("fetch")
+        .arg(expand_url(url)?)
+        .arg("HEAD")
+        .arg("--tags")
+        .arg("--quiet")
+        .check(true)
+        .output()
+        .await?;
+
+    Ok(())
+}
+
+/// Get the tag that `rev` points at, or the latest tag reachable from `rev` if `exact` is false.
+pub async fn describe_tag(path: &Path, rev: &str, exact: bool) -> Result<Option<String>, Error> {
+    let mut cmd = git_cmd(&format!("describe `{rev}`"))?;
+    cmd.current_dir(path).arg("describe").arg(rev).arg("--tags");
+    if exact {
+        cmd.arg("--exact-match");
     } else {
-        Ok(())
+        cmd.arg("--abbrev=0");
     }
+    let output = cmd.check(false).output().await?;
+    if output.status.success() {
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Get the tags that point at `rev`.
+pub async fn get_tags_points_at(path: &Path, rev: &str) -> Result<Vec<String>, Error> {
+    let output = git_cmd(&format!("get tags pointing at `{rev}`"))?
+        .current_dir(path)
+        .arg("tag")
+        .arg("--points-at")
+        .arg(rev)
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Get the commit sha of `rev`.
+pub async fn rev_parse(path: &Path, rev: &str) -> Result<String, Error> {
+    let output = git_cmd(&format!("resolve `{rev}`"))?
+        .current_dir(path)
+        .arg("rev-parse")
+        .arg(format!("{rev}^{{commit}}"))
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the commit sha of `HEAD` of the remote repo at `url`.
+pub async fn get_remote_head(url: &str) -> Result<String, Error> {
+    let output = git_cmd(&format!("get `HEAD` of `{}`", mask_url(url)))?
+        .arg("ls-remote")
+        .arg("--exit-code")
+        .arg(expand_url(url)?)
+        .arg("HEAD")
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Checkout `rev` in the repo at `path`.
+pub async fn checkout(path: &Path, rev: &str) -> Result<(), Error> {
+    git_cmd(&format!("checkout `{rev}`"))?
+        .current_dir(path)
+        .arg("checkout")
+        .arg("--quiet")
+        .arg(rev)
+        .check(true)
+        .output()
+        .await?;
+    Ok(())
 }
 
 pub async fn has_hooks_path_set() -> Result<bool> {
@@ -369,3 +599,84 @@ pub async fn has_hooks_path_set() -> Result<bool> {
         Ok(false)
     }
 }
+
+/// Check if a revision exists in the repository.
+pub async fn rev_exists(rev: &str) -> Result<bool, Error> {
+    let output = git_cmd("check rev exists")?
+        .arg("cat-file")
+        .arg("-e")
+        .arg(rev)
+        .check(false)
+        .output()
+        .await?;
+    Ok(output.status.success())
+}
+
+/// Whether `sha` is the all-zeros object name git uses for a missing ref.
+pub fn is_zero_sha(sha: &str) -> bool {
+    !sha.is_empty() && sha.chars().all(|c| c == '0')
+}
+
+/// Get the best common ancestor of two revisions, if any.
+pub async fn get_merge_base(a: &str, b: &str) -> Result<Option<String>, Error> {
+    let output = git_cmd(&format!("get merge base of `{a}` and `{b}`"))?
+        .arg("merge-base")
+        .arg(a)
+        .arg(b)
+        .check(false)
+        .output()
+        .await?;
+    if output.status.success() {
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Get the commits reachable from `rev` but not from any ref of `remote`, oldest first.
+pub async fn get_ancestors_not_in_remote(rev: &str, remote: &str) -> Result<Vec<String>, Error> {
+    let output = git_cmd(&format!(
+        "get ancestors of `{rev}` not in remote `{remote}`"
+    ))?
+    .arg("rev-list")
+    .arg(rev)
+    .arg("--topo-order")
+    .arg("--reverse")
+    .arg("--not")
+    .arg(format!("--remotes={remote}"))
+    .check(true)
+    .output()
+    .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Get the root commits reachable from `rev`.
+pub async fn get_root_commits(rev: &str) -> Result<HashSet<String>, Error> {
+    let output = git_cmd(&format!("get root commits of `{rev}`"))?
+        .arg("rev-list")
+        .arg("--max-parents=0")
+        .arg(rev)
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Get the first parent of a commit.
+pub async fn get_parent_commit(rev: &str) -> Result<String, Error> {
+    let output = git_cmd(&format!("get parent commit of `{rev}`"))?
+        .arg("rev-parse")
+        .arg(format!("{rev}^"))
+        .check(true)
+        .output()
+        .await?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
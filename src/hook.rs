@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -9,26 +10,49 @@ use futures::StreamExt;
 use itertools::zip_eq;
 use thiserror::Error;
 use tracing::{debug, error};
-use url::Url;
 
 use crate::config::{
-    self, read_config, read_manifest, ConfigLocalHook, ConfigRemoteHook, ConfigRepo, ConfigWire,
-    Language, ManifestHook, Stage, CONFIG_FILE, MANIFEST_FILE,
+    self, expand_env_vars, read_config, read_manifest, ConfigLocalHook, ConfigRemoteHook,
+    ConfigRepo, ConfigWire, ExcludePatterns, Language, ManifestHook, Stage, CONFIG_FILE,
+    MANIFEST_FILE,
 };
 use crate::fs::{Simplified, CWD};
+use crate::git;
 use crate::languages::DEFAULT_VERSION;
+use crate::meta_hooks;
 use crate::printer::Printer;
 use crate::store::Store;
 use crate::warn_user;
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("Failed to parse URL: {0}")]
-    InvalidUrl(#[from] url::ParseError),
     #[error(transparent)]
     Config(#[from] config::Error),
     #[error("Hook {hook} in not present in repository {repo}")]
     HookNotFound { hook: String, repo: String },
+    #[error("Hook `{hook}` uses the environment of `{target}`, but no hook with that id is found")]
+    UseEnvOfNotFound { hook: String, target: String },
+    #[error("Hook `{hook}` uses the environment of `{target}`, but `{target}` has no environment")]
+    UseEnvOfNoEnv { hook: String, target: String },
+    #[error("Hook `{hook}` references undefined environment variable `{var}` in additional dependency `{dependency}`")]
+    UndefinedVariable {
+        hook: String,
+        dependency: String,
+        var: String,
+    },
+    #[error("Hook `{hook}` references undefined environment variable `{var}` in env `{key}`")]
+    UndefinedEnvVariable {
+        hook: String,
+        key: String,
+        var: String,
+    },
+    #[error("Hook `{hook}` {version}")]
+    UnsupportedVersion {
+        hook: String,
+        version: config::UnsupportedVersion,
+    },
+    #[error("Hook `{0}` uses language `remote_script`, but `checksum` is not set")]
+    MissingChecksum(String),
     #[error(transparent)]
     Store(#[from] Box<crate::store::Error>),
     #[error(transparent)]
@@ -40,28 +64,28 @@ pub enum Repo {
     Remote {
         /// Path to the stored repo.
         path: PathBuf,
-        url: Url,
+        url: String,
         rev: String,
         hooks: Vec<ManifestHook>,
     },
     Local {
         hooks: Vec<ManifestHook>,
     },
-    Meta,
+    Meta {
+        hooks: Vec<ManifestHook>,
+    },
 }
 
 impl Repo {
     /// Load the remote repo manifest from the path.
     pub fn remote(url: &str, rev: &str, path: &str) -> Result<Self, Error> {
-        let url = Url::parse(url)?;
-
         let path = PathBuf::from(path);
         let manifest = read_manifest(&path.join(MANIFEST_FILE))?;
         let hooks = manifest.hooks;
 
         Ok(Self::Remote {
             path,
-            url,
+            url: url.to_string(),
             rev: rev.to_string(),
             hooks,
         })
@@ -72,16 +96,18 @@ impl Repo {
         Self::Local { hooks }
     }
 
-    pub fn meta() -> Result<Self, Error> {
-        todo!()
+    /// Construct the meta repo, with the built-in meta hooks.
+    pub fn meta() -> Self {
+        Self::Meta {
+            hooks: meta_hooks::manifest_hooks(),
+        }
     }
 
     /// Get a hook by id.
     pub fn get_hook(&self, id: &str) -> Option<&ManifestHook> {
         let hooks = match self {
             Repo::Remote { ref hooks, .. } => hooks,
-            Repo::Local { ref hooks } => hooks,
-            Repo::Meta => return None,
+            Repo::Local { ref hooks } | Repo::Meta { ref hooks } => hooks,
         };
         hooks.iter().find(|hook| hook.id == id)
     }
@@ -89,8 +115,7 @@ impl Repo {
     pub fn path(&self) -> &Path {
         match self {
             Repo::Remote { ref path, .. } => path,
-            Repo::Local { .. } => &CWD,
-            Repo::Meta => todo!(),
+            Repo::Local { .. } | Repo::Meta { .. } => &CWD,
         }
     }
 }
@@ -98,9 +123,9 @@ impl Repo {
 impl Display for Repo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Repo::Remote { url, rev, .. } => write!(f, "{url}@{rev}"),
+            Repo::Remote { url, rev, .. } => write!(f, "{}@{rev}", git::mask_url(url)),
             Repo::Local { .. } => write!(f, "local"),
-            Repo::Meta => write!(f, "meta"),
+            Repo::Meta { .. } => write!(f, "meta"),
         }
     }
 }
@@ -152,6 +177,10 @@ impl Project {
     }
 
     async fn init_repos(&mut self, store: &Store, printer: Printer) -> Result<(), Error> {
+        store
+            .mark_config_used(&self.config_path)
+            .map_err(Box::new)?;
+
         let mut repos = Vec::with_capacity(self.config.repos.len());
 
         // TODO: progress bar
@@ -169,7 +198,7 @@ impl Project {
                     repos.push((idx, Rc::new(repo)));
                 }
                 ConfigRepo::Meta(_) => {
-                    todo!()
+                    repos.push((idx, Rc::new(Repo::meta())));
                 }
             }
         }
@@ -179,6 +208,11 @@ impl Project {
             let ConfigRepo::Remote(repo_config) = &self.config.repos[idx] else {
                 unreachable!();
             };
+            // Hooks of a repo with `path` are defined and run in that subdirectory.
+            let repo_path = match &repo_config.path {
+                Some(path) => repo_path.join(path),
+                None => repo_path,
+            };
             let repo = Repo::remote(
                 repo_config.repo.as_str(),
                 &repo_config.rev,
@@ -204,85 +238,219 @@ impl Project {
         let mut hooks = Vec::new();
 
         // TODO: progress bar
-        for (repo_config, repo) in zip_eq(self.config.repos.iter(), self.repos.iter()) {
-            match repo_config {
-                ConfigRepo::Remote(repo_config) => {
-                    for hook_config in &repo_config.hooks {
-                        // Check hook id is valid.
-                        let Some(hook) = repo.get_hook(&hook_config.id) else {
-                            return Err(Error::HookNotFound {
-                                hook: hook_config.id.clone(),
-                                repo: repo.to_string(),
-                            });
-                        };
-
-                        let repo = Rc::clone(repo);
-                        let mut builder = HookBuilder::new(repo, hook.clone());
-                        builder.update(hook_config);
-                        builder.combine(&self.config);
-                        let mut hook = builder.build();
-
-                        if hook.additional_dependencies.is_empty() {
-                            // Use the shared repo environment.
-                            let path = hook.repo.path().to_path_buf();
-                            hook = hook.with_path(path);
-                        } else {
-                            // Prepare hooks with `additional_dependencies` (they need separate environments).
-                            let path = store
-                                .prepare_remote_repo(
-                                    repo_config,
-                                    &hook.additional_dependencies,
-                                    printer,
-                                )
-                                .await
-                                .map_err(Box::new)?;
-
-                            hook = hook.with_path(path);
-                        }
-
-                        hooks.push(hook);
+        for (repo_idx, (repo_config, repo)) in
+            zip_eq(self.config.repos.iter(), self.repos.iter()).enumerate()
+        {
+            for mut hook in self.build_hooks(repo_idx, repo_config, repo)? {
+                let path = match repo_config {
+                    // Hooks with `additional_dependencies` need separate environments.
+                    ConfigRepo::Remote(repo_config) if !hook.additional_dependencies.is_empty() => {
+                        store
+                            .prepare_remote_repo(
+                                repo_config,
+                                &hook.additional_dependencies,
+                                printer,
+                            )
+                            .await
+                            .map_err(Box::new)?
                     }
+                    ConfigRepo::Local(_) if hook.language.environment_dir().is_some() => store
+                        .prepare_local_repo(&hook, &hook.additional_dependencies, printer)
+                        .map_err(Box::new)?,
+                    // Use the shared repo environment, meta hooks run in-process without one.
+                    _ => hook.repo.path().to_path_buf(),
+                };
+                hook = hook.with_path(path);
+                hooks.push(hook);
+            }
+        }
+
+        let files_prefix = self.config.files_prefix(&self.config_path);
+        for hook in &mut hooks {
+            hook.files_prefix.clone_from(&files_prefix);
+        }
+
+        // Resolve the environments referenced by `use_env_of`.
+        for idx in 0..hooks.len() {
+            let Some(target) = hooks[idx].use_env_of.clone() else {
+                continue;
+            };
+            let Some(other) = hooks.iter().find(|hook| hook.id == target) else {
+                return Err(Error::UseEnvOfNotFound {
+                    hook: hooks[idx].id.clone(),
+                    target,
+                });
+            };
+            let Some(bin_dir) = other
+                .environment_dir()
+                .and_then(|env_dir| other.language.env_bin_dir(&env_dir))
+            else {
+                return Err(Error::UseEnvOfNoEnv {
+                    hook: hooks[idx].id.clone(),
+                    target,
+                });
+            };
+            hooks[idx].extra_path = Some(bin_dir);
+        }
+
+        Ok(hooks)
+    }
+}
+
+/// A hook of the config, built without cloning the remote repos.
+pub enum CachedHook {
+    Resolved(Box<Hook>),
+    /// A hook that needs its own repo for its environment, which isn't prepared yet,
+    /// so it has no environment.
+    Unprepared(Box<Hook>),
+    /// A hook of a remote repo that isn't cloned yet, so its manifest is unknown.
+    Unresolved {
+        id: String,
+        repo: String,
+    },
+}
+
+impl Project {
+    /// Build the hooks from the cached remote repos, without cloning or preparing environments.
+    ///
+    /// The hooks of the remote repos that aren't in the store yet are unresolved.
+    pub fn cached_hooks(&self, store: &Store) -> Result<Vec<CachedHook>, Error> {
+        let files_prefix = self.config.files_prefix(&self.config_path);
+        let mut hooks = Vec::new();
+
+        for (repo_idx, repo_config) in self.config.repos.iter().enumerate() {
+            let repo = match repo_config {
+                ConfigRepo::Remote(remote) => {
+                    let Some(repo_path) =
+                        store.cached_remote_repo(remote, &[]).map_err(Box::new)?
+                    else {
+                        hooks.extend(remote.hooks.iter().map(|hook| CachedHook::Unresolved {
+                            id: hook.id.clone(),
+                            repo: format!("{}@{}", git::mask_url(remote.repo.as_str()), remote.rev),
+                        }));
+                        continue;
+                    };
+                    let repo_path = match &remote.path {
+                        Some(path) => repo_path.join(path),
+                        None => repo_path,
+                    };
+                    Repo::remote(
+                        remote.repo.as_str(),
+                        &remote.rev,
+                        &repo_path.to_string_lossy(),
+                    )?
                 }
-                ConfigRepo::Local(repo_config) => {
-                    for hook_config in &repo_config.hooks {
-                        let repo = Rc::clone(repo);
-                        let mut builder = HookBuilder::new(repo, hook_config.clone());
-                        builder.combine(&self.config);
-                        let mut hook = builder.build();
-
-                        // If the hook doesn't need an environment, don't do any preparation.
-                        if hook.language.environment_dir().is_some() {
-                            let path = store
-                                .prepare_local_repo(&hook, &hook.additional_dependencies, printer)
-                                .map_err(Box::new)?;
-
-                            hook = hook.with_path(path);
-                        } else {
-                            // Use the shared repo environment.
-                            let path = hook.repo.path().to_path_buf();
-                            hook = hook.with_path(path);
-                        }
-                        hooks.push(hook);
-                    }
+                ConfigRepo::Local(local) => Repo::local(local.hooks.clone()),
+                ConfigRepo::Meta(_) => Repo::meta(),
+            };
+            let repo = Rc::new(repo);
+            for mut hook in self.build_hooks(repo_idx, repo_config, &repo)? {
+                hook.files_prefix.clone_from(&files_prefix);
+                // Look up the repos `init_hooks` prepares for the environment, without preparing them.
+                let path = match repo_config {
+                    ConfigRepo::Remote(remote) if !hook.additional_dependencies.is_empty() => store
+                        .cached_remote_repo(remote, &hook.additional_dependencies)
+                        .map_err(Box::new)?,
+                    ConfigRepo::Local(_) if hook.language.environment_dir().is_some() => store
+                        .cached_local_repo(&hook.additional_dependencies)
+                        .map_err(Box::new)?,
+                    _ => Some(hook.repo.path().to_path_buf()),
+                };
+                hooks.push(match path {
+                    Some(path) => CachedHook::Resolved(Box::new(hook.with_path(path))),
+                    None => CachedHook::Unprepared(Box::new(hook)),
+                });
+            }
+        }
+
+        Ok(hooks)
+    }
+
+    /// Build the hooks of a repo from the config, without preparing their environments.
+    fn build_hooks(
+        &self,
+        repo_idx: usize,
+        repo_config: &ConfigRepo,
+        repo: &Rc<Repo>,
+    ) -> Result<Vec<Hook>, Error> {
+        let mut hooks = Vec::new();
+        match repo_config {
+            ConfigRepo::Remote(repo_config) => {
+                for (hook_idx, hook_config) in repo_config.hooks.iter().enumerate() {
+                    // Check hook id is valid.
+                    let Some(hook) = repo.get_hook(&hook_config.id) else {
+                        return Err(Error::HookNotFound {
+                            hook: hook_config.id.clone(),
+                            repo: repo.to_string(),
+                        });
+                    };
+                    let mut builder = HookBuilder::from_manifest(
+                        Rc::clone(repo),
+                        (repo_idx, hook_idx),
+                        hook.clone(),
+                    );
+                    builder.update(hook_config);
+                    builder.combine(&self.config);
+                    hooks.push(builder.build()?);
                 }
-                ConfigRepo::Meta(_) => {
-                    todo!()
+            }
+            ConfigRepo::Local(repo_config) => {
+                for (hook_idx, hook_config) in repo_config.hooks.iter().enumerate() {
+                    let mut builder = HookBuilder::new(
+                        Rc::clone(repo),
+                        (repo_idx, hook_idx),
+                        hook_config.clone(),
+                    );
+                    builder.combine(&self.config);
+                    hooks.push(builder.build()?);
+                }
+            }
+            ConfigRepo::Meta(repo_config) => {
+                for (hook_idx, hook_config) in repo_config.hooks.iter().enumerate() {
+                    let Some(hook) = repo.get_hook(hook_config.id.as_str()) else {
+                        return Err(Error::HookNotFound {
+                            hook: hook_config.id.to_string(),
+                            repo: repo.to_string(),
+                        });
+                    };
+                    let mut builder = HookBuilder::from_manifest(
+                        Rc::clone(repo),
+                        (repo_idx, hook_idx),
+                        hook.clone(),
+                    );
+                    builder.combine(&self.config);
+                    hooks.push(builder.build()?);
                 }
             }
         }
-
         Ok(hooks)
     }
 }
 
 struct HookBuilder {
     repo: Rc<Repo>,
+    idx: (usize, usize),
     config: ManifestHook,
+    /// Whether `exclude` is a glob, `config.files_glob` only covers `files`.
+    exclude_glob: Option<bool>,
 }
 
 impl HookBuilder {
-    fn new(repo: Rc<Repo>, config: ManifestHook) -> Self {
-        Self { repo, config }
+    fn new(repo: Rc<Repo>, idx: (usize, usize), config: ManifestHook) -> Self {
+        let exclude_glob = config.files_glob;
+        Self {
+            repo,
+            idx,
+            config,
+            exclude_glob,
+        }
+    }
+
+    /// A hook from a manifest, its patterns don't follow the top-level `files_glob` of the
+    /// config, which is written for the patterns of the config.
+    fn from_manifest(repo: Rc<Repo>, idx: (usize, usize), mut config: ManifestHook) -> Self {
+        config.files_glob.get_or_insert(false);
+        Self::new(repo, idx, config)
     }
 
     /// Update the hook from the project level hook configuration.
@@ -304,19 +472,47 @@ impl HookBuilder {
             types_or,
             exclude_types,
             additional_dependencies,
-            args,
             always_run,
             fail_fast,
             pass_filenames,
+            repo_wide,
             description,
             language_version,
             log_file,
             require_serial,
+            read_only,
             stages,
             verbose,
             minimum_pre_commit_version,
         );
 
+        if let Some(glob) = config.files_glob {
+            self.config.files_glob = Some(glob);
+            self.exclude_glob = Some(glob);
+        } else {
+            // The patterns set in the config follow its top-level `files_glob`.
+            if config.files.is_some() {
+                self.config.files_glob = None;
+            }
+            if config.exclude.is_some() {
+                self.exclude_glob = None;
+            }
+        }
+
+        if let Some(args) = &config.args {
+            match &mut self.config.args {
+                Some(manifest_args) if config.append_args == Some(true) => {
+                    manifest_args.extend(args.iter().cloned());
+                }
+                manifest_args => *manifest_args = Some(args.clone()),
+            }
+        }
+        if let Some(env) = &config.env {
+            self.config
+                .env
+                .get_or_insert_with(HashMap::new)
+                .extend(env.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
         if let Some(name) = &config.name {
             self.config.name.clone_from(name);
         }
@@ -346,6 +542,13 @@ impl HookBuilder {
         if self.config.stages.is_none() {
             self.config.stages.clone_from(&config.default_stages);
         }
+
+        if self.config.files_glob.is_none() {
+            self.config.files_glob = config.files_glob;
+        }
+        if self.exclude_glob.is_none() {
+            self.exclude_glob = config.files_glob;
+        }
     }
 
     /// Fill in the default values for the hook configuration.
@@ -358,10 +561,14 @@ impl HookBuilder {
         self.config.types.get_or_insert(vec!["file".to_string()]);
         self.config.types_or.get_or_insert(Vec::new());
         self.config.exclude_types.get_or_insert(Vec::new());
+        self.config.files_glob.get_or_insert(false);
+        self.exclude_glob.get_or_insert(false);
         self.config.always_run.get_or_insert(false);
         self.config.fail_fast.get_or_insert(false);
         self.config.pass_filenames.get_or_insert(true);
+        self.config.repo_wide.get_or_insert(false);
         self.config.require_serial.get_or_insert(false);
+        self.config.read_only.get_or_insert(false);
         self.config.verbose.get_or_insert(false);
         self.config
             .stages
@@ -389,16 +596,45 @@ impl HookBuilder {
                 );
             }
         }
+
+        if self.config.repo_wide == Some(true) && self.config.pass_filenames == Some(true) {
+            warn_user!(
+                "Hook {} sets repo_wide, so pass_filenames is ignored",
+                self.config.id
+            );
+        }
+
+        if self.config.use_env_of.is_some()
+            && !(language == Language::System && matches!(*self.repo, Repo::Local { .. }))
+        {
+            warn_user!(
+                "Hook {} sets use_env_of, but it is only supported for local system hooks",
+                self.config.id
+            );
+        }
     }
 
     /// Build the hook.
-    fn build(mut self) -> Hook {
+    fn build(mut self) -> Result<Hook, Error> {
         self.check();
         self.fill_in_defaults();
 
-        Hook {
+        if self.config.language == Language::RemoteScript && self.config.checksum.is_none() {
+            return Err(Error::MissingChecksum(self.config.id));
+        }
+
+        // A repo-wide hook runs once, without filenames, so it can't be batched.
+        if self.config.repo_wide == Some(true) {
+            self.config.always_run = Some(true);
+            self.config.pass_filenames = Some(false);
+            self.config.require_serial = Some(true);
+        }
+
+        Ok(Hook {
             repo: self.repo,
+            idx: self.idx,
             path: None,
+            extra_path: None,
             id: self.config.id,
             name: self.config.name,
             entry: self.config.entry,
@@ -406,6 +642,9 @@ impl HookBuilder {
             alias: self.config.alias.expect("alias not set"),
             files: self.config.files,
             exclude: self.config.exclude,
+            files_glob: self.config.files_glob.expect("files_glob not set"),
+            exclude_glob: self.exclude_glob.expect("exclude_glob not set"),
+            files_prefix: None,
             types: self.config.types.expect("types not set"),
             types_or: self.config.types_or.expect("types_or not set"),
             exclude_types: self.config.exclude_types.expect("exclude_types not set"),
@@ -417,6 +656,7 @@ impl HookBuilder {
             always_run: self.config.always_run.expect("always_run not set"),
             fail_fast: self.config.fail_fast.expect("fail_fast not set"),
             pass_filenames: self.config.pass_filenames.expect("pass_filenames not set"),
+            repo_wide: self.config.repo_wide.expect("repo_wide not set"),
             description: self.config.description,
             language_version: self
                 .config
@@ -424,10 +664,14 @@ impl HookBuilder {
                 .expect("language_version not set"),
             log_file: self.config.log_file,
             require_serial: self.config.require_serial.expect("require_serial not set"),
+            read_only: self.config.read_only.expect("read_only not set"),
             stages: self.config.stages.expect("stages not set"),
             verbose: self.config.verbose.expect("verbose not set"),
             minimum_pre_commit_version: self.config.minimum_pre_commit_version,
-        }
+            env: self.config.env.unwrap_or_default(),
+            use_env_of: self.config.use_env_of,
+            checksum: self.config.checksum,
+        })
     }
 }
 
@@ -435,7 +679,11 @@ impl HookBuilder {
 #[derive(Debug, Clone)]
 pub struct Hook {
     repo: Rc<Repo>,
+    /// The index of the repo in the config, and of the hook in the repo.
+    idx: (usize, usize),
     path: Option<PathBuf>,
+    /// The directory of executables from the environment referenced by `use_env_of`.
+    extra_path: Option<PathBuf>,
 
     pub id: String,
     pub name: String,
@@ -443,7 +691,14 @@ pub struct Hook {
     pub language: Language,
     pub alias: String,
     pub files: Option<String>,
-    pub exclude: Option<String>,
+    pub exclude: Option<ExcludePatterns>,
+    /// Whether `files` is a gitignore-style glob.
+    pub files_glob: bool,
+    /// Whether `exclude` is a gitignore-style glob, differs from `files_glob` when only one of
+    /// them is overridden by the config of a remote hook.
+    pub exclude_glob: bool,
+    /// The directory `files` and `exclude` are relative to, if the config sets `files_relative`.
+    pub files_prefix: Option<String>,
     pub types: Vec<String>,
     pub types_or: Vec<String>,
     pub exclude_types: Vec<String>,
@@ -452,13 +707,19 @@ pub struct Hook {
     pub always_run: bool,
     pub fail_fast: bool,
     pub pass_filenames: bool,
+    pub repo_wide: bool,
     pub description: Option<String>,
     pub language_version: String,
     pub log_file: Option<String>,
     pub require_serial: bool,
+    pub read_only: bool,
     pub stages: Vec<Stage>,
     pub verbose: bool,
     pub minimum_pre_commit_version: Option<String>,
+    /// Additional environment variables to set for the hook, see `expanded_env`.
+    pub env: HashMap<String, String>,
+    pub use_env_of: Option<String>,
+    pub checksum: Option<String>,
 }
 
 impl Display for Hook {
@@ -487,31 +748,111 @@ impl Hook {
         self
     }
 
+    /// The `minimum_pre_commit_version` of the hook, if it's newer than prefligit is compatible with.
+    pub fn unsupported_version(&self) -> Option<config::UnsupportedVersion> {
+        self.minimum_pre_commit_version
+            .as_deref()
+            .and_then(|version| config::check_version(version).err())
+    }
+
+    /// Fail if the hook requires a newer pre-commit than prefligit is compatible with.
+    pub fn check_version(&self) -> Result<(), Error> {
+        match self.unsupported_version() {
+            Some(version) => Err(Error::UnsupportedVersion {
+                hook: self.id.clone(),
+                version,
+            }),
+            None => Ok(()),
+        }
+    }
+
     pub fn repo(&self) -> &Repo {
         &self.repo
     }
 
+    /// The index of the repo in the config, and of the hook in the repo.
+    pub fn idx(&self) -> (usize, usize) {
+        self.idx
+    }
+
+    /// The `additional_dependencies` with `${VAR}` references expanded from the environment.
+    ///
+    /// Only pass these to the installer: the unexpanded form is what gets displayed and keyed on,
+    /// so secrets never end up in the store.
+    pub fn install_dependencies(&self) -> Result<Vec<String>, Error> {
+        self.additional_dependencies
+            .iter()
+            .map(|dep| {
+                expand_env_vars(dep).map_err(|var| Error::UndefinedVariable {
+                    hook: self.id.clone(),
+                    dependency: dep.clone(),
+                    var,
+                })
+            })
+            .collect()
+    }
+
+    /// The `env` with `${VAR}` references expanded from the environment.
+    ///
+    /// It's expanded when the hook runs, so an undefined variable doesn't fail the hooks that
+    /// are skipped or not selected.
+    pub fn expanded_env(&self) -> Result<HashMap<String, String>, Error> {
+        self.env
+            .iter()
+            .map(|(key, value)| {
+                let value = expand_env_vars(value).map_err(|var| Error::UndefinedEnvVariable {
+                    hook: self.id.clone(),
+                    key: key.clone(),
+                    var,
+                })?;
+                Ok((key.clone(), value))
+            })
+            .collect()
+    }
+
     /// Get the working directory for the hook.
     pub fn path(&self) -> &Path {
         self.path.as_deref().unwrap_or_else(|| self.repo.path())
     }
 
+    /// Get the extra directory to put on `PATH`, from the environment referenced by `use_env_of`.
+    pub fn extra_path(&self) -> Option<&Path> {
+        self.extra_path.as_deref()
+    }
+
     /// Get the environment directory that the hook will be installed to.
+    ///
+    /// It lives in the store, keyed on the repo, revision and dependencies, never on the project,
+    /// so projects with the same hooks share their environments.
     pub fn environment_dir(&self) -> Option<PathBuf> {
         let env_dir = self.language.environment_dir()?;
-        Some(
-            self.path()
-                .join(format!("{}-{}", env_dir, &self.language_version)),
-        )
+        // Downloaded scripts are cached by their checksum.
+        let version = match &self.checksum {
+            Some(checksum) if self.language == Language::RemoteScript => checksum,
+            _ => &self.language_version,
+        };
+        Some(self.path().join(format!("{env_dir}-{version}")))
+    }
+
+    /// Whether the hook never modifies files, as declared by `read_only` or by its nature.
+    ///
+    /// Running hooks concurrently is opt-in: `read_only` is a prefligit extension that
+    /// pre-commit configs and manifests don't set, and nothing tells a hook that only checks
+    /// files apart from a fixer, so other hooks are assumed to modify files.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+            || matches!(self.language, Language::Fail | Language::Pygrep)
+            || matches!(*self.repo, Repo::Meta { .. })
     }
 
     pub fn install_key(&self) -> String {
         format!(
-            "{}-{}-{}-{}",
+            "{}-{}-{}-{}-{}",
             self.repo,
             self.language,
             self.language_version,
-            self.additional_dependencies.join(",")
+            self.additional_dependencies.join(","),
+            self.checksum.as_deref().unwrap_or_default()
         )
     }
 
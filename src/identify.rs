@@ -635,26 +635,58 @@ fn is_encoding_tag(tag: &str) -> bool {
     matches!(tag, tags::TEXT | tags::BINARY)
 }
 
-pub fn tags_from_path(path: &Path) -> Result<Vec<&str>> {
-    let metadata = std::fs::metadata(path)?;
+/// Whether `tag` is the kind of a file, which is all [`file_kind_tag`] detects.
+pub fn is_file_kind_tag(tag: &str) -> bool {
+    matches!(
+        tag,
+        tags::DIRECTORY
+            | tags::SYMLINK
+            | tags::SOCKET
+            | tags::FIFO
+            | tags::BLOCK_DEVICE
+            | tags::CHARACTER_DEVICE
+            | tags::FILE
+    )
+}
+
+/// The tag of the kind of the file at `path`, like `file` or `directory`.
+///
+/// Only reads the metadata of the file, unlike [`tags_from_path`].
+pub fn file_kind_tag(path: &Path) -> Result<&'static str> {
+    // Don't follow symlinks, a symlink is tagged as `symlink` regardless of its target.
+    let metadata = std::fs::symlink_metadata(path)?;
+    Ok(file_kind_tag_from_metadata(&metadata))
+}
+
+fn file_kind_tag_from_metadata(metadata: &std::fs::Metadata) -> &'static str {
     if metadata.is_dir() {
-        return Ok(vec![tags::DIRECTORY]);
+        return tags::DIRECTORY;
     } else if metadata.is_symlink() {
-        return Ok(vec![tags::SYMLINK]);
+        return tags::SYMLINK;
     }
     #[cfg(unix)]
     {
         let file_type = metadata.file_type();
         if file_type.is_socket() {
-            return Ok(vec![tags::SOCKET]);
+            return tags::SOCKET;
         } else if file_type.is_fifo() {
-            return Ok(vec![tags::FIFO]);
+            return tags::FIFO;
         } else if file_type.is_block_device() {
-            return Ok(vec![tags::BLOCK_DEVICE]);
+            return tags::BLOCK_DEVICE;
         } else if file_type.is_char_device() {
-            return Ok(vec![tags::CHARACTER_DEVICE]);
+            return tags::CHARACTER_DEVICE;
         }
     };
+    tags::FILE
+}
+
+pub fn tags_from_path(path: &Path) -> Result<Vec<&'static str>> {
+    // Don't follow symlinks, a symlink is tagged as `symlink` regardless of its target.
+    let metadata = std::fs::symlink_metadata(path)?;
+    let kind = file_kind_tag_from_metadata(&metadata);
+    if kind != tags::FILE {
+        return Ok(vec![kind]);
+    }
 
     let mut tags = HashSet::new();
     tags.insert(tags::FILE);
@@ -673,8 +705,11 @@ pub fn tags_from_path(path: &Path) -> Result<Vec<&str>> {
         tags.insert(tags::NON_EXECUTABLE);
     }
 
-    tags.extend(tags_from_filename(path));
-    if executable {
+    let filename_tags = tags_from_filename(path);
+    // Scripts without an extension are identified by their shebang, executable or not.
+    let identify_by_shebang = executable || filename_tags.is_empty();
+    tags.extend(filename_tags);
+    if identify_by_shebang {
         if let Ok(shebang) = parse_shebang(path) {
             tags.extend(tags_from_interpreter(&shebang));
         }
@@ -691,7 +726,7 @@ pub fn tags_from_path(path: &Path) -> Result<Vec<&str>> {
     Ok(tags.into_iter().collect())
 }
 
-fn tags_from_filename(filename: &Path) -> Vec<&str> {
+fn tags_from_filename(filename: &Path) -> Vec<&'static str> {
     let ext = filename.extension().and_then(|ext| ext.to_str());
     let filename = filename
         .file_name()
@@ -721,8 +756,22 @@ fn tags_from_filename(filename: &Path) -> Vec<&str> {
     result.into_iter().collect()
 }
 
-fn tags_from_interpreter(_interpreter: &[String]) -> Vec<&'static str> {
-    todo!()
+fn tags_from_interpreter(interpreter: &[String]) -> Vec<&'static str> {
+    let Some(interpreter) = interpreter.first() else {
+        return vec![];
+    };
+    let mut name = interpreter
+        .rsplit_once('/')
+        .map_or(interpreter.as_str(), |(_, name)| name);
+
+    // Try `python3.12.1` => `python3.12` => `python3` until one matches.
+    while !name.is_empty() {
+        if let Some(tags) = by_interpreter().get(name) {
+            return tags.clone();
+        }
+        name = name.rsplit_once('.').map_or("", |(name, _)| name);
+    }
+    vec![]
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -748,6 +797,8 @@ fn parse_shebang(path: &Path) -> Result<Vec<String>, ShebangError> {
         return Err(ShebangError::NoShebang);
     }
 
+    let line = line.trim_end_matches(['\r', '\n']);
+
     // Require only printable ASCII
     if line.bytes().any(|b| !(0x20..=0x7E).contains(&b)) {
         return Err(ShebangError::NonPrintableChars);
@@ -811,4 +862,52 @@ mod tests {
         let tags = super::tags_from_filename(Path::new("data.json"));
         assert_eq!(tags, vec!["json", "text"]);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn tags_from_path() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("test.py");
+        fs_err::write(&file, "print('hello')\n")?;
+        let link = dir.path().join("link.py");
+        std::os::unix::fs::symlink(&file, &link)?;
+
+        let mut tags = super::tags_from_path(&file)?;
+        tags.sort_unstable();
+        assert_eq!(tags, vec!["file", "non-executable", "python", "text"]);
+        // A symlink is not followed to its target.
+        assert_eq!(super::tags_from_path(&link)?, vec!["symlink"]);
+        assert_eq!(super::tags_from_path(dir.path())?, vec!["directory"]);
+
+        // The kind of a file agrees with the full tags.
+        assert_eq!(super::file_kind_tag(&file)?, "file");
+        assert_eq!(super::file_kind_tag(&link)?, "symlink");
+        assert_eq!(super::file_kind_tag(dir.path())?, "directory");
+
+        // Extensionless scripts are identified by their shebang.
+        let script = dir.path().join("script");
+        fs_err::write(&script, "#!/usr/bin/env python3.12\nprint('hello')\n")?;
+        let mut tags = super::tags_from_path(&script)?;
+        tags.sort_unstable();
+        assert_eq!(
+            tags,
+            vec!["file", "non-executable", "python", "python3", "text"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tags_from_interpreter() {
+        let interpreter = |cmd: &[&str]| {
+            super::tags_from_interpreter(&cmd.iter().map(ToString::to_string).collect::<Vec<_>>())
+        };
+        assert_eq!(interpreter(&["/bin/sh"]), vec!["shell", "sh"]);
+        assert_eq!(
+            interpreter(&["python3.12.1", "-u"]),
+            vec!["python", "python3"]
+        );
+        assert!(interpreter(&["unknown"]).is_empty());
+        assert!(interpreter(&[]).is_empty());
+    }
 }
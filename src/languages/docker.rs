@@ -11,9 +11,9 @@ use tracing::trace;
 
 use crate::fs::CWD;
 use crate::hook::Hook;
-use crate::languages::{LanguageImpl, DEFAULT_VERSION};
+use crate::languages::{InstallOptions, LanguageImpl, DEFAULT_VERSION};
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{run_by_batch, BatchOptions};
 
 const PRE_COMMIT_LABEL: &str = "PRE_COMMIT";
 
@@ -21,14 +21,36 @@ const PRE_COMMIT_LABEL: &str = "PRE_COMMIT";
 pub struct Docker;
 
 impl Docker {
+    /// The tag of the image of the hook, keyed on its repo and build args, so hooks of the same
+    /// repo with different build args don't share an image.
     fn docker_tag(hook: &Hook) -> Option<String> {
-        hook.path()
-            .file_name()
-            .and_then(OsStr::to_str)
-            .map(|s| format!("pre-commit-{:x}", md5::compute(s)))
+        let mut key = hook.path().file_name().and_then(OsStr::to_str)?.to_string();
+        for arg in &hook.additional_dependencies {
+            key.push('\0');
+            key.push_str(arg);
+        }
+        Some(format!("pre-commit-{:x}", md5::compute(key)))
+    }
+
+    /// The `additional_dependencies` of a docker hook are build args of its image, `KEY=VALUE`,
+    /// or `KEY` to take the value from the environment.
+    fn build_args(hook: &Hook) -> Result<Vec<String>> {
+        for arg in &hook.additional_dependencies {
+            let key = arg.split_once('=').map_or(arg.as_str(), |(key, _)| key);
+            let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                anyhow::bail!(
+                    "Invalid build arg `{arg}` in the `additional_dependencies` of hook `{}`, expected `KEY=VALUE` or `KEY`",
+                    hook.id
+                );
+            }
+        }
+        Ok(hook.install_dependencies()?)
     }
 
     async fn build_docker_image(hook: &Hook, pull: bool) -> Result<()> {
+        let build_args = Self::build_args(hook)?;
         let mut cmd = Cmd::new("docker", "build docker image");
 
         let cmd = cmd
@@ -41,6 +63,9 @@ impl Docker {
         if pull {
             cmd.arg("--pull");
         }
+        for arg in build_args {
+            cmd.arg("--build-arg").arg(arg);
+        }
 
         // This must come last for old versions of docker.
         // see https://github.com/pre-commit/pre-commit/issues/477
@@ -126,11 +151,15 @@ impl Docker {
         Ok(Cow::Borrowed(path))
     }
 
-    pub(crate) async fn docker_cmd() -> Result<Cmd> {
+    /// The `docker run` command, with the `env` of the hook set in the container.
+    pub(crate) async fn docker_cmd(
+        env: &HashMap<String, String>,
+        color: ColorChoice,
+    ) -> Result<Cmd> {
         let mut command = Cmd::new("docker", "run container");
         command.arg("run").arg("--rm");
 
-        match ColorChoice::global() {
+        match color {
             ColorChoice::Always | ColorChoice::AlwaysAnsi => {
                 command.arg("--tty");
             }
@@ -150,12 +179,16 @@ impl Docker {
             .arg("-v")
             // https://docs.docker.com/engine/reference/commandline/run/#mount-volumes-from-container-volumes-from
             .arg(format!(
-                "{}:/src:ro,Z",
+                "{}:/src:rw,Z",
                 Self::get_docker_path(&CWD.to_string_lossy()).await?
             ))
             .arg("--workdir")
             .arg("/src");
 
+        for (key, value) in env {
+            command.arg("-e").arg(format!("{key}={value}"));
+        }
+
         Ok(command)
     }
 }
@@ -169,7 +202,7 @@ impl LanguageImpl for Docker {
         Some("docker")
     }
 
-    async fn install(&self, hook: &Hook) -> Result<()> {
+    async fn install(&self, hook: &Hook, _options: InstallOptions) -> Result<()> {
         let env = hook.environment_dir().expect("No environment dir found");
 
         Docker::build_docker_image(hook, true).await?;
@@ -177,15 +210,16 @@ impl LanguageImpl for Docker {
         Ok(())
     }
 
-    async fn check_health(&self) -> Result<()> {
-        todo!()
+    async fn check_health(&self, _hook: &Hook) -> Result<()> {
+        Ok(())
     }
 
     async fn run(
         &self,
         hook: &Hook,
         filenames: &[&String],
-        env_vars: Arc<HashMap<&'static str, String>>,
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
     ) -> Result<(i32, Vec<u8>)> {
         Docker::build_docker_image(hook, false).await?;
 
@@ -195,16 +229,19 @@ impl LanguageImpl for Docker {
 
         let cmds = Arc::new(cmds);
         let hook_args = Arc::new(hook.args.clone());
+        let hook_env = Arc::new(hook.expanded_env()?);
+        let color = options.color;
 
         let run = move |batch: Vec<String>| {
             let cmds = cmds.clone();
             let docker_tag = docker_tag.clone();
             let hook_args = hook_args.clone();
             let env_vars = env_vars.clone();
+            let hook_env = hook_env.clone();
 
             async move {
                 // docker run [OPTIONS] IMAGE [COMMAND] [ARG...]
-                let mut cmd = Docker::docker_cmd().await?;
+                let mut cmd = Docker::docker_cmd(&hook_env, color).await?;
                 let cmd = cmd
                     .arg("--entrypoint")
                     .arg(&cmds[0])
@@ -222,7 +259,7 @@ impl LanguageImpl for Docker {
             }
         };
 
-        let results = run_by_batch(hook, filenames, run).await?;
+        let results = run_by_batch(hook, filenames, options, run).await?;
 
         // Collect results
         let mut combined_status = 0;
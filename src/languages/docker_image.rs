@@ -3,8 +3,8 @@ use std::sync::Arc;
 
 use crate::hook::Hook;
 use crate::languages::docker::Docker;
-use crate::languages::{LanguageImpl, DEFAULT_VERSION};
-use crate::run::run_by_batch;
+use crate::languages::{InstallOptions, LanguageImpl, DEFAULT_VERSION};
+use crate::run::{run_by_batch, BatchOptions};
 
 #[derive(Debug, Copy, Clone)]
 pub struct DockerImage;
@@ -18,32 +18,36 @@ impl LanguageImpl for DockerImage {
         None
     }
 
-    async fn install(&self, _: &Hook) -> anyhow::Result<()> {
+    async fn install(&self, _: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
         Ok(())
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
-        todo!()
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
+        Ok(())
     }
 
     async fn run(
         &self,
         hook: &Hook,
         filenames: &[&String],
-        env_vars: Arc<HashMap<&'static str, String>>,
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
     ) -> anyhow::Result<(i32, Vec<u8>)> {
         let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
 
         let cmds = Arc::new(cmds);
         let hook_args = Arc::new(hook.args.clone());
+        let hook_env = Arc::new(hook.expanded_env()?);
+        let color = options.color;
 
         let run = move |batch: Vec<String>| {
             let cmds = cmds.clone();
             let hook_args = hook_args.clone();
             let env_vars = env_vars.clone();
+            let hook_env = hook_env.clone();
 
             async move {
-                let mut cmd = Docker::docker_cmd().await?;
+                let mut cmd = Docker::docker_cmd(&hook_env, color).await?;
                 let cmd = cmd
                     .args(&cmds[..])
                     .args(hook_args.as_ref())
@@ -58,7 +62,7 @@ impl LanguageImpl for DockerImage {
             }
         };
 
-        let results = run_by_batch(hook, filenames, run).await?;
+        let results = run_by_batch(hook, filenames, options, run).await?;
 
         // Collect results
         let mut combined_status = 0;
@@ -1,7 +1,8 @@
 use std::{collections::HashMap, sync::Arc};
 
 use crate::hook::Hook;
-use crate::languages::{LanguageImpl, DEFAULT_VERSION};
+use crate::languages::{InstallOptions, LanguageImpl, DEFAULT_VERSION};
+use crate::run::BatchOptions;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Fail;
@@ -15,11 +16,11 @@ impl LanguageImpl for Fail {
         None
     }
 
-    async fn install(&self, _hook: &Hook) -> anyhow::Result<()> {
+    async fn install(&self, _hook: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
         Ok(())
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
         Ok(())
     }
 
@@ -27,7 +28,8 @@ impl LanguageImpl for Fail {
         &self,
         hook: &Hook,
         filenames: &[&String],
-        _env_vars: Arc<HashMap<&'static str, String>>,
+        _env_vars: Arc<HashMap<String, String>>,
+        _options: BatchOptions<'_>,
     ) -> anyhow::Result<(i32, Vec<u8>)> {
         let mut out = hook.entry.as_bytes().to_vec();
         out.extend(b"\n\n");
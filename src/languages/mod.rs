@@ -1,16 +1,28 @@
 use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
+use tracing::trace;
 
+use crate::cli::PythonInstaller;
 use crate::config::Language;
+use crate::fs::LockedFile;
 use crate::hook::Hook;
+use crate::process::Cmd;
+use crate::run::BatchOptions;
+use crate::store::Store;
 
 mod docker;
 mod docker_image;
 mod fail;
 mod node;
+mod pygrep;
 mod python;
+mod remote_script;
+mod ruby;
 mod system;
 
 static PYTHON: python::Python = python::Python;
@@ -19,19 +31,39 @@ static SYSTEM: system::System = system::System;
 static FAIL: fail::Fail = fail::Fail;
 static DOCKER: docker::Docker = docker::Docker;
 static DOCKER_IMAGE: docker_image::DockerImage = docker_image::DockerImage;
+static REMOTE_SCRIPT: remote_script::RemoteScript = remote_script::RemoteScript;
+static RUBY: ruby::Ruby = ruby::Ruby;
+static PYGREP: pygrep::Pygrep = pygrep::Pygrep;
 
 pub const DEFAULT_VERSION: &str = "default";
 
+/// How the environments of hooks are installed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstallOptions {
+    /// The installer of python environments, `uv` with a fallback to `pip` if not set.
+    pub python_installer: Option<PythonInstaller>,
+}
+
 trait LanguageImpl {
     fn default_version(&self) -> &str;
     fn environment_dir(&self) -> Option<&str>;
-    async fn install(&self, hook: &Hook) -> Result<()>;
-    async fn check_health(&self) -> Result<()>;
+    /// The directory of executables in an installed environment.
+    fn env_bin_dir(&self, _env_dir: &Path) -> Option<PathBuf> {
+        None
+    }
+    /// The dependencies installed in an environment, like `name==version` or `name@version`.
+    fn installed_dependencies(&self, _env_dir: &Path) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+    async fn install(&self, hook: &Hook, options: InstallOptions) -> Result<()>;
+    /// Check that the installed environment of the hook is usable.
+    async fn check_health(&self, hook: &Hook) -> Result<()>;
     async fn run(
         &self,
         hook: &Hook,
         filenames: &[&String],
-        env_vars: Arc<HashMap<&'static str, String>>,
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
     ) -> Result<(i32, Vec<u8>)>;
 }
 
@@ -40,10 +72,13 @@ impl Language {
         match self {
             Self::Python => PYTHON.default_version(),
             Self::Node => NODE.default_version(),
+            Self::Ruby => RUBY.default_version(),
             Self::System => SYSTEM.default_version(),
             Self::Fail => FAIL.default_version(),
             Self::Docker => DOCKER.default_version(),
             Self::DockerImage => DOCKER_IMAGE.default_version(),
+            Self::RemoteScript => REMOTE_SCRIPT.default_version(),
+            Self::Pygrep => PYGREP.default_version(),
             _ => todo!(),
         }
     }
@@ -52,34 +87,73 @@ impl Language {
         match self {
             Self::Python => PYTHON.environment_dir(),
             Self::Node => NODE.environment_dir(),
+            Self::Ruby => RUBY.environment_dir(),
             Self::System => SYSTEM.environment_dir(),
             Self::Fail => FAIL.environment_dir(),
             Self::Docker => DOCKER.environment_dir(),
             Self::DockerImage => DOCKER_IMAGE.environment_dir(),
+            Self::RemoteScript => REMOTE_SCRIPT.environment_dir(),
+            Self::Pygrep => PYGREP.environment_dir(),
             _ => todo!(),
         }
     }
 
-    pub async fn install(&self, hook: &Hook) -> Result<()> {
+    pub fn env_bin_dir(self, env_dir: &Path) -> Option<PathBuf> {
         match self {
-            Self::Python => PYTHON.install(hook).await,
-            Self::Node => NODE.install(hook).await,
-            Self::System => SYSTEM.install(hook).await,
-            Self::Fail => FAIL.install(hook).await,
-            Self::Docker => DOCKER.install(hook).await,
-            Self::DockerImage => DOCKER_IMAGE.install(hook).await,
+            Self::Python => PYTHON.env_bin_dir(env_dir),
+            Self::Node => NODE.env_bin_dir(env_dir),
+            Self::Ruby => RUBY.env_bin_dir(env_dir),
+            Self::System => SYSTEM.env_bin_dir(env_dir),
+            Self::Fail => FAIL.env_bin_dir(env_dir),
+            Self::Docker => DOCKER.env_bin_dir(env_dir),
+            Self::DockerImage => DOCKER_IMAGE.env_bin_dir(env_dir),
+            Self::RemoteScript => REMOTE_SCRIPT.env_bin_dir(env_dir),
+            Self::Pygrep => PYGREP.env_bin_dir(env_dir),
             _ => todo!(),
         }
     }
 
-    pub async fn check_health(&self) -> Result<()> {
+    pub async fn install(&self, hook: &Hook, options: InstallOptions) -> Result<()> {
         match self {
-            Self::Python => PYTHON.check_health().await,
-            Self::Node => NODE.check_health().await,
-            Self::System => SYSTEM.check_health().await,
-            Self::Fail => FAIL.check_health().await,
-            Self::Docker => DOCKER.check_health().await,
-            Self::DockerImage => DOCKER_IMAGE.check_health().await,
+            Self::Python => PYTHON.install(hook, options).await,
+            Self::Node => NODE.install(hook, options).await,
+            Self::Ruby => RUBY.install(hook, options).await,
+            Self::System => SYSTEM.install(hook, options).await,
+            Self::Fail => FAIL.install(hook, options).await,
+            Self::Docker => DOCKER.install(hook, options).await,
+            Self::DockerImage => DOCKER_IMAGE.install(hook, options).await,
+            Self::RemoteScript => REMOTE_SCRIPT.install(hook, options).await,
+            Self::Pygrep => PYGREP.install(hook, options).await,
+            _ => todo!(),
+        }
+    }
+
+    pub fn installed_dependencies(self, env_dir: &Path) -> Result<Vec<String>> {
+        match self {
+            Self::Python => PYTHON.installed_dependencies(env_dir),
+            Self::Node => NODE.installed_dependencies(env_dir),
+            Self::Ruby => RUBY.installed_dependencies(env_dir),
+            Self::System => SYSTEM.installed_dependencies(env_dir),
+            Self::Fail => FAIL.installed_dependencies(env_dir),
+            Self::Docker => DOCKER.installed_dependencies(env_dir),
+            Self::DockerImage => DOCKER_IMAGE.installed_dependencies(env_dir),
+            Self::RemoteScript => REMOTE_SCRIPT.installed_dependencies(env_dir),
+            Self::Pygrep => PYGREP.installed_dependencies(env_dir),
+            _ => todo!(),
+        }
+    }
+
+    pub async fn check_health(&self, hook: &Hook) -> Result<()> {
+        match self {
+            Self::Python => PYTHON.check_health(hook).await,
+            Self::Node => NODE.check_health(hook).await,
+            Self::Ruby => RUBY.check_health(hook).await,
+            Self::System => SYSTEM.check_health(hook).await,
+            Self::Fail => FAIL.check_health(hook).await,
+            Self::Docker => DOCKER.check_health(hook).await,
+            Self::DockerImage => DOCKER_IMAGE.check_health(hook).await,
+            Self::RemoteScript => REMOTE_SCRIPT.check_health(hook).await,
+            Self::Pygrep => PYGREP.check_health(hook).await,
             _ => todo!(),
         }
     }
@@ -88,16 +162,231 @@ impl Language {
         &self,
         hook: &Hook,
         filenames: &[&String],
-        env_vars: Arc<HashMap<&'static str, String>>,
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
     ) -> Result<(i32, Vec<u8>)> {
         match self {
-            Self::Python => PYTHON.run(hook, filenames, env_vars).await,
-            Self::Node => NODE.run(hook, filenames, env_vars).await,
-            Self::System => SYSTEM.run(hook, filenames, env_vars).await,
-            Self::Fail => FAIL.run(hook, filenames, env_vars).await,
-            Self::Docker => DOCKER.run(hook, filenames, env_vars).await,
-            Self::DockerImage => DOCKER_IMAGE.run(hook, filenames, env_vars).await,
+            Self::Python => PYTHON.run(hook, filenames, env_vars, options).await,
+            Self::Node => NODE.run(hook, filenames, env_vars, options).await,
+            Self::Ruby => RUBY.run(hook, filenames, env_vars, options).await,
+            Self::System => SYSTEM.run(hook, filenames, env_vars, options).await,
+            Self::Fail => FAIL.run(hook, filenames, env_vars, options).await,
+            Self::Docker => DOCKER.run(hook, filenames, env_vars, options).await,
+            Self::DockerImage => DOCKER_IMAGE.run(hook, filenames, env_vars, options).await,
+            Self::RemoteScript => REMOTE_SCRIPT.run(hook, filenames, env_vars, options).await,
+            Self::Pygrep => PYGREP.run(hook, filenames, env_vars, options).await,
             _ => todo!(),
         }
     }
 }
+
+/// Resolve the executable of a hook entry.
+///
+/// On Windows, the executable is looked up in `path` (or `PATH`) with the extensions in
+/// `PATHEXT`, so an entry like `black` finds `black.exe` or a `black.cmd` shim. Elsewhere,
+/// the entry is left for the OS to resolve.
+pub(crate) fn resolve_entry(entry: &str, path: Option<&OsStr>) -> PathBuf {
+    if !cfg!(windows) {
+        return PathBuf::from(entry);
+    }
+
+    let path = path
+        .map(OsStr::to_os_string)
+        .or_else(|| std::env::var_os("PATH"));
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| which::which_in(entry, path, cwd).ok())
+        .unwrap_or_else(|| PathBuf::from(entry))
+}
+
+/// Prepend `dirs` to `PATH`.
+pub(crate) fn join_path(dirs: &[PathBuf]) -> Result<OsString> {
+    Ok(std::env::join_paths(
+        dirs.iter().cloned().chain(
+            std::env::var_os("PATH")
+                .as_ref()
+                .iter()
+                .flat_map(std::env::split_paths),
+        ),
+    )?)
+}
+
+/// The lowercase hex SHA-256 digest of `content`.
+pub(crate) fn sha256_hex(content: &[u8]) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, content);
+    digest.as_ref().iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// Whether `version` (like `3.12.7`) satisfies `spec` (like `3`, `3.12` or `3.12.7`).
+pub(crate) fn version_matches(spec: &str, version: &str) -> bool {
+    version == spec
+        || version
+            .strip_prefix(spec)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Parse a stable version like `3.12.7` to compare numerically, `None` for prereleases.
+pub(crate) fn version_key(version: &str) -> Option<Vec<u64>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Find the directory of `program` in `PATH`.
+///
+/// With a `spec`, the version printed by running `program` with `version_args` must satisfy it,
+/// otherwise any version is accepted.
+async fn find_system_toolchain(
+    program: &str,
+    version_args: &[&str],
+    spec: Option<&str>,
+) -> Result<Option<PathBuf>> {
+    let Ok(executable) = which::which(program) else {
+        return Ok(None);
+    };
+    let Some(bin) = executable.parent().map(Path::to_path_buf) else {
+        return Ok(None);
+    };
+    let Some(spec) = spec else {
+        return Ok(Some(bin));
+    };
+
+    let output = Cmd::new(&executable, format!("get {program} version"))
+        .args(version_args)
+        .check(true)
+        .output()
+        .await?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    let version = version.trim().trim_start_matches('v');
+    Ok(version_matches(spec, version).then_some(bin))
+}
+
+/// Find the latest toolchain in `toolchains_dir` matching `spec`, where each toolchain is
+/// installed in a directory named after its version.
+fn find_installed_toolchain(toolchains_dir: &Path, spec: &str) -> Result<Option<PathBuf>> {
+    if !toolchains_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let mut versions = Vec::new();
+    for entry in fs_err::read_dir(toolchains_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && version_matches(spec, &name) {
+            versions.push(name);
+        }
+    }
+
+    Ok(versions
+        .into_iter()
+        .max_by_key(|version| version_key(version))
+        .map(|version| toolchains_dir.join(version)))
+}
+
+/// A toolchain used from `PATH` if it matches, or otherwise downloaded into the store by
+/// `prefligit`, like Node.js or Ruby.
+trait ManagedToolchain {
+    /// The name of the executable, like `node`.
+    const PROGRAM: &'static str;
+    /// The arguments that make the executable print its version.
+    const VERSION_ARGS: &'static [&'static str];
+    /// The prefix a `language_version` may have, like `v` in `v20.11.1`.
+    const VERSION_PREFIX: &'static str;
+    /// The version to install for `default` when there is none in `PATH`.
+    const PINNED_VERSION: &'static str;
+
+    /// The directory of the installed toolchains, one per version.
+    fn toolchains_dir(store: &Store) -> PathBuf;
+    /// The directory of the executables in an installed toolchain.
+    fn bin_dir(toolchain: &Path) -> PathBuf {
+        toolchain.join("bin")
+    }
+    /// Resolve `spec` to the latest matching release.
+    async fn resolve_version(spec: &str) -> Result<String>;
+    /// Download, verify and extract the release `version` into `toolchains_dir/version`.
+    async fn install(toolchains_dir: &Path, version: &str) -> Result<()>;
+}
+
+/// Find or install a toolchain matching `language_version`, returning its `bin` directory.
+///
+/// `language_version` is one of:
+/// - `default`: the executable from `PATH`, or a managed install of the pinned version.
+/// - `system`: the executable from `PATH`.
+/// - a version like `3`, `3.3` or `3.3.6`: the executable from `PATH` if it matches,
+///   otherwise a managed install of the latest matching release.
+async fn ensure_toolchain<T: ManagedToolchain>(language_version: &str) -> Result<PathBuf> {
+    let program = T::PROGRAM;
+    let spec = match language_version {
+        DEFAULT_VERSION | "system" => None,
+        version => Some(version.trim_start_matches(T::VERSION_PREFIX)),
+    };
+
+    // 1) Check the executable from `PATH`.
+    if let Some(bin) = find_system_toolchain(program, T::VERSION_ARGS, spec).await? {
+        trace!(bin = %bin.display(), "Found {program} from PATH");
+        return Ok(bin);
+    }
+    if language_version == "system" {
+        anyhow::bail!("Failed to find `{program}` in `PATH`");
+    }
+    let spec = spec.unwrap_or(T::PINNED_VERSION);
+
+    // 2) Check the toolchains installed by `prefligit`, again once locked as another process
+    // may have installed it meanwhile.
+    let store = Store::from_settings()?;
+    let toolchains_dir = T::toolchains_dir(&store);
+    let find_installed = || -> Result<_> {
+        let toolchain = find_installed_toolchain(&toolchains_dir, spec)?;
+        Ok(toolchain.map(|toolchain| T::bin_dir(&toolchain)))
+    };
+    if let Some(bin) = find_installed()? {
+        trace!(bin = %bin.display(), "Found managed {program}");
+        return Ok(bin);
+    }
+
+    fs_err::create_dir_all(&toolchains_dir)?;
+    let _lock = LockedFile::acquire(toolchains_dir.join(".lock"), program).await?;
+
+    if let Some(bin) = find_installed()? {
+        trace!(bin = %bin.display(), "Found managed {program}");
+        return Ok(bin);
+    }
+
+    // 3) Download and install the latest matching release.
+    let version = T::resolve_version(spec).await?;
+    T::install(&toolchains_dir, &version).await?;
+    Ok(T::bin_dir(&toolchains_dir.join(&version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_matches() {
+        assert!(version_matches("20", "20.11.1"));
+        assert!(version_matches("20.11", "20.11.1"));
+        assert!(version_matches("20.11.1", "20.11.1"));
+        assert!(!version_matches("2", "20.11.1"));
+        assert!(!version_matches("20.1", "20.11.1"));
+        assert!(!version_matches("18", "20.11.1"));
+        assert!(!version_matches("3.3.1", "3.3.10"));
+    }
+
+    #[test]
+    fn test_version_key() {
+        assert!(version_key("20.11.1") > version_key("20.9.0"));
+        assert!(version_key("22.0.0") > version_key("20.11.1"));
+        assert!(version_key("3.10.15") > version_key("3.9.20"));
+        assert!(version_key("3.4.0-preview1").is_none());
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::hook::Hook;
+use crate::languages::node::installer::ensure_node;
+use crate::languages::{join_path, resolve_entry, InstallOptions, LanguageImpl, DEFAULT_VERSION};
+use crate::process::Cmd;
+use crate::run::{run_by_batch, BatchOptions};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Node;
+
+impl LanguageImpl for Node {
+    fn default_version(&self) -> &str {
+        DEFAULT_VERSION
+    }
+
+    fn environment_dir(&self) -> Option<&str> {
+        Some("node_env")
+    }
+
+    fn env_bin_dir(&self, env_dir: &Path) -> Option<PathBuf> {
+        Some(bin_dir(env_dir))
+    }
+
+    async fn install(&self, hook: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        let node_bin = ensure_node(&hook.language_version).await?;
+        debug!(node = %node_bin.display(), "Installing environment with node");
+
+        fs_err::create_dir_all(&env)?;
+
+        // The environment is a global npm prefix, with the hook repo (if it's a package) and
+        // the additional dependencies installed into its `node_modules`.
+        let mut packages = Vec::new();
+        if hook.path().join("package.json").is_file() {
+            packages.push(".".to_string());
+        }
+        packages.extend(hook.install_dependencies()?);
+        if packages.is_empty() {
+            return Ok(());
+        }
+
+        let path = join_path(&[bin_dir(&env), node_bin.clone()])?;
+        Cmd::new(resolve_entry("npm", Some(&path)), "install dependencies")
+            .arg("install")
+            .arg("--global")
+            .arg("--no-progress")
+            .arg("--no-save")
+            .arg("--no-fund")
+            .arg("--no-audit")
+            .args(&packages)
+            .current_dir(hook.path())
+            .env("PATH", &path)
+            .env("NPM_CONFIG_PREFIX", &env)
+            .env("npm_config_prefix", &env)
+            .check(true)
+            .output()
+            .await?;
+
+        Ok(())
+    }
+
+    fn installed_dependencies(&self, env_dir: &Path) -> anyhow::Result<Vec<String>> {
+        let node_modules = node_modules_dir(env_dir);
+        if !node_modules.is_dir() {
+            return Ok(vec![]);
+        }
+
+        // Scoped packages are nested one level deeper, like `@scope/name`.
+        let mut packages = Vec::new();
+        for entry in fs_err::read_dir(&node_modules)? {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('@'))
+            {
+                for entry in fs_err::read_dir(&path)? {
+                    packages.push(entry?.path());
+                }
+            } else {
+                packages.push(path);
+            }
+        }
+
+        let mut dependencies = Vec::new();
+        for package in packages {
+            let Ok(content) = fs_err::read_to_string(package.join("package.json")) else {
+                continue;
+            };
+            let manifest: serde_json::Value = serde_json::from_str(&content)?;
+            if let (Some(name), Some(version)) =
+                (manifest["name"].as_str(), manifest["version"].as_str())
+            {
+                dependencies.push(format!("{name}@{version}"));
+            }
+        }
+        dependencies.sort_unstable_by_key(|dep| dep.to_lowercase());
+
+        Ok(dependencies)
+    }
+
+    async fn check_health(&self, hook: &Hook) -> anyhow::Result<()> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        if !env.is_dir() {
+            anyhow::bail!("The environment directory `{}` is missing", env.display());
+        }
+
+        // The toolchain may have been removed from the system or the store.
+        let node_bin = ensure_node(&hook.language_version).await?;
+        let path = join_path(&[node_bin])?;
+        Cmd::new(resolve_entry("node", Some(&path)), "get node version")
+            .arg("--version")
+            .env("PATH", &path)
+            .check(true)
+            .output()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        hook: &Hook,
+        filenames: &[&String],
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
+    ) -> anyhow::Result<(i32, Vec<u8>)> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        let node_bin = ensure_node(&hook.language_version).await?;
+
+        let cmds = shlex::split(&hook.entry)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse entry command"))?;
+
+        // Put the executables of the environment and the toolchain first in `PATH`.
+        let new_path = join_path(&[bin_dir(&env), node_bin])?;
+        let entry = resolve_entry(&cmds[0], Some(&new_path));
+
+        let cmds = Arc::new(cmds);
+        let entry = Arc::new(entry);
+        let hook_args = Arc::new(hook.args.clone());
+        let node_modules = Arc::new(node_modules_dir(&env));
+        let env = Arc::new(env);
+        let new_path = Arc::new(new_path);
+
+        let run = move |batch: Vec<String>| {
+            let cmds = cmds.clone();
+            let entry = entry.clone();
+            let hook_args = hook_args.clone();
+            let node_modules = node_modules.clone();
+            let env = env.clone();
+            let new_path = new_path.clone();
+            let env_vars = env_vars.clone();
+
+            async move {
+                let mut output = Cmd::new(entry.as_ref(), "run node command")
+                    .args(&cmds[1..])
+                    .env("PATH", new_path.as_ref())
+                    .env("NODE_VIRTUAL_ENV", env.as_ref())
+                    .env("NPM_CONFIG_PREFIX", env.as_ref())
+                    .env("npm_config_prefix", env.as_ref())
+                    .env("NODE_PATH", node_modules.as_ref())
+                    .envs(env_vars.as_ref())
+                    .args(hook_args.as_slice())
+                    .args(batch)
+                    .check(false)
+                    .output()
+                    .await?;
+
+                output.stdout.extend(output.stderr);
+                let code = output.status.code().unwrap_or(1);
+                anyhow::Ok((code, output.stdout))
+            }
+        };
+
+        let results = run_by_batch(hook, filenames, options, run).await?;
+
+        // Collect results
+        let mut combined_status = 0;
+        let mut combined_output = Vec::new();
+
+        for (code, output) in results {
+            combined_status |= code;
+            combined_output.extend(output);
+        }
+
+        Ok((combined_status, combined_output))
+    }
+}
+
+/// The directory of executables installed into a global npm prefix.
+fn bin_dir(env: &Path) -> PathBuf {
+    if cfg!(windows) {
+        env.to_path_buf()
+    } else {
+        env.join("bin")
+    }
+}
+
+/// The `node_modules` directory of a global npm prefix.
+fn node_modules_dir(env: &Path) -> PathBuf {
+    if cfg!(windows) {
+        env.join("node_modules")
+    } else {
+        env.join("lib").join("node_modules")
+    }
+}
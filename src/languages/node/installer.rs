@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::languages::{
+    ensure_toolchain, sha256_hex, version_key, version_matches, ManagedToolchain,
+};
+use crate::process::Cmd;
+use crate::store::Store;
+
+// The version of Node.js to install when there is no `node` on the system.
+// Should update periodically to the latest LTS.
+const NODE_VERSION: &str = "22.11.0";
+
+const DIST_URL: &str = "https://nodejs.org/dist";
+
+/// Find or install a Node.js toolchain matching `language_version`, returning its `bin` directory.
+///
+/// Versions are like `20`, `20.11` or `v20.11.1`.
+pub(crate) async fn ensure_node(language_version: &str) -> Result<PathBuf> {
+    ensure_toolchain::<NodeToolchain>(language_version).await
+}
+
+struct NodeToolchain;
+
+impl ManagedToolchain for NodeToolchain {
+    const PROGRAM: &'static str = "node";
+    const VERSION_ARGS: &'static [&'static str] = &["--version"];
+    const VERSION_PREFIX: &'static str = "v";
+    const PINNED_VERSION: &'static str = NODE_VERSION;
+
+    fn toolchains_dir(store: &Store) -> PathBuf {
+        store.node_path()
+    }
+
+    fn bin_dir(toolchain: &Path) -> PathBuf {
+        bin_dir(toolchain)
+    }
+
+    async fn resolve_version(spec: &str) -> Result<String> {
+        resolve_version(spec).await
+    }
+
+    async fn install(toolchains_dir: &Path, version: &str) -> Result<()> {
+        install(toolchains_dir, version).await
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    version: String,
+}
+
+/// Resolve `spec` to the latest matching release from the Node.js release index.
+async fn resolve_version(spec: &str) -> Result<String> {
+    let url = format!("{DIST_URL}/index.json");
+    let releases: Vec<Release> = reqwest::get(&url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to fetch `{url}`"))?
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse `{url}`"))?;
+
+    releases
+        .into_iter()
+        .map(|release| release.version.trim_start_matches('v').to_string())
+        .filter(|version| version_matches(spec, version))
+        .max_by_key(|version| version_key(version))
+        .with_context(|| format!("No Node.js release found for version `{spec}`"))
+}
+
+/// The name of the release archive for this platform, without the extension.
+fn archive_name(version: &str) -> Result<String> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "win",
+        os => anyhow::bail!("Unsupported platform `{os}` for Node.js"),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        arch => anyhow::bail!("Unsupported architecture `{arch}` for Node.js"),
+    };
+    Ok(format!("node-v{version}-{os}-{arch}"))
+}
+
+/// Download, verify and extract the release `version` into `node_dir/version`.
+async fn install(node_dir: &Path, version: &str) -> Result<()> {
+    let name = archive_name(version)?;
+    let archive = if cfg!(windows) {
+        format!("{name}.zip")
+    } else {
+        format!("{name}.tar.gz")
+    };
+    let url = format!("{DIST_URL}/v{version}/{archive}");
+    debug!(url, "Downloading node");
+
+    let content = reqwest::get(&url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download `{url}`"))?
+        .bytes()
+        .await?;
+
+    // Verify the archive against the published checksums.
+    let shasums_url = format!("{DIST_URL}/v{version}/SHASUMS256.txt");
+    let shasums = reqwest::get(&shasums_url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download `{shasums_url}`"))?
+        .text()
+        .await?;
+    let expected = shasums
+        .lines()
+        .find_map(|line| {
+            let (checksum, file) = line.split_once(char::is_whitespace)?;
+            (file.trim() == archive).then_some(checksum)
+        })
+        .with_context(|| format!("No checksum found for `{archive}`"))?;
+    let actual = sha256_hex(&content);
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!("Checksum mismatch for `{url}`, expected `{expected}`, got `{actual}`");
+    }
+
+    let temp = tempfile::tempdir_in(node_dir)?;
+    let archive_path = temp.path().join(&archive);
+    fs_err::write(&archive_path, &content)?;
+    // `tar` extracts both `.tar.gz` and, on Windows, `.zip` archives.
+    Cmd::new("tar", "extract node")
+        .arg("-xf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(temp.path())
+        .check(true)
+        .output()
+        .await?;
+    fs_err::rename(temp.path().join(&name), node_dir.join(version))?;
+
+    debug!(version, "Successfully installed node");
+    Ok(())
+}
+
+/// The directory of the `node` and `npm` executables in a toolchain.
+fn bin_dir(toolchain: &Path) -> PathBuf {
+    if cfg!(windows) {
+        toolchain.to_path_buf()
+    } else {
+        toolchain.join("bin")
+    }
+}
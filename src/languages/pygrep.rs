@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use anyhow::Context;
+use fancy_regex::Regex;
+
+use crate::hook::Hook;
+use crate::languages::{InstallOptions, LanguageImpl, DEFAULT_VERSION};
+use crate::run::BatchOptions;
+
+/// Fail if the `entry` regex matches a line of the files.
+///
+/// Like in pre-commit, the hook `args` can include:
+/// - `-i` or `--ignore-case`: match case-insensitively.
+/// - `--multiline`: match against the whole file instead of line by line.
+/// - `--negate`: fail for the files that don't match instead.
+#[derive(Debug, Copy, Clone)]
+pub struct Pygrep;
+
+#[derive(Debug, Default)]
+struct Args {
+    ignore_case: bool,
+    multiline: bool,
+    negate: bool,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut parsed = Self::default();
+        for arg in args {
+            match arg.as_str() {
+                "-i" | "--ignore-case" => parsed.ignore_case = true,
+                "--multiline" => parsed.multiline = true,
+                "--negate" => parsed.negate = true,
+                arg => anyhow::bail!("Unknown argument for `pygrep`: `{arg}`"),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl LanguageImpl for Pygrep {
+    fn default_version(&self) -> &str {
+        DEFAULT_VERSION
+    }
+
+    fn environment_dir(&self) -> Option<&str> {
+        None
+    }
+
+    async fn install(&self, _hook: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        hook: &Hook,
+        filenames: &[&String],
+        _env_vars: Arc<HashMap<String, String>>,
+        _options: BatchOptions<'_>,
+    ) -> anyhow::Result<(i32, Vec<u8>)> {
+        let args = Args::parse(&hook.args)?;
+
+        let mut flags = String::new();
+        if args.ignore_case {
+            flags.push('i');
+        }
+        if args.multiline {
+            flags.push_str("ms");
+        }
+        let pattern = if flags.is_empty() {
+            hook.entry.clone()
+        } else {
+            format!("(?{flags}){}", hook.entry)
+        };
+        let regex = Regex::new(&pattern)
+            .with_context(|| format!("Invalid regex in hook `{}`: `{}`", hook.id, hook.entry))?;
+
+        let mut code = 0;
+        let mut output = String::new();
+        for filename in filenames {
+            let content = fs_err::read(filename.as_str())?;
+            let content = String::from_utf8_lossy(&content);
+
+            let matched = if args.multiline {
+                match_at_once(&regex, filename, &content, !args.negate, &mut output)?
+            } else {
+                match_by_line(&regex, filename, &content, !args.negate, &mut output)?
+            };
+
+            if args.negate {
+                if !matched {
+                    code = 1;
+                    writeln!(output, "{filename}")?;
+                }
+            } else if matched {
+                code = 1;
+            }
+        }
+
+        Ok((code, output.into_bytes()))
+    }
+}
+
+/// Match each line, writing `filename:lineno:line` for the matched lines if `report`.
+fn match_by_line(
+    regex: &Regex,
+    filename: &str,
+    content: &str,
+    report: bool,
+    output: &mut String,
+) -> anyhow::Result<bool> {
+    let mut matched = false;
+    for (lineno, line) in content.lines().enumerate() {
+        if regex.is_match(line)? {
+            matched = true;
+            if !report {
+                break;
+            }
+            writeln!(output, "{filename}:{}:{line}", lineno + 1)?;
+        }
+    }
+    Ok(matched)
+}
+
+/// Match the whole file, writing `filename:lineno:` and the matched lines if `report`.
+fn match_at_once(
+    regex: &Regex,
+    filename: &str,
+    content: &str,
+    report: bool,
+    output: &mut String,
+) -> anyhow::Result<bool> {
+    let Some(found) = regex.find(content)? else {
+        return Ok(false);
+    };
+    if report {
+        let line_start = content[..found.start()].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[found.end()..]
+            .find('\n')
+            .map_or(content.len(), |i| found.end() + i);
+        let lineno = content[..found.start()].matches('\n').count() + 1;
+        writeln!(
+            output,
+            "{filename}:{lineno}:{}",
+            &content[line_start..line_end]
+        )?;
+    }
+    Ok(true)
+}
@@ -2,11 +2,15 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::hook::Hook;
+use tracing::{debug, warn};
+
+use crate::cli::PythonInstaller;
+use crate::hook::{Hook, Repo};
+use crate::languages::python::installer::ensure_python;
 use crate::languages::python::uv::ensure_uv;
-use crate::languages::LanguageImpl;
+use crate::languages::{join_path, resolve_entry, InstallOptions, LanguageImpl};
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{run_by_batch, BatchOptions};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Python;
@@ -21,19 +25,34 @@ impl LanguageImpl for Python {
         Some("py_env")
     }
 
-    // TODO: fallback to virtualenv, pip
-    async fn install(&self, hook: &Hook) -> anyhow::Result<()> {
+    fn env_bin_dir(&self, env_dir: &Path) -> Option<PathBuf> {
+        Some(bin_dir(env_dir))
+    }
+
+    async fn install(&self, hook: &Hook, options: InstallOptions) -> anyhow::Result<()> {
         let venv = hook.environment_dir().expect("No environment dir found");
 
-        let uv = ensure_uv().await?;
+        let uv = match options.python_installer {
+            Some(PythonInstaller::Uv) => Some(ensure_uv().await?),
+            Some(PythonInstaller::Pip) => None,
+            None => ensure_uv()
+                .await
+                .inspect_err(|err| warn!(?err, "Failed to find uv, falling back to pip"))
+                .ok(),
+        };
+        let Some(uv) = uv else {
+            return install_with_pip(hook, &venv).await;
+        };
+        let python = ensure_python(&hook.language_version).await?;
+        debug!(uv = %uv.display(), python = %python.display(), "Installing environment with uv");
 
-        // Set uv cache dir? tools dir? python dir?
+        // Set uv cache dir? tools dir?
         // Create venv
         Cmd::new(&uv, "create venv")
             .arg("venv")
             .arg(&venv)
             .arg("--python")
-            .arg(&hook.language_version)
+            .arg(&python)
             .check(true)
             .output()
             .await?;
@@ -45,7 +64,7 @@ impl LanguageImpl for Python {
             .arg("pip")
             .arg("install")
             .arg(".")
-            .args(&hook.additional_dependencies)
+            .args(hook.install_dependencies()?)
             .current_dir(hook.path())
             .env("VIRTUAL_ENV", &venv)
             .check(true)
@@ -55,35 +74,69 @@ impl LanguageImpl for Python {
         Ok(())
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
-        todo!()
+    fn installed_dependencies(&self, env_dir: &Path) -> anyhow::Result<Vec<String>> {
+        let Some(site_packages) = site_packages_dir(env_dir)? else {
+            return Ok(vec![]);
+        };
+
+        let mut dependencies = Vec::new();
+        for entry in fs_err::read_dir(site_packages)? {
+            let name = entry?.file_name();
+            let Some(dist_info) = name.to_str().and_then(|n| n.strip_suffix(".dist-info")) else {
+                continue;
+            };
+            if let Some((name, version)) = dist_info.rsplit_once('-') {
+                dependencies.push(format!("{name}=={version}"));
+            }
+        }
+        dependencies.sort_unstable_by_key(|dep| dep.to_lowercase());
+
+        Ok(dependencies)
+    }
+
+    async fn check_health(&self, hook: &Hook) -> anyhow::Result<()> {
+        let env_dir = hook
+            .environment_dir()
+            .expect("No environment dir for Python");
+
+        // The venv is broken if the interpreter it was created from is gone or upgraded.
+        let version = get_full_version(&env_dir).await?;
+        let cfg = fs_err::read_to_string(env_dir.join("pyvenv.cfg"))?;
+        let cfg_version = cfg.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "version_info").then(|| value.trim().to_string())
+        });
+
+        match cfg_version {
+            Some(cfg_version) if cfg_version != version => anyhow::bail!(
+                "Python version changed from `{cfg_version}` to `{version}`, the environment needs to be reinstalled"
+            ),
+            _ => Ok(()),
+        }
     }
 
     async fn run(
         &self,
         hook: &Hook,
         filenames: &[&String],
-        env_vars: Arc<HashMap<&'static str, String>>,
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
     ) -> anyhow::Result<(i32, Vec<u8>)> {
         // Get environment directory and parse command
         let env_dir = hook
             .environment_dir()
             .expect("No environment dir for Python");
 
-        let cmds = shlex::split(&hook.entry)
+        let mut cmds = shlex::split(&hook.entry)
             .ok_or_else(|| anyhow::anyhow!("Failed to parse entry command"))?;
+        resolve_script(hook, &mut cmds);
 
         // Construct PATH with venv bin directory first
-        let new_path = std::env::join_paths(
-            std::iter::once(bin_dir(env_dir.as_path())).chain(
-                std::env::var_os("PATH")
-                    .as_ref()
-                    .iter()
-                    .flat_map(std::env::split_paths),
-            ),
-        )?;
+        let new_path = join_path(&[bin_dir(env_dir.as_path())])?;
+        let entry = resolve_entry(&cmds[0], Some(&new_path));
 
         let cmds = Arc::new(cmds);
+        let entry = Arc::new(entry);
         let hook_args = Arc::new(hook.args.clone());
         let env_dir = Arc::new(env_dir.clone());
         let new_path = Arc::new(new_path);
@@ -92,6 +145,7 @@ impl LanguageImpl for Python {
             // This closure should be Fn, as it is called for each batch. We need to clone the variables,
             // otherwise it will be moved into the async block and can't be used again.
             let cmds = cmds.clone();
+            let entry = entry.clone();
             let hook_args = hook_args.clone();
             let env_dir = env_dir.clone();
             let new_path = new_path.clone();
@@ -99,7 +153,7 @@ impl LanguageImpl for Python {
 
             // TODO: combine stdout and stderr
             async move {
-                let mut output = Cmd::new(&cmds[0], "run python command")
+                let mut output = Cmd::new(entry.as_ref(), "run python command")
                     .args(&cmds[1..])
                     .env("VIRTUAL_ENV", env_dir.as_ref())
                     .env("PATH", new_path.as_ref())
@@ -117,7 +171,7 @@ impl LanguageImpl for Python {
             }
         };
 
-        let results = run_by_batch(hook, filenames, run).await?;
+        let results = run_by_batch(hook, filenames, options, run).await?;
 
         // Collect results
         let mut combined_status = 0;
@@ -132,6 +186,58 @@ impl LanguageImpl for Python {
     }
 }
 
+/// Create the environment with `venv`, and install the dependencies with `pip`.
+async fn install_with_pip(hook: &Hook, venv: &Path) -> anyhow::Result<()> {
+    let python = ensure_python(&hook.language_version).await?;
+    debug!(python = %python.display(), "Installing environment with pip");
+
+    Cmd::new(&python, "create venv")
+        .arg("-m")
+        .arg("venv")
+        .arg(venv)
+        .check(true)
+        .output()
+        .await?;
+
+    Cmd::new(bin_dir(venv).join("python"), "install dependencies")
+        .arg("-m")
+        .arg("pip")
+        .arg("install")
+        .arg(".")
+        .args(hook.install_dependencies()?)
+        .current_dir(hook.path())
+        .env("VIRTUAL_ENV", venv)
+        .check(true)
+        .output()
+        .await?;
+
+    Ok(())
+}
+
+/// For entries like `python path/to/script.py` in a remote repo, resolve the script relative to
+/// the cloned repo, while `python` is still the interpreter of the environment.
+fn resolve_script(hook: &Hook, cmds: &mut [String]) {
+    let Repo::Remote { path, .. } = hook.repo() else {
+        return;
+    };
+    let [interpreter, script, ..] = cmds else {
+        return;
+    };
+
+    let is_python = Path::new(interpreter)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("python"));
+    if !is_python || script.starts_with('-') || Path::new(script).is_absolute() {
+        return;
+    }
+
+    let resolved = path.join(&*script);
+    if resolved.is_file() {
+        *script = resolved.to_string_lossy().to_string();
+    }
+}
+
 fn bin_dir(venv: &Path) -> PathBuf {
     if cfg!(windows) {
         venv.join("Scripts")
@@ -140,6 +246,26 @@ fn bin_dir(venv: &Path) -> PathBuf {
     }
 }
 
+/// Find the `site-packages` directory of a venv.
+fn site_packages_dir(venv: &Path) -> anyhow::Result<Option<PathBuf>> {
+    if cfg!(windows) {
+        let dir = venv.join("Lib").join("site-packages");
+        return Ok(dir.is_dir().then_some(dir));
+    }
+
+    let lib = venv.join("lib");
+    if !lib.is_dir() {
+        return Ok(None);
+    }
+    for entry in fs_err::read_dir(lib)? {
+        let dir = entry?.path().join("site-packages");
+        if dir.is_dir() {
+            return Ok(Some(dir));
+        }
+    }
+    Ok(None)
+}
+
 async fn get_full_version(path: &Path) -> anyhow::Result<String> {
     let python = bin_dir(path).join("python");
     let output = Cmd::new(&python, "run python")
@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::{debug, trace};
+
+use crate::fs::LockedFile;
+use crate::languages::{sha256_hex, version_key, version_matches, DEFAULT_VERSION};
+use crate::process::Cmd;
+use crate::store::Store;
+
+// The version of Python to install for `python3` when there is no interpreter on the system.
+// Should update periodically, with `RELEASE` and `VERSIONS`.
+const PYTHON_VERSION: &str = "3.12.7";
+
+/// The release of `python-build-standalone` to install Python from.
+const RELEASE: &str = "20241016";
+
+/// The Python versions in `RELEASE`.
+const VERSIONS: &[&str] = &["3.9.20", "3.10.15", "3.11.10", "3.12.7", "3.13.0"];
+
+const RELEASES_URL: &str = "https://github.com/astral-sh/python-build-standalone/releases/download";
+
+/// Find or install a Python interpreter matching `language_version`, returning its path.
+///
+/// `language_version` is one of:
+/// - `python3` (the default): the `python3` from `PATH`, or a managed install of the pinned version.
+/// - `system`: the `python3` from `PATH`.
+/// - a version like `python3.12` or `3.12`: the matching interpreter from `PATH`,
+///   otherwise a managed install of that version.
+pub(crate) async fn ensure_python(language_version: &str) -> Result<PathBuf> {
+    // 1) Check the interpreters from `PATH`.
+    if let Some(python) = find_python(language_version) {
+        trace!(python = %python.display(), "Found python from PATH");
+        return Ok(python);
+    }
+    if language_version == "system" {
+        anyhow::bail!("Failed to find `python3` in `PATH`");
+    }
+
+    let spec = match language_version.trim_start_matches("python") {
+        "" | "3" => PYTHON_VERSION,
+        version => version,
+    };
+    let version = VERSIONS
+        .iter()
+        .copied()
+        .filter(|version| version_matches(spec, version))
+        .max_by_key(|version| version_key(version))
+        .with_context(|| format!("No managed Python available for version `{language_version}`"))?;
+
+    // 2) Check the interpreters installed by `prefligit`.
+    let store = Store::from_settings()?;
+    let python_dir = store.python_path();
+    let python = executable(&python_dir.join(version));
+    if python.is_file() {
+        trace!(python = %python.display(), "Found managed python");
+        return Ok(python);
+    }
+
+    fs_err::create_dir_all(&python_dir)?;
+    let _lock = LockedFile::acquire(python_dir.join(".lock"), "python").await?;
+
+    if python.is_file() {
+        trace!(python = %python.display(), "Found managed python");
+        return Ok(python);
+    }
+
+    // 3) Download and install the version.
+    install(&python_dir, version).await?;
+    Ok(python)
+}
+
+/// Find the interpreter for a `language_version` like `python3.12` or `3.12` in `PATH`.
+fn find_python(version: &str) -> Option<PathBuf> {
+    let version = match version {
+        DEFAULT_VERSION | "system" => "python3",
+        version => version,
+    };
+    which::which(version)
+        .or_else(|_| which::which(format!("python{version}")))
+        .ok()
+}
+
+/// The target triple of the builds for this platform.
+fn target() -> Result<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("x86_64-unknown-linux-gnu"),
+        ("linux", "aarch64") => Ok("aarch64-unknown-linux-gnu"),
+        ("macos", "x86_64") => Ok("x86_64-apple-darwin"),
+        ("macos", "aarch64") => Ok("aarch64-apple-darwin"),
+        ("windows", "x86_64") => Ok("x86_64-pc-windows-msvc"),
+        (os, arch) => anyhow::bail!(
+            "No managed Python for `{os}-{arch}`, please install Python and make sure `python3` is in `PATH`"
+        ),
+    }
+}
+
+/// Download, verify and extract the `version` into `python_dir/version`.
+async fn install(python_dir: &Path, version: &str) -> Result<()> {
+    let archive = format!(
+        "cpython-{version}+{RELEASE}-{}-install_only.tar.gz",
+        target()?
+    );
+    let url = format!("{RELEASES_URL}/{RELEASE}/{archive}");
+    debug!(url, "Downloading python");
+
+    let content = reqwest::get(&url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download `{url}`"))?
+        .bytes()
+        .await?;
+
+    // Verify the archive against the published checksums.
+    let shasums_url = format!("{RELEASES_URL}/{RELEASE}/SHA256SUMS");
+    let shasums = reqwest::get(&shasums_url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download `{shasums_url}`"))?
+        .text()
+        .await?;
+    let expected = shasums
+        .lines()
+        .find_map(|line| {
+            let (checksum, file) = line.split_once(char::is_whitespace)?;
+            (file.trim() == archive).then_some(checksum)
+        })
+        .with_context(|| format!("No checksum found for `{archive}`"))?;
+    let actual = sha256_hex(&content);
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!("Checksum mismatch for `{url}`, expected `{expected}`, got `{actual}`");
+    }
+
+    let temp = tempfile::tempdir_in(python_dir)?;
+    let archive_path = temp.path().join(&archive);
+    fs_err::write(&archive_path, &content)?;
+    Cmd::new("tar", "extract python")
+        .arg("-xzf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(temp.path())
+        .check(true)
+        .output()
+        .await?;
+    // The archive contains a single `python` directory.
+    fs_err::rename(temp.path().join("python"), python_dir.join(version))?;
+
+    debug!(version, "Successfully installed python");
+    Ok(())
+}
+
+/// The interpreter in an installed build.
+fn executable(build: &Path) -> PathBuf {
+    if cfg!(windows) {
+        build.join("python.exe")
+    } else {
+        build.join("bin").join("python3")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions() {
+        assert!(VERSIONS.contains(&PYTHON_VERSION));
+    }
+}
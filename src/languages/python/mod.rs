@@ -1,4 +1,5 @@
 mod r#impl;
+mod installer;
 mod uv;
 
 pub use r#impl::Python;
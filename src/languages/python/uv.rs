@@ -11,7 +11,6 @@ use crate::store::Store;
 // The version of `uv` to install. Should update periodically.
 const UV_VERSION: &str = "0.5.2";
 
-// TODO: allow opt-out uv
 // TODO: allow install uv using pip
 
 /// Ensure that the `uv` binary is available.
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use crate::hook::Hook;
+use crate::languages::{sha256_hex, InstallOptions, LanguageImpl, DEFAULT_VERSION};
+use crate::process::Cmd;
+use crate::run::{run_by_batch, BatchOptions};
+
+/// The name of the downloaded script in the environment directory.
+const SCRIPT_NAME: &str = "script";
+
+/// Whether the script can be downloaded from `url`: only https, or http from a loopback host
+/// since the traffic never leaves the machine, e.g. for a local mirror.
+fn is_secure(url: &url::Url) -> bool {
+    match url.scheme() {
+        "https" => true,
+        "http" => match url.host() {
+            Some(url::Host::Domain(domain)) => domain == "localhost",
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// An experimental language where `entry` is a URL of a script, which is downloaded,
+/// verified against the `checksum` of the hook, and cached in the store.
+#[derive(Debug, Copy, Clone)]
+pub struct RemoteScript;
+
+impl LanguageImpl for RemoteScript {
+    fn default_version(&self) -> &str {
+        DEFAULT_VERSION
+    }
+
+    fn environment_dir(&self) -> Option<&str> {
+        Some("script_env")
+    }
+
+    async fn install(&self, hook: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        let checksum = hook
+            .checksum
+            .as_deref()
+            .expect("checksum should be checked when building the hook");
+
+        let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
+        let url = url::Url::parse(&cmds[0])
+            .with_context(|| format!("Invalid script URL `{}`", cmds[0]))?;
+        if !is_secure(&url) {
+            anyhow::bail!("Unsupported script URL `{url}`, only https is supported");
+        }
+
+        let content = reqwest::get(url.clone())
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .with_context(|| format!("Failed to download script from `{url}`"))?
+            .bytes()
+            .await?;
+
+        let actual = sha256_hex(&content);
+        if !actual.eq_ignore_ascii_case(checksum) {
+            anyhow::bail!(
+                "Checksum mismatch for script `{url}`, expected `{checksum}`, got `{actual}`"
+            );
+        }
+
+        fs_err::create_dir_all(&env)?;
+        let script = env.join(SCRIPT_NAME);
+        fs_err::write(&script, &content)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs_err::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        Ok(())
+    }
+
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        hook: &Hook,
+        filenames: &[&String],
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
+    ) -> anyhow::Result<(i32, Vec<u8>)> {
+        let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
+        let script = hook
+            .environment_dir()
+            .expect("No environment dir found")
+            .join(SCRIPT_NAME);
+
+        let cmds = Arc::new(cmds);
+        let script = Arc::new(script);
+        let hook_args = Arc::new(hook.args.clone());
+
+        let run = move |batch: Vec<String>| {
+            let cmds = cmds.clone();
+            let script = script.clone();
+            let hook_args = hook_args.clone();
+            let env_vars = env_vars.clone();
+
+            async move {
+                let mut output = Cmd::new(script.as_ref(), "run remote script")
+                    .args(&cmds[1..])
+                    .args(hook_args.as_ref())
+                    .args(batch)
+                    .envs(env_vars.as_ref())
+                    .check(false)
+                    .output()
+                    .await?;
+
+                output.stdout.extend(output.stderr);
+                let code = output.status.code().unwrap_or(1);
+                anyhow::Ok((code, output.stdout))
+            }
+        };
+
+        let results = run_by_batch(hook, filenames, options, run).await?;
+
+        // Collect results
+        let mut combined_status = 0;
+        let mut combined_output = Vec::new();
+
+        for (code, output) in results {
+            combined_status |= code;
+            combined_output.extend(output);
+        }
+
+        Ok((combined_status, combined_output))
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::hook::Hook;
+use crate::languages::ruby::installer::ensure_ruby;
+use crate::languages::{join_path, resolve_entry, InstallOptions, LanguageImpl, DEFAULT_VERSION};
+use crate::process::Cmd;
+use crate::run::{run_by_batch, BatchOptions};
+
+#[derive(Debug, Copy, Clone)]
+pub struct Ruby;
+
+impl LanguageImpl for Ruby {
+    fn default_version(&self) -> &str {
+        DEFAULT_VERSION
+    }
+
+    fn environment_dir(&self) -> Option<&str> {
+        Some("ruby_env")
+    }
+
+    fn env_bin_dir(&self, env_dir: &Path) -> Option<PathBuf> {
+        Some(bin_dir(env_dir))
+    }
+
+    async fn install(&self, hook: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        let ruby_bin = ensure_ruby(&hook.language_version).await?;
+        debug!(ruby = %ruby_bin.display(), "Installing environment with ruby");
+
+        fs_err::create_dir_all(&env)?;
+        let path = join_path(&[bin_dir(&env), ruby_bin])?;
+
+        // Build the gems of the hook repo, then install them with the additional dependencies.
+        let mut gems = Vec::new();
+        for gemspec in files_with_extension(hook.path(), "gemspec")? {
+            Cmd::new(resolve_entry("gem", Some(&path)), "build gem")
+                .arg("build")
+                .arg(&gemspec)
+                .current_dir(hook.path())
+                .env("PATH", &path)
+                .envs(gem_envs(&env))
+                .check(true)
+                .output()
+                .await?;
+        }
+        gems.extend(
+            files_with_extension(hook.path(), "gem")?
+                .into_iter()
+                .map(|gem| gem.to_string_lossy().to_string()),
+        );
+        gems.extend(hook.install_dependencies()?);
+        if gems.is_empty() {
+            return Ok(());
+        }
+
+        Cmd::new(resolve_entry("gem", Some(&path)), "install gems")
+            .arg("install")
+            .arg("--no-document")
+            .arg("--no-format-executable")
+            .arg("--no-user-install")
+            .arg("--install-dir")
+            .arg(&env)
+            .arg("--bindir")
+            .arg(bin_dir(&env))
+            .args(&gems)
+            .current_dir(hook.path())
+            .env("PATH", &path)
+            .envs(gem_envs(&env))
+            .check(true)
+            .output()
+            .await?;
+
+        Ok(())
+    }
+
+    fn installed_dependencies(&self, env_dir: &Path) -> anyhow::Result<Vec<String>> {
+        // Installed gems are recorded as `specifications/<name>-<version>.gemspec`.
+        let mut dependencies = Vec::new();
+        for spec in files_with_extension(&env_dir.join("specifications"), "gemspec")? {
+            let Some(stem) = spec.file_stem() else {
+                continue;
+            };
+            if let Some((name, version)) = stem.to_string_lossy().rsplit_once('-') {
+                dependencies.push(format!("{name}:{version}"));
+            }
+        }
+        dependencies.sort_unstable_by_key(|dep| dep.to_lowercase());
+
+        Ok(dependencies)
+    }
+
+    async fn check_health(&self, hook: &Hook) -> anyhow::Result<()> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        if !env.is_dir() {
+            anyhow::bail!("The environment directory `{}` is missing", env.display());
+        }
+
+        // The toolchain may have been removed from the system or the store.
+        let ruby_bin = ensure_ruby(&hook.language_version).await?;
+        let path = join_path(&[ruby_bin])?;
+        Cmd::new(resolve_entry("ruby", Some(&path)), "get ruby version")
+            .arg("--version")
+            .env("PATH", &path)
+            .check(true)
+            .output()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        hook: &Hook,
+        filenames: &[&String],
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
+    ) -> anyhow::Result<(i32, Vec<u8>)> {
+        let env = hook.environment_dir().expect("No environment dir found");
+        let ruby_bin = ensure_ruby(&hook.language_version).await?;
+
+        let cmds = shlex::split(&hook.entry)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse entry command"))?;
+
+        // Put the executables of the environment and the toolchain first in `PATH`.
+        let new_path = join_path(&[bin_dir(&env), ruby_bin])?;
+        let entry = resolve_entry(&cmds[0], Some(&new_path));
+
+        let cmds = Arc::new(cmds);
+        let entry = Arc::new(entry);
+        let hook_args = Arc::new(hook.args.clone());
+        let env = Arc::new(env);
+        let new_path = Arc::new(new_path);
+
+        let run = move |batch: Vec<String>| {
+            let cmds = cmds.clone();
+            let entry = entry.clone();
+            let hook_args = hook_args.clone();
+            let env = env.clone();
+            let new_path = new_path.clone();
+            let env_vars = env_vars.clone();
+
+            async move {
+                let mut output = Cmd::new(entry.as_ref(), "run ruby command")
+                    .args(&cmds[1..])
+                    .env("PATH", new_path.as_ref())
+                    .envs(gem_envs(&env))
+                    .envs(env_vars.as_ref())
+                    .args(hook_args.as_slice())
+                    .args(batch)
+                    .check(false)
+                    .output()
+                    .await?;
+
+                output.stdout.extend(output.stderr);
+                let code = output.status.code().unwrap_or(1);
+                anyhow::Ok((code, output.stdout))
+            }
+        };
+
+        let results = run_by_batch(hook, filenames, options, run).await?;
+
+        // Collect results
+        let mut combined_status = 0;
+        let mut combined_output = Vec::new();
+
+        for (code, output) in results {
+            combined_status |= code;
+            combined_output.extend(output);
+        }
+
+        Ok((combined_status, combined_output))
+    }
+}
+
+/// Isolate gems to the environment, ignoring the user's gems and bundler config.
+fn gem_envs(env: &Path) -> [(&'static str, &OsStr); 3] {
+    [
+        ("GEM_HOME", env.as_os_str()),
+        ("GEM_PATH", env.as_os_str()),
+        ("BUNDLE_IGNORE_CONFIG", OsStr::new("1")),
+    ]
+}
+
+/// The files directly in `dir` with the given extension, sorted.
+fn files_with_extension(dir: &Path, extension: &str) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut files = Vec::new();
+    for entry in fs_err::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// The directory of executables installed into `GEM_HOME`.
+fn bin_dir(env: &Path) -> PathBuf {
+    env.join("bin")
+}
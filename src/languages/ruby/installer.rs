@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::languages::{
+    ensure_toolchain, sha256_hex, version_key, version_matches, ManagedToolchain,
+};
+use crate::process::Cmd;
+use crate::store::Store;
+
+// The version of Ruby to install when there is no `ruby` on the system.
+// Should update periodically to the latest stable release.
+const RUBY_VERSION: &str = "3.3.6";
+
+/// The index of Ruby releases, used to resolve partial versions.
+const INDEX_URL: &str = "https://cache.ruby-lang.org/pub/ruby/index.txt";
+
+/// Prebuilt, relocatable Ruby builds, the same ones used by `ruby/setup-ruby`.
+const BUILDS_URL: &str = "https://github.com/ruby/ruby-builder/releases/download/toolcache";
+
+/// The release of the prebuilt Ruby builds, listing the SHA-256 digest of each archive.
+const BUILDS_RELEASE_URL: &str =
+    "https://api.github.com/repos/ruby/ruby-builder/releases/tags/toolcache";
+
+/// Find or install a Ruby toolchain matching `language_version`, returning its `bin` directory.
+///
+/// Versions are like `3`, `3.3` or `ruby-3.3.6`.
+pub(crate) async fn ensure_ruby(language_version: &str) -> Result<PathBuf> {
+    ensure_toolchain::<RubyToolchain>(language_version).await
+}
+
+struct RubyToolchain;
+
+impl ManagedToolchain for RubyToolchain {
+    const PROGRAM: &'static str = "ruby";
+    const VERSION_ARGS: &'static [&'static str] = &["-e", "print RUBY_VERSION"];
+    const VERSION_PREFIX: &'static str = "ruby-";
+    const PINNED_VERSION: &'static str = RUBY_VERSION;
+
+    fn toolchains_dir(store: &Store) -> PathBuf {
+        store.ruby_path()
+    }
+
+    async fn resolve_version(spec: &str) -> Result<String> {
+        resolve_version(spec).await
+    }
+
+    async fn install(toolchains_dir: &Path, version: &str) -> Result<()> {
+        install(toolchains_dir, version).await
+    }
+}
+
+/// Resolve `spec` to the latest matching stable release from the Ruby release index.
+async fn resolve_version(spec: &str) -> Result<String> {
+    let index = reqwest::get(INDEX_URL)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to fetch `{INDEX_URL}`"))?
+        .text()
+        .await?;
+
+    // Each line is `name url sha1 sha256 sha512`, with names like `ruby-3.3.6`.
+    index
+        .lines()
+        .filter_map(|line| line.split_whitespace().next()?.strip_prefix("ruby-"))
+        .filter(|version| version_matches(spec, version))
+        .filter_map(|version| Some((version_key(version)?, version)))
+        .max()
+        .map(|(_, version)| version.to_string())
+        .with_context(|| format!("No Ruby release found for version `{spec}`"))
+}
+
+/// The platform of the prebuilt Ruby builds.
+fn platform() -> Result<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("ubuntu-22.04"),
+        ("linux", "aarch64") => Ok("ubuntu-22.04-arm64"),
+        ("macos", "x86_64") => Ok("macos-13"),
+        ("macos", "aarch64") => Ok("macos-13-arm64"),
+        (os, arch) => anyhow::bail!(
+            "No prebuilt Ruby for `{os}-{arch}`, please install Ruby and make sure `ruby` is in `PATH`"
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    /// The digest of the asset, like `sha256:<hex>`.
+    digest: Option<String>,
+}
+
+/// Fetch the published SHA-256 checksum of the prebuilt `archive`.
+async fn fetch_checksum(archive: &str) -> Result<String> {
+    let release: Release = reqwest::Client::builder()
+        .user_agent(concat!("prefligit/", env!("CARGO_PKG_VERSION")))
+        .build()?
+        .get(BUILDS_RELEASE_URL)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to fetch `{BUILDS_RELEASE_URL}`"))?
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse `{BUILDS_RELEASE_URL}`"))?;
+
+    release
+        .assets
+        .into_iter()
+        .find(|asset| asset.name == archive)
+        .and_then(|asset| Some(asset.digest?.strip_prefix("sha256:")?.to_string()))
+        .with_context(|| format!("No checksum found for `{archive}`"))
+}
+
+/// Download, verify and extract the release `version` into `ruby_dir/version`.
+async fn install(ruby_dir: &Path, version: &str) -> Result<()> {
+    let archive = format!("ruby-{version}-{}.tar.gz", platform()?);
+    let url = format!("{BUILDS_URL}/{archive}");
+    debug!(url, "Downloading ruby");
+
+    let content = reqwest::get(&url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download `{url}`"))?
+        .bytes()
+        .await?;
+
+    // Verify the archive against the published checksum.
+    let expected = fetch_checksum(&archive).await?;
+    let actual = sha256_hex(&content);
+    if !actual.eq_ignore_ascii_case(&expected) {
+        anyhow::bail!("Checksum mismatch for `{url}`, expected `{expected}`, got `{actual}`");
+    }
+
+    let temp = tempfile::tempdir_in(ruby_dir)?;
+    let archive_path = temp.path().join(&archive);
+    fs_err::write(&archive_path, &content)?;
+    let extract_dir = temp.path().join("extract");
+    fs_err::create_dir_all(&extract_dir)?;
+    Cmd::new("tar", "extract ruby")
+        .arg("-xzf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(&extract_dir)
+        .check(true)
+        .output()
+        .await?;
+
+    // The archive contains a single directory named after the architecture.
+    let toolchain = fs_err::read_dir(&extract_dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| path.join("bin").is_dir())
+        .with_context(|| format!("No Ruby toolchain found in `{url}`"))?;
+    fs_err::rename(toolchain, ruby_dir.join(version))?;
+
+    debug!(version, "Successfully installed ruby");
+    Ok(())
+}
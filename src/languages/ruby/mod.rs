@@ -0,0 +1,4 @@
+mod r#impl;
+mod installer;
+
+pub use r#impl::Ruby;
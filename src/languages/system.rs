@@ -2,9 +2,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::hook::Hook;
-use crate::languages::{LanguageImpl, DEFAULT_VERSION};
+use crate::languages::{join_path, resolve_entry, InstallOptions, LanguageImpl, DEFAULT_VERSION};
 use crate::process::Cmd;
-use crate::run::run_by_batch;
+use crate::run::{run_by_batch, BatchOptions};
 
 #[derive(Debug, Copy, Clone)]
 pub struct System;
@@ -18,11 +18,11 @@ impl LanguageImpl for System {
         None
     }
 
-    async fn install(&self, _hook: &Hook) -> anyhow::Result<()> {
+    async fn install(&self, _hook: &Hook, _options: InstallOptions) -> anyhow::Result<()> {
         Ok(())
     }
 
-    async fn check_health(&self) -> anyhow::Result<()> {
+    async fn check_health(&self, _hook: &Hook) -> anyhow::Result<()> {
         Ok(())
     }
 
@@ -30,20 +30,36 @@ impl LanguageImpl for System {
         &self,
         hook: &Hook,
         filenames: &[&String],
-        env_vars: Arc<HashMap<&'static str, String>>,
+        env_vars: Arc<HashMap<String, String>>,
+        options: BatchOptions<'_>,
     ) -> anyhow::Result<(i32, Vec<u8>)> {
         let cmds = shlex::split(&hook.entry).ok_or(anyhow::anyhow!("Failed to parse entry"))?;
 
+        // Put the environment referenced by `use_env_of` first on PATH.
+        let new_path = hook
+            .extra_path()
+            .map(|extra_path| join_path(&[extra_path.to_path_buf()]))
+            .transpose()?;
+        let entry = resolve_entry(&cmds[0], new_path.as_deref());
+
         let cmds = Arc::new(cmds);
+        let entry = Arc::new(entry);
         let hook_args = Arc::new(hook.args.clone());
+        let new_path = Arc::new(new_path);
 
         let run = move |batch: Vec<String>| {
             let cmds = cmds.clone();
+            let entry = entry.clone();
             let hook_args = hook_args.clone();
             let env_vars = env_vars.clone();
+            let new_path = new_path.clone();
 
             async move {
-                let mut output = Cmd::new(&cmds[0], "run system command")
+                let mut cmd = Cmd::new(entry.as_ref(), "run system command");
+                if let Some(new_path) = new_path.as_ref() {
+                    cmd.env("PATH", new_path);
+                }
+                let mut output = cmd
                     .args(&cmds[1..])
                     .args(hook_args.as_ref())
                     .args(batch)
@@ -58,7 +74,7 @@ impl LanguageImpl for System {
             }
         };
 
-        let results = run_by_batch(hook, filenames, run).await?;
+        let results = run_by_batch(hook, filenames, options, run).await?;
 
         // Collect results
         let mut combined_status = 0;
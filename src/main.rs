@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::fmt::Write;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
@@ -12,9 +13,14 @@ use tracing_subscriber::filter::Directive;
 use tracing_subscriber::EnvFilter;
 
 use crate::cleanup::cleanup;
-use crate::cli::{Cli, Command, ExitStatus, SelfCommand, SelfNamespace, SelfUpdateArgs};
+use crate::cli::{
+    CacheCommand, CacheNamespace, Cli, Command, EnvCommand, EnvNamespace, ExitStatus, OutputFormat,
+    PythonInstaller, RunArgs, RunOptions, SelfCommand, SelfNamespace, SelfUpdateArgs,
+};
 use crate::git::get_root;
-use crate::printer::Printer;
+use crate::printer::{Mode, Printer};
+use crate::profile::Profile;
+use crate::run::DiffOptions;
 
 mod cleanup;
 mod cli;
@@ -24,8 +30,10 @@ mod git;
 mod hook;
 mod identify;
 mod languages;
+mod meta_hooks;
 mod printer;
 mod process;
+mod profile;
 #[cfg(all(unix, feature = "profiler"))]
 mod profiler;
 mod run;
@@ -82,25 +90,74 @@ fn adjust_relative_paths(cli: &mut Cli, new_cwd: &Path) -> Result<()> {
         }
     }
 
-    if let Some(Command::Run(ref mut args) | Command::TryRepo(ref mut args)) = cli.command {
+    let run_args = match &mut cli.command {
+        Some(Command::Run(args)) => Some(&mut **args),
+        Some(Command::SampleConfig(args)) => {
+            args.file = args
+                .file
+                .as_ref()
+                .map(|path| fs::relative_to(std::path::absolute(path)?, new_cwd))
+                .transpose()?;
+            None
+        }
+        Some(Command::TryRepo(args)) => {
+            if Path::new(&args.repo).is_dir() {
+                args.repo = std::path::absolute(&args.repo)?
+                    .to_string_lossy()
+                    .to_string();
+            }
+            Some(&mut args.run_args)
+        }
+        _ => None,
+    };
+    if let Some(args) = run_args {
         args.files = args
             .files
             .iter()
-            .map(|path| fs::relative_to(std::path::absolute(path)?, new_cwd))
+            .map(|path| {
+                // The paths read from stdin are rebased when they are read.
+                if path == Path::new("-") {
+                    return Ok(path.clone());
+                }
+                fs::relative_to(fs::clean_path(&std::path::absolute(path)?), new_cwd)
+            })
             .collect::<Result<Vec<PathBuf>, std::io::Error>>()?;
+        if args.files.iter().any(|path| path == Path::new("-")) {
+            args.extra.files_dir = Some(std::env::current_dir()?);
+        }
         args.extra.commit_msg_filename = args
             .extra
             .commit_msg_filename
             .as_ref()
             .map(|path| fs::relative_to(std::path::absolute(path)?, new_cwd))
             .transpose()?;
+        args.profile = args
+            .profile
+            .as_ref()
+            .map(|path| fs::relative_to(std::path::absolute(path)?, new_cwd))
+            .transpose()?;
+        args.extra.push_refs_file = args
+            .extra
+            .push_refs_file
+            .as_ref()
+            .map(|path| fs::relative_to(std::path::absolute(path)?, new_cwd))
+            .transpose()?;
     }
 
     Ok(())
 }
 
 async fn run(mut cli: Cli) -> Result<ExitStatus> {
-    ColorChoice::write_global(cli.globals.color.into());
+    // Change the directory first, so everything (paths, git, config discovery) resolves from there.
+    if let Some(dir) = &cli.globals.working_dir {
+        std::env::set_current_dir(dir)
+            .with_context(|| format!("Failed to change directory to `{}`", dir.display()))?;
+    }
+
+    // The printer carries the color choice, but `anstream` needs it globally for the output
+    // written without a printer: the warnings, the logs and the errors of `main`.
+    let color = cli.globals.color_choice().into();
+    ColorChoice::write_global(color);
 
     setup_logging(match cli.globals.verbose {
         0 => Level::Default,
@@ -108,15 +165,16 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
         _ => Level::ExtraVerbose,
     })?;
 
-    let printer = if cli.globals.quiet {
-        Printer::Quiet
+    let mode = if cli.globals.quiet {
+        Mode::Quiet
     } else if cli.globals.verbose > 0 {
-        Printer::Verbose
+        Mode::Verbose
     } else if cli.globals.no_progress {
-        Printer::NoProgress
+        Mode::NoProgress
     } else {
-        Printer::Default
+        Mode::Default
     };
+    let printer = Printer::new(mode, color);
 
     if cli.globals.quiet {
         warnings::disable();
@@ -130,17 +188,20 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
 
     debug!("prefligit: {}", env!("CARGO_PKG_VERSION"));
 
-    match get_root().await {
-        Ok(root) => {
-            debug!("Git root: {}", root.display());
+    let no_git = matches!(&cli.command, Some(Command::Run(args)) if args.experimental_no_git);
+    if !no_git {
+        match get_root().await {
+            Ok(root) => {
+                debug!("Git root: {}", root.display());
 
-            // Adjust relative paths before changing the working directory.
-            adjust_relative_paths(&mut cli, &root)?;
+                // Adjust relative paths before changing the working directory.
+                adjust_relative_paths(&mut cli, &root)?;
 
-            std::env::set_current_dir(&root)?;
-        }
-        Err(err) => {
-            error!("Failed to find git root: {}", err);
+                std::env::set_current_dir(&root)?;
+            }
+            Err(err) => {
+                error!("Failed to find git root: {}", err);
+            }
         }
     }
 
@@ -171,6 +232,7 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
                 args.install_hooks,
                 args.overwrite,
                 args.allow_missing_config,
+                cli.globals.python_installer,
                 printer,
                 None,
             )
@@ -184,16 +246,29 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
         Command::Run(args) => {
             show_settings!(args);
 
-            cli::run(
+            run_hooks(
                 cli.globals.config,
-                args.hook_id,
-                args.hook_stage,
-                args.from_ref,
-                args.to_ref,
-                args.all_files,
-                args.files,
-                args.show_diff_on_failure,
-                args.extra,
+                *args,
+                cli.globals.python_installer,
+                cli.globals.verbose > 0,
+                printer,
+            )
+            .await
+        }
+        Command::TryRepo(args) => {
+            show_settings!(args);
+
+            let (config, _temp) = cli::try_repo(
+                args.repo,
+                args.rev,
+                args.run_args.hook_id.as_deref(),
+                printer,
+            )
+            .await?;
+            run_hooks(
+                Some(config),
+                args.run_args,
+                cli.globals.python_installer,
                 cli.globals.verbose > 0,
                 printer,
             )
@@ -208,11 +283,14 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
                 args.hook_dir,
                 args.skip_on_missing_config,
                 args.args,
+                cli.globals.python_installer,
                 printer,
             )
             .await
         }
-        Command::Clean => cli::clean(printer),
+        Command::Clean => cli::clean(printer).await,
+        Command::MigrateConfig => cli::migrate_config(cli.globals.config, printer),
+        Command::GC => cli::gc(printer).await,
         Command::ValidateConfig(args) => {
             show_settings!(args);
 
@@ -223,7 +301,62 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
 
             Ok(cli::validate_manifest(args.manifests))
         }
-        Command::SampleConfig => Ok(cli::sample_config()),
+        Command::SampleConfig(args) => {
+            show_settings!(args);
+
+            cli::sample_config(args.file, printer)
+        }
+        Command::Version => cli::version(cli.globals.verbose > 0, printer),
+        Command::AutoUpdate(args) => {
+            show_settings!(args);
+
+            cli::autoupdate(
+                cli.globals.config,
+                args.bleeding_edge,
+                args.freeze,
+                args.repo,
+                args.jobs,
+                printer,
+            )
+            .await
+        }
+        Command::List(args) => {
+            show_settings!(args);
+
+            cli::list(cli.globals.config, args.format, printer).await
+        }
+        Command::Env(EnvNamespace {
+            command: EnvCommand::Info(args),
+        }) => {
+            show_settings!(args);
+
+            cli::env_info(cli.globals.config, args.hook_id, printer).await
+        }
+        Command::Cache(CacheNamespace {
+            command: CacheCommand::Verify(args),
+        }) => {
+            show_settings!(args);
+
+            cli::cache_verify(
+                cli.globals.config,
+                args.repair,
+                cli.globals.python_installer,
+                printer,
+            )
+            .await
+        }
+        Command::Doctor(args) => {
+            show_settings!(args);
+
+            cli::doctor(
+                cli.globals.config,
+                args.fix,
+                args.yes,
+                cli.globals.python_installer,
+                printer,
+            )
+            .await
+        }
         Command::Self_(SelfNamespace {
             command:
                 SelfCommand::Update(SelfUpdateArgs {
@@ -261,6 +394,70 @@ async fn run(mut cli: Cli) -> Result<ExitStatus> {
     }
 }
 
+/// Run the hooks of `config` with the `run` arguments, shared by `run` and `try-repo`.
+async fn run_hooks(
+    config: Option<PathBuf>,
+    args: RunArgs,
+    python_installer: Option<PythonInstaller>,
+    verbose: bool,
+    printer: Printer,
+) -> Result<ExitStatus> {
+    if args.print_resolved_revs {
+        return cli::print_resolved_revs(config, printer).await;
+    }
+    let profile = args
+        .profile
+        .is_some()
+        .then(|| RefCell::new(Profile::default()));
+    let start = std::time::Instant::now();
+
+    let options = RunOptions {
+        config,
+        hook_id: args.hook_id,
+        hook_stage: args.hook_stage,
+        from_ref: args.from_ref,
+        to_ref: args.to_ref,
+        all_files: args.all_files,
+        files: args.files,
+        no_git: args.experimental_no_git,
+        show_diff_on_failure: args.show_diff_on_failure.then_some(DiffOptions {
+            context: args.diff_context,
+            color: args.diff_color.map(Into::into),
+        }),
+        output_format: if args.summary_only {
+            OutputFormat::Summary
+        } else {
+            args.output_format
+        },
+        hide_skipped: args.hide_skipped,
+        no_fail_on_modified: args.no_fail_on_modified,
+        keep_going_per_repo: args.keep_going_per_repo,
+        retry_failed: args.retry_failed && !args.rerun,
+        only_changed_hooks: args.only_changed_hooks && !args.rerun,
+        install_hooks_first: args.install_hooks_first,
+        fixer_passes: args.fixer_passes,
+        jobs: args.jobs,
+        bail_after: args.bail_after,
+        fail_fast_on_error: args.fail_fast_on_error,
+        docker_jobs: args.docker_jobs,
+        python_installer,
+        max_file_size: args.max_file_size,
+        explain: args.explain,
+        extra_args: args.extra,
+        profile: profile.as_ref(),
+        verbose,
+    };
+    let status = cli::run(options, printer).await;
+
+    if let (Some(path), Some(profile)) = (args.profile, profile) {
+        let mut profile = profile.into_inner();
+        profile.total = start.elapsed().as_secs_f64();
+        fs_err::write(&path, serde_json::to_string_pretty(&profile)?)
+            .context("Failed to write the profile")?;
+    }
+    status
+}
+
 fn main() -> ExitCode {
     ctrlc::set_handler(move || {
         cleanup();
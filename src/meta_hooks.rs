@@ -0,0 +1,166 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::config::{read_config, ConfigRepo, ExcludePatterns, Language, ManifestHook, MetaHookID};
+use crate::git;
+use crate::hook::Hook;
+use crate::run::{hook_filenames, FileTags, FilenameFilter};
+
+/// The definitions of the hooks of `repo: meta`, in the manifest format.
+///
+/// They run in-process, so their `entry` is only informational.
+const META_HOOKS: &str = r"
+- id: check-hooks-apply
+  name: Check hooks apply to the repository
+  entry: check-hooks-apply
+  language: system
+  files: ^\.pre-commit-config\.yaml$
+- id: check-useless-excludes
+  name: Check for useless excludes
+  entry: check-useless-excludes
+  language: system
+  files: ^\.pre-commit-config\.yaml$
+- id: identity
+  name: identity
+  entry: identity
+  language: system
+  verbose: true
+";
+
+/// The hooks of `repo: meta`.
+pub(crate) fn manifest_hooks() -> Vec<ManifestHook> {
+    serde_yaml::from_str(META_HOOKS).expect("Failed to parse meta hooks")
+}
+
+/// Run a meta hook, `hooks` are all the hooks of the config.
+pub(crate) async fn run(
+    hook: &Hook,
+    hooks: &[Hook],
+    filenames: &[&String],
+) -> Result<(i32, Vec<u8>)> {
+    let mut output = String::new();
+    let code = if hook.id == MetaHookID::Identity.as_str() {
+        identity(filenames, &mut output)?
+    } else if hook.id == MetaHookID::CheckHooksApply.as_str() {
+        check_hooks_apply(hooks, &mut output).await?
+    } else if hook.id == MetaHookID::CheckUselessExcludes.as_str() {
+        check_useless_excludes(filenames, &mut output).await?
+    } else {
+        anyhow::bail!("Unknown meta hook `{}`", hook.id);
+    };
+    Ok((code, output.into_bytes()))
+}
+
+/// Print the filenames the hook receives, to debug the file selection.
+fn identity(filenames: &[&String], output: &mut String) -> Result<i32> {
+    for filename in filenames {
+        writeln!(output, "{filename}")?;
+    }
+    Ok(0)
+}
+
+/// Fail for the hooks of the config that don't apply to any file in the repository, whether
+/// they're selected for this run or not.
+async fn check_hooks_apply(hooks: &[Hook], output: &mut String) -> Result<i32> {
+    let files = git::get_all_files().await?;
+    let file_tags = FileTags::new(&files);
+
+    let mut code = 0;
+    for hook in hooks {
+        if hook.always_run || hook.language == Language::Fail {
+            continue;
+        }
+        if hook_filenames(hook, &files, &file_tags)?.is_empty() {
+            code = 1;
+            writeln!(output, "{} does not apply to this repository", hook.id)?;
+        }
+    }
+    Ok(code)
+}
+
+/// Fail for the `exclude` patterns of the configs that don't exclude any file.
+///
+/// Only the `files` pattern is taken into account for the files of a hook, not its types.
+async fn check_useless_excludes(filenames: &[&String], output: &mut String) -> Result<i32> {
+    let files = git::get_all_files().await?;
+
+    let mut code = 0;
+    for filename in filenames {
+        let config = read_config(Path::new(filename.as_str()))?;
+        let glob = config.files_glob.unwrap_or(false);
+        let prefix = config.files_prefix(Path::new(filename.as_str()));
+        let prefix = prefix.as_deref();
+
+        if let Some(exclude) = &config.exclude {
+            if !excludes_any(&files, None, exclude, glob, prefix)? {
+                code = 1;
+                writeln!(
+                    output,
+                    "The global exclude pattern `{}` does not match any files",
+                    exclude.patterns().join("`, `")
+                )?;
+            }
+        }
+
+        for repo in &config.repos {
+            let hooks = match repo {
+                ConfigRepo::Remote(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|h| {
+                        (
+                            h.id.as_str(),
+                            h.files.as_deref(),
+                            h.exclude.as_ref(),
+                            h.files_glob,
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+                ConfigRepo::Local(repo) => repo
+                    .hooks
+                    .iter()
+                    .map(|h| {
+                        (
+                            h.id.as_str(),
+                            h.files.as_deref(),
+                            h.exclude.as_ref(),
+                            h.files_glob,
+                        )
+                    })
+                    .collect(),
+                ConfigRepo::Meta(_) => continue,
+            };
+            for (id, include, exclude, files_glob) in hooks {
+                let Some(exclude) = exclude else {
+                    continue;
+                };
+                if !excludes_any(&files, include, exclude, files_glob.unwrap_or(glob), prefix)? {
+                    code = 1;
+                    writeln!(
+                        output,
+                        "The exclude pattern `{}` for `{id}` does not match any files",
+                        exclude.patterns().join("`, `")
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Whether `exclude` matches any of the files matched by `include`.
+fn excludes_any(
+    files: &[String],
+    include: Option<&str>,
+    exclude: &ExcludePatterns,
+    glob: bool,
+    prefix: Option<&str>,
+) -> Result<bool> {
+    let include = FilenameFilter::new(include, None, glob)?.with_prefix(prefix);
+    let exclude = FilenameFilter::new(None, Some(exclude), glob)?.with_prefix(prefix);
+    Ok(files
+        .iter()
+        .any(|file| include.filter(file) && !exclude.filter(file)))
+}
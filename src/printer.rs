@@ -20,11 +20,36 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use anstream::{eprint, print};
-use indicatif::ProgressDrawTarget;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Mutex, PoisonError};
+
+use anstream::stream::{AsLockedWrite, RawStream};
+use anstream::{AutoStream, ColorChoice};
+use indicatif::{MultiProgress, ProgressDrawTarget};
+
+/// The lock all console writes go through, with the progress bars being drawn, if any.
+///
+/// Holding it for a whole write keeps the lines of concurrent writers from interleaving,
+/// and suspending the progress bars keeps them from drawing over the lines.
+static RENDER_LOCK: Mutex<Option<MultiProgress>> = Mutex::new(None);
+
+/// Run `write` while holding the render lock.
+pub(crate) fn render<R>(write: impl FnOnce() -> R) -> R {
+    let progress = RENDER_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    match progress.as_ref() {
+        Some(progress) => progress.suspend(write),
+        None => write(),
+    }
+}
+
+/// Set the progress bars to suspend while writing, or `None` when they are done.
+pub(crate) fn set_progress(progress: Option<MultiProgress>) {
+    *RENDER_LOCK.lock().unwrap_or_else(PoisonError::into_inner) = progress;
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Printer {
+pub enum Mode {
     /// A printer that prints to standard streams (e.g., stdout).
     Default,
     /// A printer that suppresses all output.
@@ -35,80 +60,153 @@ pub enum Printer {
     NoProgress,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Printer {
+    mode: Mode,
+    /// Whether the output keeps its ANSI colors, from `--color`.
+    color: ColorChoice,
+}
+
 impl Printer {
+    pub fn new(mode: Mode, color: ColorChoice) -> Self {
+        Self { mode, color }
+    }
+
+    pub fn mode(self) -> Mode {
+        self.mode
+    }
+
+    /// The same printer, with the output of `mode`.
+    #[must_use]
+    pub fn with_mode(self, mode: Mode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// The color choice of the output, also for the commands whose output is shown.
+    pub fn color(self) -> ColorChoice {
+        self.color
+    }
+
     /// Return the [`ProgressDrawTarget`] for this printer.
     pub fn target(self) -> ProgressDrawTarget {
-        match self {
-            Self::Default => ProgressDrawTarget::stderr(),
-            Self::Quiet => ProgressDrawTarget::hidden(),
+        match self.mode {
+            Mode::Default => ProgressDrawTarget::stderr(),
+            Mode::Quiet => ProgressDrawTarget::hidden(),
             // Confusingly, hide the progress bar when in verbose mode.
             // Otherwise, it gets interleaved with debug messages.
-            Self::Verbose => ProgressDrawTarget::hidden(),
-            Self::NoProgress => ProgressDrawTarget::hidden(),
+            Mode::Verbose => ProgressDrawTarget::hidden(),
+            Mode::NoProgress => ProgressDrawTarget::hidden(),
         }
     }
 
     /// Return the [`Stdout`] for this printer.
     pub fn stdout(self) -> Stdout {
-        match self {
-            Self::Default => Stdout::Enabled,
-            Self::Quiet => Stdout::Disabled,
-            Self::Verbose => Stdout::Enabled,
-            Self::NoProgress => Stdout::Enabled,
+        match self.mode {
+            Mode::Default => Stdout::Enabled(self.color),
+            Mode::Quiet => Stdout::Disabled,
+            Mode::Verbose => Stdout::Enabled(self.color),
+            Mode::NoProgress => Stdout::Enabled(self.color),
         }
     }
 
     /// Return the [`Stderr`] for this printer.
     pub fn stderr(self) -> Stderr {
-        match self {
-            Self::Default => Stderr::Enabled,
-            Self::Quiet => Stderr::Disabled,
-            Self::Verbose => Stderr::Enabled,
-            Self::NoProgress => Stderr::Enabled,
+        match self.mode {
+            Mode::Default => Stderr::Enabled(self.color),
+            Mode::Quiet => Stderr::Disabled,
+            Mode::Verbose => Stderr::Enabled(self.color),
+            Mode::NoProgress => Stderr::Enabled(self.color),
         }
     }
 }
 
+/// Write `s` to `raw`, stripping the ANSI codes unless `color` keeps them.
+fn write_colored(raw: impl RawStream + AsLockedWrite, color: ColorChoice, s: &str) -> fmt::Result {
+    match render(|| AutoStream::new(raw, color).write_all(s.as_bytes())) {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(fmt::Error),
+        Err(_) | Ok(()) => Ok(()),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Stdout {
-    Enabled,
+    Enabled(ColorChoice),
     Disabled,
 }
 
-impl std::fmt::Write for Stdout {
-    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+impl fmt::Write for Stdout {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
         match self {
-            Self::Enabled => {
-                #[allow(clippy::print_stdout, clippy::ignored_unit_patterns)]
-                {
-                    print!("{s}");
-                }
-            }
-            Self::Disabled => {}
+            Self::Enabled(color) => write_colored(io::stdout(), *color, s),
+            Self::Disabled => Ok(()),
         }
+    }
 
-        Ok(())
+    /// Format first, so a `write!` is a single write under the render lock.
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        match self {
+            Self::Enabled(_) => self.write_str(&args.to_string()),
+            Self::Disabled => Ok(()),
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Stderr {
-    Enabled,
+    Enabled(ColorChoice),
     Disabled,
 }
 
-impl std::fmt::Write for Stderr {
-    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+impl fmt::Write for Stderr {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
         match self {
-            Self::Enabled => {
-                #[allow(clippy::print_stderr, clippy::ignored_unit_patterns)]
-                {
-                    eprint!("{s}");
-                }
-            }
-            Self::Disabled => {}
+            Self::Enabled(color) => write_colored(io::stderr(), *color, s),
+            Self::Disabled => Ok(()),
+        }
+    }
+
+    /// Format first, so a `write!` is a single write under the render lock.
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+        match self {
+            Self::Enabled(_) => self.write_str(&args.to_string()),
+            Self::Disabled => Ok(()),
         }
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::render;
+
+    /// The writes of concurrent writers never interleave.
+    #[test]
+    fn concurrent_writers() {
+        let sink = Mutex::new(String::new());
+        std::thread::scope(|scope| {
+            for writer in 0..8 {
+                let sink = &sink;
+                scope.spawn(move || {
+                    let line = format!("{}\n", writer.to_string().repeat(50));
+                    for _ in 0..100 {
+                        render(|| {
+                            // Write a char at a time, to give the other writers a chance to cut in.
+                            for c in line.chars() {
+                                sink.lock().unwrap().push(c);
+                                std::thread::yield_now();
+                            }
+                        });
+                    }
+                });
+            }
+        });
+
+        let output = sink.into_inner().unwrap();
+        assert_eq!(output.lines().count(), 800);
+        for line in output.lines() {
+            assert_eq!(line.len(), 50);
+            assert!(line.chars().all(|c| line.starts_with(c)), "{line}");
+        }
     }
 }
@@ -36,6 +36,8 @@ use owo_colors::OwoColorize;
 use thiserror::Error;
 use tracing::trace;
 
+use crate::git::mask_url;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// An error from executing a Command
@@ -58,9 +60,29 @@ pub enum Error {
         summary: String,
         /// What status the Command returned
         status: ExitStatus,
+        /// What the Command wrote to stderr, if it was captured
+        #[source]
+        stderr: Option<Stderr>,
     },
 }
 
+/// The captured stderr of a failed Command
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct Stderr(String);
+
+impl Stderr {
+    fn from_output(stderr: &[u8]) -> Option<Self> {
+        let stderr = String::from_utf8_lossy(stderr);
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            None
+        } else {
+            Some(Self(stderr.to_string()))
+        }
+    }
+}
+
 /// A fancier Command, see the crate's top-level docs!
 pub struct Cmd {
     /// The inner Command, in case you need to access it
@@ -130,14 +152,22 @@ impl Cmd {
     }
 
     /// Equivalent to [`std::process::Command::output`][],
-    /// but logged, with the error wrapped, and status checked (by default)
+    /// but logged, with the error wrapped, and status checked (by default).
+    ///
+    /// If the status check fails, the captured stderr is attached to the error.
     pub async fn output(&mut self) -> Result<std::process::Output> {
         self.log_command();
         let res = self.inner.output().await.map_err(|cause| Error::Exec {
             summary: self.summary.clone(),
             cause,
         })?;
-        self.maybe_check_status(res.status)?;
+        if self.check_status && !res.status.success() {
+            return Err(Error::Status {
+                summary: self.summary.clone(),
+                status: res.status,
+                stderr: Stderr::from_output(&res.stderr),
+            });
+        }
         Ok(res)
     }
 
@@ -260,6 +290,7 @@ impl Cmd {
             Err(Error::Status {
                 summary: self.summary.clone(),
                 status,
+                stderr: None,
             })
         }
     }
@@ -280,7 +311,8 @@ impl Cmd {
     }
 }
 
-/// Simplified Command Debug output, with args truncated if they're too long.
+/// Simplified Command Debug output, with args truncated if they're too long, and the credentials
+/// of URL args masked.
 impl std::fmt::Display for Cmd {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(cwd) = self.get_current_dir() {
@@ -291,13 +323,13 @@ impl std::fmt::Display for Cmd {
         if let Some(arg) = args.next() {
             write!(f, "{} ", program.to_string_lossy().cyan())?;
             if arg != program {
-                write!(f, "{}", arg.to_string_lossy().dimmed())?;
+                write!(f, "{}", mask_url(&arg.to_string_lossy()).dimmed())?;
             }
         }
 
         let mut len = 0;
         for arg in args {
-            write!(f, " {}", arg.to_string_lossy().dimmed())?;
+            write!(f, " {}", mask_url(&arg.to_string_lossy()).dimmed())?;
             len += arg.len() + 1;
             if len > 100 {
                 write!(f, " {}", "[...]".dimmed())?;
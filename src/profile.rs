@@ -0,0 +1,60 @@
+//! Timings of a run, written by `prefligit run --profile`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// The timings of a run, all durations are in seconds.
+#[derive(Debug, Default, Serialize)]
+pub struct Profile {
+    /// The whole run.
+    pub total: f64,
+    /// Preparing the hook repos, including cloning them.
+    pub clone: f64,
+    /// Installing each hook environment.
+    pub install: Vec<InstallTiming>,
+    /// Collecting and filtering the files to run on.
+    pub files: FilesTiming,
+    /// Running each hook.
+    pub hooks: Vec<HookTiming>,
+    /// The batches of the hooks currently running, keyed on [`Hook::idx`] since hook ids may
+    /// repeat, moved to [`HookTiming`] when the hook finishes.
+    ///
+    /// [`Hook::idx`]: crate::hook::Hook::idx
+    #[serde(skip)]
+    pub pending_batches: HashMap<(usize, usize), Vec<BatchTiming>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstallTiming {
+    pub hook: String,
+    pub duration: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FilesTiming {
+    pub count: usize,
+    pub duration: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HookTiming {
+    pub id: String,
+    pub duration: f64,
+    /// The batches of files the hook ran on, which may run in parallel.
+    pub batches: Vec<BatchTiming>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchTiming {
+    pub files: usize,
+    pub duration: f64,
+}
+
+/// Record timings into the `profile`, if enabled.
+pub fn record(profile: Option<&RefCell<Profile>>, f: impl FnOnce(&mut Profile)) {
+    if let Some(profile) = profile {
+        f(&mut profile.borrow_mut());
+    }
+}
@@ -1,66 +1,219 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::cmp::max;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
 use std::future::Future;
 use std::io::Write as _;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
 
 use anstream::{eprintln, ColorChoice};
-use anyhow::Result;
-use fancy_regex::{self as regex, Regex};
+use anyhow::{Context, Result};
+use fancy_regex::Regex;
+use futures::StreamExt;
+use globset::{GlobBuilder, GlobMatcher};
 use owo_colors::{OwoColorize, Style};
 use rand::prelude::{SliceRandom, StdRng};
 use rand::SeedableRng;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use serde::Serialize;
 use tokio::task::JoinSet;
 use tracing::{error, trace};
 use unicode_width::UnicodeWidthStr;
 
 use crate::cleanup::add_cleanup;
-use crate::cli::ExitStatus;
+use crate::cli::{ExitStatus, OutputFormat};
+use crate::config::{ExcludePatterns, Language, Stage};
 use crate::fs::Simplified;
 use crate::git;
 use crate::git::{get_diff, git_cmd, GIT};
-use crate::hook::Hook;
-use crate::identify::tags_from_path;
-use crate::printer::Printer;
+use crate::hook::{Hook, Repo};
+use crate::identify::{file_kind_tag, is_file_kind_tag, tags_from_path};
+use crate::meta_hooks;
+use crate::printer::{Mode, Printer, Stdout};
+use crate::profile::{self, BatchTiming, HookTiming, Profile};
 use crate::store::Store;
 
 const SKIPPED: &str = "Skipped";
 const NO_FILES: &str = "(no files to check)";
 
+/// A gitignore-style glob pattern.
+struct GlobPattern {
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// A list of gitignore-style glob patterns, one per line.
+///
+/// Like gitignore, a pattern without a slash matches at any depth, a leading slash anchors the
+/// pattern to the repository root, a trailing slash only matches directories, and a leading `!`
+/// negates the pattern. The last matching pattern wins.
+struct GlobPatterns(Vec<GlobPattern>);
+
+impl GlobPatterns {
+    fn new(patterns: &str) -> Result<Self, globset::Error> {
+        let mut globs = Vec::new();
+        for line in patterns.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let dir_only = line.ends_with('/');
+            let line = line.trim_end_matches('/');
+            let glob = if line.contains('/') {
+                line.trim_start_matches('/').to_string()
+            } else {
+                format!("**/{line}")
+            };
+
+            let matcher = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()?
+                .compile_matcher();
+            globs.push(GlobPattern {
+                matcher,
+                negated,
+                dir_only,
+            });
+        }
+        Ok(Self(globs))
+    }
+
+    fn is_match(&self, filename: &str) -> bool {
+        let mut matched = false;
+        for glob in &self.0 {
+            // A file also matches if any of its parent directories matches.
+            let mut candidates = Path::new(filename)
+                .ancestors()
+                .filter(|path| !path.as_os_str().is_empty());
+            if glob.dir_only {
+                candidates.next();
+            }
+            if candidates.any(|path| glob.matcher.is_match(path)) {
+                matched = !glob.negated;
+            }
+        }
+        matched
+    }
+}
+
+/// A `files` or `exclude` pattern, either regexes or gitignore-style globs.
+///
+/// A list of patterns matches if any of them matches.
+enum FilePattern {
+    Regex(Vec<Regex>),
+    Glob(GlobPatterns),
+}
+
+impl FilePattern {
+    fn new(patterns: &[&str], glob: bool) -> Result<Self> {
+        if glob {
+            // Each glob pattern is a line, so a list is the same as the lines joined.
+            let pattern = patterns.join("\n");
+            let globs = GlobPatterns::new(&pattern)
+                .with_context(|| format!("Invalid glob pattern: `{pattern}`"))?;
+            Ok(Self::Glob(globs))
+        } else {
+            let regexes = patterns
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern)
+                        .with_context(|| format!("Invalid regex pattern: `{pattern}`"))
+                })
+                .collect::<Result<_>>()?;
+            Ok(Self::Regex(regexes))
+        }
+    }
+
+    fn is_match(&self, filename: &str) -> bool {
+        match self {
+            Self::Regex(regexes) => regexes
+                .iter()
+                .any(|re| re.is_match(filename).unwrap_or(false)),
+            Self::Glob(globs) => globs.is_match(filename),
+        }
+    }
+}
+
 /// Filter filenames by include/exclude patterns.
 pub struct FilenameFilter {
-    include: Option<Regex>,
-    exclude: Option<Regex>,
+    include: Option<FilePattern>,
+    exclude: Option<FilePattern>,
+    /// Match the patterns against the path relative to this directory, like `sub/`.
+    prefix: Option<String>,
 }
 
 impl FilenameFilter {
-    pub fn new(include: Option<&str>, exclude: Option<&str>) -> Result<Self, Box<regex::Error>> {
-        let include = include.map(Regex::new).transpose()?;
-        let exclude = exclude.map(Regex::new).transpose()?;
-        Ok(Self { include, exclude })
+    pub fn new(
+        include: Option<&str>,
+        exclude: Option<&ExcludePatterns>,
+        glob: bool,
+    ) -> Result<Self> {
+        let include = include.map(|p| FilePattern::new(&[p], glob)).transpose()?;
+        let exclude = exclude
+            .map(|p| FilePattern::new(&p.patterns(), glob))
+            .transpose()?;
+        Ok(Self {
+            include,
+            exclude,
+            prefix: None,
+        })
+    }
+
+    /// Match the patterns relative to the `prefix` directory, see `files_relative`.
+    #[must_use]
+    pub fn with_prefix(mut self, prefix: Option<&str>) -> Self {
+        self.prefix = prefix.map(ToString::to_string);
+        self
     }
 
     pub fn filter(&self, filename: impl AsRef<str>) -> bool {
-        let filename = filename.as_ref();
-        if let Some(re) = &self.include {
-            if !re.is_match(filename).unwrap_or(false) {
+        let mut filename = filename.as_ref();
+        if let Some(prefix) = &self.prefix {
+            // Files outside the directory match no pattern.
+            let Some(relative) = filename.strip_prefix(prefix.as_str()) else {
+                return self.include.is_none();
+            };
+            filename = relative;
+        }
+        if let Some(pattern) = &self.include {
+            if !pattern.is_match(filename) {
                 return false;
             }
         }
-        if let Some(re) = &self.exclude {
-            if re.is_match(filename).unwrap_or(false) {
+        if let Some(pattern) = &self.exclude {
+            if pattern.is_match(filename) {
                 return false;
             }
         }
         true
     }
 
-    pub fn from_hook(hook: &Hook) -> Result<Self, Box<regex::Error>> {
-        Self::new(hook.files.as_deref(), hook.exclude.as_deref())
+    pub fn from_hook(hook: &Hook) -> Result<Self> {
+        let include = hook
+            .files
+            .as_deref()
+            .map(|p| FilePattern::new(&[p], hook.files_glob))
+            .transpose()?;
+        let exclude = hook
+            .exclude
+            .as_ref()
+            .map(|p| FilePattern::new(&p.patterns(), hook.exclude_glob))
+            .transpose()?;
+        Ok(Self {
+            include,
+            exclude,
+            prefix: hook.files_prefix.clone(),
+        })
     }
 }
 
@@ -100,6 +253,16 @@ impl<'a> FileTagFilter<'a> {
     fn from_hook(hook: &'a Hook) -> Self {
         Self::new(&hook.types, &hook.types_or, &hook.exclude_types)
     }
+
+    /// Whether the filter only checks the kind of files, like the default `types: [file]`,
+    /// so the contents of files don't need to be read to identify them.
+    fn only_file_kinds(&self) -> bool {
+        self.all
+            .iter()
+            .chain(self.any)
+            .chain(self.exclude)
+            .all(|tag| is_file_kind_tag(tag))
+    }
 }
 
 fn status_line(start: &str, cols: usize, end_msg: &str, end_color: Style, postfix: &str) -> String {
@@ -122,68 +285,302 @@ fn calculate_columns(hooks: &[Hook]) -> usize {
     max(80, name_len + 3 + NO_FILES.len() + 1 + SKIPPED.len())
 }
 
-/// Run all hooks.
+/// How the hooks of a run are run.
+#[derive(Clone, Copy)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct HookRunOptions<'a> {
+    /// All the hooks of the config, not only the ones run, for the meta hooks.
+    pub project_hooks: &'a [Hook],
+    pub stage: Stage,
+    pub fail_fast: bool,
+    pub keep_going_per_repo: bool,
+    pub show_diff_on_failure: Option<DiffOptions>,
+    pub output_format: OutputFormat,
+    pub hide_skipped: bool,
+    pub fail_on_modified: bool,
+    /// The maximum number of passes, the hooks run once if not set.
+    pub fixer_passes: Option<usize>,
+    /// The number of read-only hooks run at once.
+    pub jobs: usize,
+    pub bail_after: Option<NonZeroUsize>,
+    pub fail_fast_on_error: bool,
+    pub no_git: bool,
+    /// The untracked files passed by `--files`, whose modifications `git diff` can't see and
+    /// are detected by their content, like all the files without git.
+    pub untracked_files: &'a HashSet<String>,
+    pub docker_jobs: Option<NonZeroUsize>,
+    pub profile: Option<&'a RefCell<Profile>>,
+    pub verbose: bool,
+}
+
+/// Run all hooks, returning the overall status and whether each hook passed.
 pub async fn run_hooks(
     hooks: &[Hook],
     skips: &[String],
     filenames: Vec<String>,
     env_vars: HashMap<&'static str, String>,
-    fail_fast: bool,
-    show_diff_on_failure: bool,
-    verbose: bool,
+    options: HookRunOptions<'_>,
     printer: Printer,
-) -> Result<ExitStatus> {
+) -> Result<(ExitStatus, Vec<(String, bool)>)> {
+    let HookRunOptions {
+        fail_fast,
+        keep_going_per_repo,
+        show_diff_on_failure,
+        output_format,
+        fixer_passes,
+        jobs,
+        bail_after,
+        fail_fast_on_error,
+        no_git,
+        ..
+    } = options;
     let env_vars = Arc::new(env_vars);
 
+    // The JSON report and the summary replace the human-readable output.
+    let report_printer = printer;
+    let printer = if matches!(output_format, OutputFormat::Json | OutputFormat::Summary) {
+        printer.with_mode(Mode::Quiet)
+    } else {
+        printer
+    };
+
     let columns = calculate_columns(hooks);
+    let file_tags = FileTags::new(&filenames);
     // TODO: progress bar, format output
     let mut success = true;
+    let mut results = Vec::with_capacity(hooks.len());
+    let mut reports: Vec<(&Hook, HookReport)> = Vec::with_capacity(hooks.len());
+
+    let mut diff = work_tree_diff(no_git).await?;
+    // Whether any hook modified files, in any pass.
+    let mut files_modified = false;
+    let passes = fixer_passes.unwrap_or(1);
+    // The first pass runs all hooks, the next passes all the hooks that may modify files, so a
+    // fixer also sees the changes of the fixers after it.
+    let mut pass_hooks = hooks.iter().collect::<Vec<_>>();
+    for pass in 1..=passes {
+        if pass > 1 {
+            writeln!(
+                printer.stdout(),
+                "{}",
+                format!(
+                    "Files were modified by hooks, re-running the hooks that may modify files (pass {pass}/{passes})"
+                )
+                .yellow()
+            )?;
+        }
 
-    let mut diff = get_diff().await?;
-    // hooks must run in serial
-    for hook in hooks {
-        let (hook_success, new_diff) = run_hook(
-            hook,
-            &filenames,
-            env_vars.clone(),
-            skips,
-            diff,
-            columns,
-            verbose,
-            printer,
-        )
-        .await?;
+        // A hook fails the run if it failed in any pass, the same as modifying files does.
+        let mut pass_success = true;
+        let mut pass_modified = false;
+        let mut stopped = false;
+        let mut failed = 0;
+        // The repos stopped by a failed `fail_fast` hook, with `--keep-going-per-repo`.
+        let mut stopped_repos: Vec<&Repo> = Vec::new();
+
+        // Consecutive read-only hooks run concurrently, the other hooks run one at a time.
+        let mut remaining = &pass_hooks[..];
+        'groups: while !remaining.is_empty() {
+            let len = if jobs > 1 && remaining[0].is_read_only() {
+                remaining
+                    .iter()
+                    .take_while(|hook| hook.is_read_only())
+                    .count()
+            } else {
+                1
+            };
+            let (group, rest) = remaining.split_at(len);
+            remaining = rest;
+
+            let is_stopped = |stopped_repos: &[&Repo], hook: &Hook| {
+                stopped_repos
+                    .iter()
+                    .any(|&repo| std::ptr::eq(repo, hook.repo()))
+            };
+            let group = group
+                .iter()
+                .copied()
+                .filter(|hook| !is_stopped(&stopped_repos, hook))
+                .collect::<Vec<_>>();
+            if group.is_empty() {
+                continue;
+            }
+
+            // Each hook of the group gets the diff from before the group, a single hook takes it.
+            let concurrent = group.len() > 1;
+            let mut diffs = (1..group.len()).map(|_| diff.clone()).collect::<Vec<_>>();
+            diffs.push(std::mem::take(&mut diff));
+
+            // The outcomes are handled in order as they complete, so a hook stopping the run
+            // stops polling the concurrent hooks after it.
+            let mut outcomes =
+                futures::stream::iter(group.iter().zip(diffs).map(|(&hook, diff)| {
+                    let env_vars = env_vars.clone();
+                    let filenames = &filenames;
+                    let file_tags = &file_tags;
+                    async move {
+                        // The output of concurrent hooks is kept until it's done, and printed in order.
+                        let stdout = hook_printer(hook, printer, output_format).stdout();
+                        let mut out = if concurrent {
+                            HookOutput::Buffered(stdout, String::new())
+                        } else {
+                            HookOutput::Console(stdout)
+                        };
+                        let result = run_hook(
+                            hook,
+                            filenames,
+                            file_tags,
+                            env_vars,
+                            skips,
+                            diff,
+                            columns,
+                            options,
+                            printer.color(),
+                            &mut out,
+                        )
+                        .await?;
+                        anyhow::Ok((hook, result, out))
+                    }
+                }))
+                .buffered(jobs);
+
+            while let Some(outcome) = outcomes.next().await {
+                let (hook, (report, new_diff), out) = outcome?;
+                // A hook run concurrently may be stopped by a `fail_fast` hook before it.
+                if is_stopped(&stopped_repos, hook) {
+                    continue;
+                }
+                out.finish()?;
+
+                let hook_success = !report.status.is_failure();
+
+                let report_status = report.status;
+                success &= hook_success;
+                pass_success &= hook_success;
+                files_modified |= report.files_modified;
+                pass_modified |= report.files_modified;
+                // A hook run again keeps a single report, covering all its passes.
+                match reports
+                    .iter_mut()
+                    .find(|(reported, _)| std::ptr::eq(*reported, hook))
+                {
+                    Some((_, previous)) => previous.merge(&report),
+                    None => reports.push((hook, report)),
+                }
+                // Hooks skipped by `SKIP` didn't run, so they neither passed nor failed.
+                if !skips.contains(&hook.id) && !skips.contains(&hook.alias) {
+                    // The same hook id may be used by several repos, it only passes if all of them pass.
+                    match results.iter_mut().find(|(id, _)| id == &hook.id) {
+                        Some((_, passed)) => *passed &= hook_success,
+                        None => results.push((hook.id.clone(), hook_success)),
+                    }
+                }
+                diff = new_diff;
+                if !hook_success {
+                    failed += 1;
+                }
+                if keep_going_per_repo {
+                    // Only stop the hooks of the same repo, the other repos keep going.
+                    if !hook_success && (fail_fast || hook.fail_fast) {
+                        stopped_repos.push(hook.repo());
+                    }
+                } else if !pass_success && (fail_fast || hook.fail_fast) {
+                    stopped = true;
+                    break 'groups;
+                }
+                if fail_fast_on_error && report_status == HookStatus::Errored {
+                    writeln!(
+                        printer.stdout(),
+                        "{}",
+                        format!("Stopped because hook `{}` errored", hook.id).red()
+                    )?;
+                    stopped = true;
+                    break 'groups;
+                }
+                if bail_after.is_some_and(|bail_after| failed >= bail_after.get()) {
+                    writeln!(
+                        printer.stdout(),
+                        "{}",
+                        format!(
+                            "Stopped after {failed} failed hook{}",
+                            if failed == 1 { "" } else { "s" }
+                        )
+                        .red()
+                    )?;
+                    stopped = true;
+                    break 'groups;
+                }
+            }
+        }
 
-        success &= hook_success;
-        diff = new_diff;
-        if !success && (fail_fast || hook.fail_fast) {
+        // Stop once a pass leaves the files untouched.
+        if stopped || !pass_modified || fixer_passes.is_none() {
             break;
         }
+        if pass == passes {
+            writeln!(
+                printer.stdout(),
+                "{}",
+                format!("Files were still modified by hooks after {passes} passes").red()
+            )?;
+        }
+        pass_hooks.retain(|hook| !hook.is_read_only());
+    }
+    let reports = reports
+        .into_iter()
+        .map(|(_, report)| report)
+        .collect::<Vec<_>>();
+
+    match output_format {
+        OutputFormat::Json => {
+            writeln!(
+                report_printer.stdout(),
+                "{}",
+                serde_json::to_string_pretty(&reports)?
+            )?;
+        }
+        OutputFormat::Summary => {
+            write!(report_printer.stdout(), "{}", summary(&reports, success))?;
+        }
+        OutputFormat::Text | OutputFormat::Github => {}
     }
 
-    if !success && show_diff_on_failure {
+    // The diff only shows something useful if a hook modified files.
+    if let Some(diff_options) = show_diff_on_failure
+        .filter(|_| !success && files_modified && output_format != OutputFormat::Json)
+    {
         writeln!(printer.stdout(), "All changes made by hooks:")?;
-        let color = match ColorChoice::global() {
+        let color = match diff_options.color.unwrap_or(printer.color()) {
             ColorChoice::Auto => "--color=auto",
             ColorChoice::Always | ColorChoice::AlwaysAnsi => "--color=always",
             ColorChoice::Never => "--color=never",
         };
-        git_cmd("git diff")?
-            .arg("--no-pager")
+        let mut cmd = git_cmd("git diff")?;
+        cmd.arg("--no-pager")
             .arg("diff")
             .arg("--no-ext-diff")
-            .arg(color)
-            .check(true)
-            .spawn()?
-            .wait()
-            .await?;
+            .arg(color);
+        if let Some(context) = diff_options.context {
+            cmd.arg(format!("--unified={context}"));
+        }
+        cmd.check(true).spawn()?.wait().await?;
     };
 
-    if success {
-        Ok(ExitStatus::Success)
+    let status = if success {
+        ExitStatus::Success
     } else {
-        Ok(ExitStatus::Failure)
-    }
+        ExitStatus::Failure
+    };
+    Ok((status, results))
+}
+
+/// How to print the changes made by hooks, for `--show-diff-on-failure`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffOptions {
+    /// The number of context lines, git's default if not set.
+    pub context: Option<u32>,
+    /// Whether to color the diff, the color choice of the printer if not set.
+    pub color: Option<ColorChoice>,
 }
 
 /// Shuffle the files so that they more evenly fill out the xargs
@@ -194,19 +591,473 @@ fn shuffle<T>(filenames: &mut [T]) {
     filenames.shuffle(&mut rng);
 }
 
-async fn run_hook(
+/// Hash the contents of the files, to detect modifications that `git diff` can't see,
+/// in untracked files passed by `--files` or without git.
+fn hash_files(filenames: &[&String]) -> Vec<Option<md5::Digest>> {
+    filenames
+        .par_iter()
+        .map(|filename| fs_err::read(filename).ok().map(md5::compute))
+        .collect()
+}
+
+/// The counts of the hook results, and the ids of the failed hooks, for `--summary-only`.
+fn summary(reports: &[HookReport], success: bool) -> String {
+    let count = |status| {
+        reports
+            .iter()
+            .filter(|report| report.status == status)
+            .count()
+    };
+    let result = if success {
+        "Passed".green().to_string()
+    } else {
+        "Failed".red().to_string()
+    };
+    let mut summary = format!(
+        "{result}: {} passed, {} failed, {} skipped\n",
+        count(HookStatus::Passed),
+        count(HookStatus::Failed) + count(HookStatus::Errored),
+        count(HookStatus::Skipped)
+    );
+
+    // The same hook id may be used by several repos.
+    let mut failed: Vec<&str> = Vec::new();
+    for report in reports {
+        if report.status.is_failure() && !failed.contains(&report.id.as_str()) {
+            failed.push(&report.id);
+        }
+    }
+    if !failed.is_empty() {
+        let _ = writeln!(summary, "Failed hooks: {}", failed.join(", "));
+    }
+    summary
+}
+
+/// The outcome of a hook.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum HookStatus {
+    Passed,
+    /// The hook ran and found problems, or modified files.
+    Failed,
+    /// The hook couldn't run, e.g. its executable can't be spawned or its environment is broken.
+    Errored,
+    Skipped,
+}
+
+impl HookStatus {
+    fn is_failure(self) -> bool {
+        matches!(self, Self::Failed | Self::Errored)
+    }
+}
+
+/// The result of a hook, as reported by `--output-format json`.
+#[derive(Debug, Serialize)]
+struct HookReport {
+    id: String,
+    name: String,
+    stage: String,
+    status: HookStatus,
+    /// The files matched by the hook.
+    files: Vec<String>,
+    /// The exit code, `None` if the hook was skipped or errored.
+    exit_code: Option<i32>,
+    duration_ms: u128,
+    files_modified: bool,
+    output: String,
+}
+
+impl HookReport {
+    /// Merge the report of a later pass of the same hook, which fails if any pass failed.
+    fn merge(&mut self, rerun: &Self) {
+        if !self.status.is_failure() {
+            self.status = rerun.status;
+            self.exit_code = rerun.exit_code;
+        }
+        self.duration_ms += rerun.duration_ms;
+        self.files_modified |= rerun.files_modified;
+        self.output.push_str(&rerun.output);
+    }
+
+    fn skipped(hook: &Hook, stage: Stage, files: Vec<String>) -> Self {
+        Self {
+            id: hook.id.clone(),
+            name: hook.name.clone(),
+            stage: stage.to_string(),
+            status: HookStatus::Skipped,
+            files,
+            exit_code: None,
+            duration_ms: 0,
+            files_modified: false,
+            output: String::new(),
+        }
+    }
+}
+
+/// The tags of the files of a run, identified at most once per file and shared by its hooks.
+///
+/// The files selected by a filter are also kept, so hooks with identical filters compile their
+/// patterns and match the files only once.
+#[derive(Default)]
+pub(crate) struct FileTags<'a> {
+    files: HashMap<&'a str, FileTagsEntry>,
+    selected: Mutex<HashMap<FilterKey, Arc<OnceLock<Vec<&'a String>>>>>,
+}
+
+/// The fields of a hook that select its files.
+#[derive(PartialEq, Eq, Hash)]
+struct FilterKey {
+    files: Option<String>,
+    exclude: Option<ExcludePatterns>,
+    files_glob: bool,
+    exclude_glob: bool,
+    files_prefix: Option<String>,
+    types: Vec<String>,
+    types_or: Vec<String>,
+    exclude_types: Vec<String>,
+}
+
+impl FilterKey {
+    fn from_hook(hook: &Hook) -> Self {
+        Self {
+            files: hook.files.clone(),
+            exclude: hook.exclude.clone(),
+            files_glob: hook.files_glob,
+            exclude_glob: hook.exclude_glob,
+            files_prefix: hook.files_prefix.clone(),
+            types: hook.types.clone(),
+            types_or: hook.types_or.clone(),
+            exclude_types: hook.exclude_types.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct FileTagsEntry {
+    /// Only the kind of the file, which doesn't need to read it.
+    kind: OnceLock<Option<Vec<&'static str>>>,
+    all: OnceLock<Option<Vec<&'static str>>>,
+}
+
+impl<'a> FileTags<'a> {
+    pub(crate) fn new(filenames: &'a [String]) -> Self {
+        let files = filenames
+            .iter()
+            .map(|filename| (filename.as_str(), FileTagsEntry::default()))
+            .collect();
+        Self {
+            files,
+            selected: Mutex::default(),
+        }
+    }
+
+    /// The tags of `filename`, or only its kind with `only_kind`.
+    ///
+    /// Returns `None` if the file can't be identified.
+    fn get(&self, filename: &str, only_kind: bool) -> Option<Cow<'_, [&'static str]>> {
+        let Some(entry) = self.files.get(filename) else {
+            return Self::identify(filename, only_kind).map(Cow::Owned);
+        };
+        let tags = if only_kind { &entry.kind } else { &entry.all };
+        tags.get_or_init(|| Self::identify(filename, only_kind))
+            .as_deref()
+            .map(Cow::Borrowed)
+    }
+
+    fn identify(filename: &str, only_kind: bool) -> Option<Vec<&'static str>> {
+        let path = Path::new(filename);
+        let tags = if only_kind {
+            file_kind_tag(path).map(|tag| vec![tag])
+        } else {
+            tags_from_path(path)
+        };
+        tags.inspect_err(|err| error!(filename, error = %err, "Failed to get tags"))
+            .ok()
+    }
+}
+
+/// Select the files the hook runs on, by its `files`, `exclude` and types.
+///
+/// The selection is shared with the other hooks of the run with the same filter.
+pub(crate) fn hook_filenames<'a>(
+    hook: &Hook,
+    filenames: &'a [String],
+    file_tags: &FileTags<'a>,
+) -> Result<Vec<&'a String>> {
+    let selected = file_tags
+        .selected
+        .lock()
+        .unwrap()
+        .entry(FilterKey::from_hook(hook))
+        .or_default()
+        .clone();
+    if let Some(selected) = selected.get() {
+        return Ok(selected.clone());
+    }
+
+    let filter = FilenameFilter::from_hook(hook)?;
+    let filenames = filenames
+        .into_par_iter()
+        .filter(|filename| filter.filter(filename))
+        .collect();
+    let filenames = filter_by_types(hook, filenames, file_tags);
+
+    Ok(selected.get_or_init(|| filenames).clone())
+}
+
+/// Select the files matching the `types`, `types_or` and `exclude_types` of the hook.
+fn filter_by_types<'a>(
+    hook: &Hook,
+    filenames: Vec<&'a String>,
+    file_tags: &FileTags,
+) -> Vec<&'a String> {
+    let filter = FileTagFilter::from_hook(hook);
+    let only_file_kinds = filter.only_file_kinds();
+    filenames
+        .into_par_iter()
+        .filter(|filename| {
+            file_tags
+                .get(filename, only_file_kinds)
+                .is_some_and(|tags| filter.filter(&tags))
+        })
+        .collect()
+}
+
+/// Print how the hook would be selected and run for `stage` and `filenames`, without running it.
+pub(crate) fn explain_hook(
     hook: &Hook,
     filenames: &[String],
+    stage: Stage,
+    skipped: bool,
+    printer: Printer,
+) -> Result<()> {
+    fn list(files: &[&String]) -> String {
+        const MAX: usize = 10;
+        let mut list = files
+            .iter()
+            .take(MAX)
+            .map(|f| f.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if files.len() > MAX {
+            let _ = write!(list, ", and {} more", files.len() - MAX);
+        }
+        list
+    }
+    fn count(files: &[&String]) -> String {
+        match files.len() {
+            0 => "no files".to_string(),
+            1 => format!("1 file: {}", list(files)),
+            n => format!("{n} files: {}", list(files)),
+        }
+    }
+
+    let mut out = printer.stdout();
+    writeln!(
+        out,
+        "Hook `{}` ({}) from {}",
+        hook.id,
+        hook.name,
+        hook.repo()
+    )?;
+
+    let stages = hook
+        .stages
+        .iter()
+        .map(|s| format!("`{s}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !hook.stages.contains(&stage) {
+        writeln!(
+            out,
+            "- stage: not selected, the hook runs at {stages}, not at `{stage}`"
+        )?;
+        return Ok(());
+    }
+    writeln!(out, "- stage: selected for `{stage}`")?;
+    if skipped {
+        writeln!(out, "- skip: skipped by `SKIP`")?;
+        return Ok(());
+    }
+
+    let candidates = filenames.iter().collect::<Vec<_>>();
+    writeln!(out, "- candidates: {}", count(&candidates))?;
+
+    let kind = |glob: bool| match (glob, hook.files_prefix.as_deref()) {
+        (false, None) => "regex".to_string(),
+        (true, None) => "glob".to_string(),
+        (false, Some(prefix)) => format!("regex relative to `{prefix}`"),
+        (true, Some(prefix)) => format!("glob relative to `{prefix}`"),
+    };
+    let matched = match hook.files.as_deref() {
+        Some(files) => {
+            let filter = FilenameFilter::new(Some(files), None, hook.files_glob)?
+                .with_prefix(hook.files_prefix.as_deref());
+            let matched = candidates
+                .into_iter()
+                .filter(|f| filter.filter(f))
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "- files ({} `{files}`): {}",
+                kind(hook.files_glob),
+                count(&matched)
+            )?;
+            matched
+        }
+        None => candidates,
+    };
+    let matched = match hook.exclude.as_ref() {
+        Some(exclude) => {
+            let filter = FilenameFilter::new(None, Some(exclude), hook.exclude_glob)?
+                .with_prefix(hook.files_prefix.as_deref());
+            let matched = matched
+                .into_iter()
+                .filter(|f| filter.filter(f))
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "- exclude ({} `{}`): {}",
+                kind(hook.exclude_glob),
+                exclude.patterns().join("`, `"),
+                count(&matched)
+            )?;
+            matched
+        }
+        None => matched,
+    };
+    let matched = filter_by_types(hook, matched, &FileTags::default());
+    writeln!(
+        out,
+        "- types {:?}, types_or {:?}, exclude_types {:?}: {}",
+        hook.types,
+        hook.types_or,
+        hook.exclude_types,
+        count(&matched)
+    )?;
+
+    if matched.is_empty() && !hook.always_run {
+        writeln!(
+            out,
+            "- result: skipped, no files to check and `always_run` is not set"
+        )?;
+        return Ok(());
+    }
+    if matched.is_empty() {
+        writeln!(out, "- always_run: runs without matched files")?;
+    }
+
+    if matches!(hook.repo(), Repo::Meta { .. }) {
+        writeln!(out, "- result: runs the `{}` meta hook in-process", hook.id)?;
+        return Ok(());
+    }
+    let mut command = vec![hook.entry.clone()];
+    command.extend(hook.args.iter().cloned());
+    if hook.pass_filenames {
+        writeln!(out, "- pass_filenames: the matched files are passed")?;
+        command.extend(matched.iter().map(|f| (*f).clone()));
+    } else {
+        writeln!(out, "- pass_filenames: no files are passed")?;
+    }
+    writeln!(
+        out,
+        "- result: runs `{}` with the `{}` language",
+        command.join(" "),
+        hook.language
+    )?;
+
+    Ok(())
+}
+
+/// The printer of a hook.
+///
+/// A hook explicitly marked `verbose` always shows its output, even under `--quiet`,
+/// but not in place of the JSON report or the summary.
+fn hook_printer(hook: &Hook, printer: Printer, output_format: OutputFormat) -> Printer {
+    if hook.verbose
+        && printer.mode() == Mode::Quiet
+        && !matches!(output_format, OutputFormat::Json | OutputFormat::Summary)
+    {
+        printer.with_mode(Mode::NoProgress)
+    } else {
+        printer
+    }
+}
+
+/// Where a hook writes its status line and output.
+enum HookOutput {
+    /// Written to the console as the hook runs.
+    Console(Stdout),
+    /// Kept until the hook is done, so concurrent hooks don't interleave their output.
+    Buffered(Stdout, String),
+}
+
+impl HookOutput {
+    /// Show the partial status line while the hook runs, when writing to the console.
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Console(_) => std::io::stdout().flush(),
+            Self::Buffered(..) => Ok(()),
+        }
+    }
+
+    /// Write the kept output to the console.
+    fn finish(self) -> std::fmt::Result {
+        match self {
+            Self::Console(_) => Ok(()),
+            Self::Buffered(mut stdout, buffer) => stdout.write_str(&buffer),
+        }
+    }
+}
+
+impl std::fmt::Write for HookOutput {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        match self {
+            Self::Console(stdout) => stdout.write_str(s),
+            Self::Buffered(_, buffer) => buffer.write_str(s),
+        }
+    }
+
+    fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> std::fmt::Result {
+        match self {
+            Self::Console(stdout) => stdout.write_fmt(args),
+            Self::Buffered(_, buffer) => buffer.write_fmt(args),
+        }
+    }
+}
+
+async fn run_hook<'a>(
+    hook: &Hook,
+    filenames: &'a [String],
+    file_tags: &FileTags<'a>,
     env_vars: Arc<HashMap<&'static str, String>>,
     skips: &[String],
     diff: Vec<u8>,
     columns: usize,
-    verbose: bool,
-    printer: Printer,
-) -> Result<(bool, Vec<u8>)> {
+    options: HookRunOptions<'_>,
+    color: ColorChoice,
+    out: &mut HookOutput,
+) -> Result<(HookReport, Vec<u8>)> {
+    let HookRunOptions {
+        project_hooks,
+        stage,
+        output_format,
+        hide_skipped,
+        fail_on_modified,
+        no_git,
+        untracked_files,
+        docker_jobs,
+        profile,
+        verbose,
+        ..
+    } = options;
     if skips.contains(&hook.id) || skips.contains(&hook.alias) {
+        let report = HookReport::skipped(hook, stage, vec![]);
+        if hide_skipped {
+            return Ok((report, diff));
+        }
         writeln!(
-            printer.stdout(),
+            out,
             "{}",
             status_line(
                 &hook.name,
@@ -216,31 +1067,18 @@ async fn run_hook(
                 "",
             )
         )?;
-        return Ok((true, diff));
+        return Ok((report, diff));
     }
 
-    let filter = FilenameFilter::from_hook(hook)?;
-    let filenames = filenames
-        .into_par_iter()
-        .filter(|filename| filter.filter(filename));
-
-    let filter = FileTagFilter::from_hook(hook);
-    let mut filenames: Vec<_> = filenames
-        .filter(|filename| {
-            let path = Path::new(filename);
-            match tags_from_path(path) {
-                Ok(tags) => filter.filter(&tags),
-                Err(err) => {
-                    error!(filename, error = %err, "Failed to get tags");
-                    false
-                }
-            }
-        })
-        .collect();
+    let mut filenames = hook_filenames(hook, filenames, file_tags)?;
 
     if filenames.is_empty() && !hook.always_run {
+        let report = HookReport::skipped(hook, stage, vec![]);
+        if hide_skipped {
+            return Ok((report, diff));
+        }
         writeln!(
-            printer.stdout(),
+            out,
             "{}",
             status_line(
                 &hook.name,
@@ -250,64 +1088,132 @@ async fn run_hook(
                 NO_FILES,
             )
         )?;
-        return Ok((true, diff));
+        return Ok((report, diff));
     }
 
     write!(
-        printer.stdout(),
+        out,
         "{}{}",
         &hook.name,
         ".".repeat(columns - hook.name.width_cjk() - 6 - 1)
     )?;
-    std::io::stdout().flush()?;
-
-    let start = std::time::Instant::now();
+    out.flush()?;
 
-    let (status, output) = if hook.pass_filenames {
+    if hook.pass_filenames {
         shuffle(&mut filenames);
-        hook.language.run(hook, &filenames, env_vars).await?
+    }
+    // Read-only hooks don't modify files, and `git diff` sees the modifications of tracked files.
+    let hashed = if hook.is_read_only() {
+        vec![]
     } else {
-        hook.language.run(hook, &[], env_vars).await?
+        filenames
+            .iter()
+            .copied()
+            .filter(|filename| no_git || untracked_files.contains(*filename))
+            .collect::<Vec<_>>()
+    };
+    let hashes = hash_files(&hashed);
+
+    let start = std::time::Instant::now();
+
+    // The `env` of the hook is set on top of the environment variables of the run.
+    let result = match hook.expanded_env() {
+        Ok(hook_env) => {
+            let env_vars = Arc::new(
+                env_vars
+                    .iter()
+                    .map(|(key, value)| ((*key).to_string(), value.clone()))
+                    .chain(hook_env)
+                    .collect(),
+            );
+            let options = BatchOptions {
+                concurrency: target_concurrency(hook, docker_jobs),
+                profile,
+                color,
+            };
+            if matches!(hook.repo(), Repo::Meta { .. }) {
+                meta_hooks::run(hook, project_hooks, &filenames).await
+            } else if hook.pass_filenames {
+                hook.language.run(hook, &filenames, env_vars, options).await
+            } else {
+                hook.language.run(hook, &[], env_vars, options).await
+            }
+        }
+        Err(err) => Err(err.into()),
     };
 
     let duration = start.elapsed();
+    profile::record(profile, |profile| {
+        let batches = profile
+            .pending_batches
+            .remove(&hook.idx())
+            .unwrap_or_default();
+        profile.hooks.push(HookTiming {
+            id: hook.id.clone(),
+            duration: duration.as_secs_f64(),
+            batches,
+        });
+    });
+
+    // A hook that couldn't run errored, which is reported apart from a failure.
+    let (status, output) = match result {
+        Ok(result) => result,
+        Err(err) => {
+            let error = format!("{err:#}");
+            writeln!(out, "{}", "Failed".on_red())?;
+            writeln!(out, "{}", format!("- hook id: {}", hook.id).dimmed())?;
+            writeln!(out, "{}", format!("- error: {error}").dimmed())?;
+            if output_format == OutputFormat::Github {
+                write!(
+                    out,
+                    "{}",
+                    github_annotations(hook, &filenames, &[], &format!("error: {error}"))
+                )?;
+            }
+
+            let report = HookReport {
+                id: hook.id.clone(),
+                name: hook.name.clone(),
+                stage: stage.to_string(),
+                status: HookStatus::Errored,
+                files: filenames
+                    .iter()
+                    .map(|filename| (*filename).clone())
+                    .collect(),
+                exit_code: None,
+                duration_ms: duration.as_millis(),
+                files_modified: false,
+                output: error,
+            };
+            return Ok((report, work_tree_diff(no_git).await?));
+        }
+    };
 
-    let new_diff = get_diff().await?;
-    let file_modified = diff != new_diff;
-    let success = status == 0 && !file_modified;
+    // A hook may modify files and still exit 0, so check the files regardless of the exit code.
+    let new_diff = work_tree_diff(no_git).await?;
+    let file_modified = diff != new_diff || hash_files(&hashed) != hashes;
+    let success = status == 0 && !(file_modified && fail_on_modified);
 
     if success {
-        writeln!(printer.stdout(), "{}", "Passed".on_green())?;
+        writeln!(out, "{}", "Passed".on_green())?;
     } else {
-        writeln!(printer.stdout(), "{}", "Failed".on_red())?;
+        writeln!(out, "{}", "Failed".on_red())?;
     }
 
     if verbose || hook.verbose || !success {
-        writeln!(
-            printer.stdout(),
-            "{}",
-            format!("- hook id: {}", hook.id).dimmed()
-        )?;
+        writeln!(out, "{}", format!("- hook id: {}", hook.id).dimmed())?;
         if verbose || hook.verbose {
             writeln!(
-                printer.stdout(),
+                out,
                 "{}",
                 format!("- duration: {:.2?}s", duration.as_secs_f64()).dimmed()
             )?;
         }
         if status != 0 {
-            writeln!(
-                printer.stdout(),
-                "{}",
-                format!("- exit code: {status}").dimmed()
-            )?;
+            writeln!(out, "{}", format!("- exit code: {status}").dimmed())?;
         }
         if file_modified {
-            writeln!(
-                printer.stdout(),
-                "{}",
-                "- files were modified by this hook".dimmed()
-            )?;
+            writeln!(out, "{}", "- files were modified by this hook".dimmed())?;
         }
 
         // To be consistent with pre-commit, merge stderr into stdout.
@@ -324,7 +1230,7 @@ async fn run_hook(
                     })?;
             } else {
                 writeln!(
-                    printer.stdout(),
+                    out,
                     "{}",
                     textwrap::indent(&String::from_utf8_lossy(stdout), "  ").dimmed()
                 )?;
@@ -332,37 +1238,176 @@ async fn run_hook(
         }
     }
 
-    Ok((success, new_diff))
+    if !success && output_format == OutputFormat::Github {
+        let reason = if status != 0 {
+            format!("exit code {status}")
+        } else {
+            "files were modified by this hook".to_string()
+        };
+        write!(
+            out,
+            "{}",
+            github_annotations(hook, &filenames, &output, &reason)
+        )?;
+    }
+
+    let report = HookReport {
+        id: hook.id.clone(),
+        name: hook.name.clone(),
+        stage: stage.to_string(),
+        status: if success {
+            HookStatus::Passed
+        } else {
+            HookStatus::Failed
+        },
+        files: filenames
+            .iter()
+            .map(|filename| (*filename).clone())
+            .collect(),
+        exit_code: Some(status),
+        duration_ms: duration.as_millis(),
+        files_modified: file_modified,
+        output: String::from_utf8_lossy(&output).into_owned(),
+    };
+
+    Ok((report, new_diff))
 }
 
-fn target_concurrency(serial: bool) -> usize {
-    if serial || std::env::var_os("PRE_COMMIT_NO_CONCURRENCY").is_some() {
-        1
-    } else {
-        std::thread::available_parallelism()
-            .map(std::num::NonZero::get)
-            .unwrap_or(1)
+/// Matches `file:line: message` and `file:line:col: message` diagnostics.
+static DIAGNOSTIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:\./)?(?P<file>[^:\s][^:]*):(?P<line>\d+)(?::(?P<col>\d+))?:\s*(?P<message>.+)$",
+    )
+    .expect("Invalid diagnostic regex")
+});
+
+/// Render the GitHub Actions annotations of a failed hook: one for the hook itself, and one for
+/// each diagnostic in the output about a file passed to the hook.
+fn github_annotations(hook: &Hook, filenames: &[&String], output: &[u8], reason: &str) -> String {
+    let mut annotations = format!(
+        "::error title={}::{}\n",
+        escape_property(&hook.id),
+        escape_data(&format!("Hook `{}` failed: {reason}", hook.id))
+    );
+
+    for line in String::from_utf8_lossy(output).lines() {
+        let Ok(Some(captures)) = DIAGNOSTIC.captures(line.trim_end()) else {
+            continue;
+        };
+        let file = &captures["file"];
+        if !filenames.iter().any(|filename| filename.as_str() == file) {
+            continue;
+        }
+
+        let mut properties = format!("file={},line={}", escape_property(file), &captures["line"]);
+        if let Some(col) = captures.name("col") {
+            let _ = write!(properties, ",col={}", col.as_str());
+        }
+        let _ = writeln!(
+            annotations,
+            "::error {properties},title={}::{}",
+            escape_property(&hook.id),
+            escape_data(&captures["message"])
+        );
+    }
+
+    annotations
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// The diff of the working tree, or nothing when running without git,
+/// where modified files are detected by their content only.
+async fn work_tree_diff(no_git: bool) -> Result<Vec<u8>> {
+    if no_git {
+        return Ok(Vec::new());
     }
+    Ok(get_diff().await?)
+}
+
+/// How the batches of files of a hook are run.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions<'a> {
+    /// The maximum number of batches run at once.
+    pub concurrency: usize,
+    /// The profile to record the timing of each batch into, if enabled.
+    pub profile: Option<&'a RefCell<Profile>>,
+    /// Whether the output of the batches is colored.
+    pub color: ColorChoice,
+}
+
+/// The number of batches of `hook` to run at once, with `docker_jobs` limiting the containers
+/// run at once by `docker` and `docker_image` hooks.
+fn target_concurrency(hook: &Hook, docker_jobs: Option<NonZeroUsize>) -> usize {
+    if hook.require_serial || std::env::var_os("PRE_COMMIT_NO_CONCURRENCY").is_some() {
+        return 1;
+    }
+
+    let concurrency = std::thread::available_parallelism()
+        .map(std::num::NonZero::get)
+        .unwrap_or(1);
+    match docker_jobs {
+        Some(jobs) if matches!(hook.language, Language::Docker | Language::DockerImage) => {
+            concurrency.min(jobs.get())
+        }
+        _ => concurrency,
+    }
+}
+
+/// The maximum length of the command line of a batch, like xargs.
+///
+/// It can be lowered with `PREFLIGIT_MAX_CLI_LENGTH`, mostly for testing.
+fn max_cli_length() -> usize {
+    if let Some(length) = std::env::var("PREFLIGIT_MAX_CLI_LENGTH")
+        .ok()
+        .and_then(|length| length.parse().ok())
+    {
+        return length;
+    }
+    platform_max_cli_length()
+}
+
+#[cfg(unix)]
+fn platform_max_cli_length() -> usize {
+    // The environment takes from the same space as the arguments.
+    let env_size: usize = std::env::vars_os()
+        .map(|(key, value)| key.len() + value.len() + 2)
+        .sum();
+    let arg_max = usize::try_from(unsafe { libc::sysconf(libc::_SC_ARG_MAX) }).unwrap_or(1 << 12);
+    arg_max
+        .saturating_sub(2048 + env_size)
+        .clamp(1 << 12, 1 << 17)
+}
+
+#[cfg(not(unix))]
+fn platform_max_cli_length() -> usize {
+    (1 << 15) - 2048 // UNICODE_STRING max - headroom
 }
 
-// TODO: do a more accurate calculation
 fn partitions<'a>(
     hook: &'a Hook,
     filenames: &'a [&String],
     concurrency: usize,
 ) -> Vec<Vec<&'a String>> {
     // If there are no filenames, we still want to run the hook once.
-    if filenames.is_empty() {
-        return vec![vec![]];
+    // A `require_serial` hook is also run once, with all the files.
+    if filenames.is_empty() || hook.require_serial {
+        return vec![filenames.to_vec()];
     }
 
     let max_per_batch = max(4, filenames.len().div_ceil(concurrency));
-    // TODO: subtract the env size
-    let max_cli_length = if cfg!(unix) {
-        1 << 12
-    } else {
-        (1 << 15) - 2048 // UNICODE_STRING max - headroom
-    };
+    let max_cli_length = max_cli_length();
 
     let command_length =
         hook.entry.len() + hook.args.iter().map(String::len).sum::<usize>() + hook.args.len();
@@ -373,7 +1418,9 @@ fn partitions<'a>(
 
     for &filename in filenames {
         let length = filename.len() + 1;
-        if current_length + length > max_cli_length || current.len() >= max_per_batch {
+        if !current.is_empty()
+            && (current_length + length > max_cli_length || current.len() >= max_per_batch)
+        {
             partitions.push(current);
             current = Vec::new();
             current_length = command_length + 1;
@@ -389,14 +1436,19 @@ fn partitions<'a>(
     partitions
 }
 
-pub async fn run_by_batch<T, F, Fut>(hook: &Hook, filenames: &[&String], run: F) -> Result<Vec<T>>
+pub async fn run_by_batch<T, F, Fut>(
+    hook: &Hook,
+    filenames: &[&String],
+    options: BatchOptions<'_>,
+    run: F,
+) -> Result<Vec<T>>
 where
     F: Fn(Vec<String>) -> Fut,
     F: Clone + Send + Sync + 'static,
     Fut: Future<Output = Result<T>> + Send + 'static,
     T: Send + 'static,
 {
-    let mut concurrency = target_concurrency(hook.require_serial);
+    let mut concurrency = options.concurrency;
 
     // Split files into batches
     let partitions = partitions(hook, filenames, concurrency);
@@ -415,11 +1467,13 @@ where
     // Spawn tasks for each batch
     let mut tasks = JoinSet::new();
 
-    for batch in partitions {
+    for (idx, batch) in partitions.into_iter().enumerate() {
         let semaphore = semaphore.clone();
         let run = run.clone();
 
-        let batch: Vec<_> = batch.into_iter().map(ToString::to_string).collect();
+        // The files are shuffled to balance the batches, but each batch is passed sorted.
+        let mut batch: Vec<_> = batch.into_iter().map(ToString::to_string).collect();
+        batch.sort_unstable();
 
         tasks.spawn(async move {
             let _permit = semaphore
@@ -427,20 +1481,56 @@ where
                 .await
                 .map_err(|_| anyhow::anyhow!("Failed to acquire semaphore"))?;
 
-            run(batch).await
+            let files = batch.len();
+            let start = std::time::Instant::now();
+            let result = run(batch).await?;
+            let timing = BatchTiming {
+                files,
+                duration: start.elapsed().as_secs_f64(),
+            };
+            anyhow::Ok((idx, result, timing))
         });
     }
 
     let mut results = Vec::new();
     while let Some(result) = tasks.join_next().await {
-        results.push(result??);
+        let (idx, result, timing) = result??;
+        // The batches run on other tasks, so their timings are recorded here.
+        profile::record(options.profile, |profile| {
+            profile
+                .pending_batches
+                .entry(hook.idx())
+                .or_default()
+                .push(timing);
+        });
+        results.push((idx, result));
     }
+    // Return the results in batch order, regardless of which batch finished first.
+    results.sort_unstable_by_key(|(idx, _)| *idx);
 
-    Ok(results)
+    Ok(results.into_iter().map(|(_, result)| result).collect())
 }
 
 static RESTORE_WORKTREE: Mutex<Option<WorkTreeKeeper>> = Mutex::new(None);
 
+/// Run a git command synchronously, so it can be used while dropping the keepers.
+///
+/// On failure, the error includes what was being done and the git stderr.
+fn run_git_sync(summary: &str, cmd: &mut Command) -> Result<()> {
+    let output = cmd.stdout(std::process::Stdio::null()).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let err = anyhow::anyhow!("Failed to {summary} (status: {})", output.status);
+    if stderr.trim().is_empty() {
+        Err(err)
+    } else {
+        Err(anyhow::anyhow!(stderr.trim().to_string()).context(err))
+    }
+}
+
 struct IntentToAddKeeper(Vec<PathBuf>);
 struct WorkingTreeKeeper(Option<PathBuf>);
 
@@ -452,15 +1542,13 @@ impl IntentToAddKeeper {
         }
 
         // TODO: xargs
-        git_cmd("git rm")?
+        git_cmd("remove intent-to-add files from the index")?
             .arg("rm")
             .arg("--cached")
             .arg("--")
             .args(&files)
             .check(true)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()
+            .output()
             .await?;
 
         Ok(Self(files.into_iter().map(PathBuf::from).collect()))
@@ -469,15 +1557,15 @@ impl IntentToAddKeeper {
     fn restore(&self) -> Result<()> {
         // Restore the intent-to-add changes.
         if !self.0.is_empty() {
-            Command::new(GIT.as_ref()?)
-                .arg("add")
-                .arg("--intent-to-add")
-                .arg("--")
-                // TODO: xargs
-                .args(&self.0)
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .status()?;
+            run_git_sync(
+                "restore intent-to-add files",
+                Command::new(GIT.as_ref()?)
+                    .arg("add")
+                    .arg("--intent-to-add")
+                    .arg("--")
+                    // TODO: xargs
+                    .args(&self.0),
+            )?;
         }
         Ok(())
     }
@@ -553,37 +1641,27 @@ impl WorkingTreeKeeper {
     }
 
     fn checkout_working_tree() -> Result<()> {
-        let status = Command::new(GIT.as_ref()?)
-            .arg("-c")
-            .arg("submodule.recurse=0")
-            .arg("checkout")
-            .arg("--")
-            .arg(".")
-            // prevent recursive post-checkout hooks
-            .env("_PRE_COMMIT_SKIP_POST_CHECKOUT", "1")
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()?;
-        if status.success() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Failed to checkout working tree"))
-        }
+        run_git_sync(
+            "checkout working tree",
+            Command::new(GIT.as_ref()?)
+                .arg("-c")
+                .arg("submodule.recurse=0")
+                .arg("checkout")
+                .arg("--")
+                .arg(".")
+                // prevent recursive post-checkout hooks
+                .env("_PRE_COMMIT_SKIP_POST_CHECKOUT", "1"),
+        )
     }
 
     fn git_apply(patch: &Path) -> Result<()> {
-        let status = Command::new(GIT.as_ref()?)
-            .arg("apply")
-            .arg("--whitespace=nowarn")
-            .arg(patch)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()?;
-        if status.success() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Failed to apply the patch"))
-        }
+        run_git_sync(
+            &format!("apply the patch `{}`", patch.user_display()),
+            Command::new(GIT.as_ref()?)
+                .arg("apply")
+                .arg("--whitespace=nowarn")
+                .arg(patch),
+        )
     }
 
     fn restore(&self) -> Result<()> {
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fmt::Write;
 use std::path::{Path, PathBuf};
 
@@ -8,10 +9,14 @@ use tracing::debug;
 
 use crate::config::ConfigRemoteRepo;
 use crate::fs::{copy_dir_all, LockedFile};
-use crate::git::clone_repo;
+use crate::git::{self, clone_repo};
 use crate::hook::{Hook, Repo};
 use crate::printer::Printer;
 
+/// The key of the local repos in the store, one per set of dependencies.
+const LOCAL_NAME: &str = "local";
+const LOCAL_REV: &str = "1";
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Home directory not found")]
@@ -110,6 +115,22 @@ impl Store {
             )?;
             conn
         };
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS run_results (
+                repo_path TEXT NOT NULL,
+                hook_id TEXT NOT NULL,
+                passed INTEGER NOT NULL,
+                PRIMARY KEY (repo_path, hook_id)
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS configs (
+                path TEXT NOT NULL PRIMARY KEY
+            );",
+            [],
+        )?;
 
         Ok(Self {
             conn: Some(conn),
@@ -117,6 +138,35 @@ impl Store {
         })
     }
 
+    /// Record a config file as used, so `gc` keeps the repos it references.
+    pub fn mark_config_used(&self, path: &Path) -> Result<(), Error> {
+        let path = std::path::absolute(path)?;
+        self.conn().execute(
+            "INSERT OR IGNORE INTO configs (path) VALUES (?)",
+            [path.to_string_lossy()],
+        )?;
+        Ok(())
+    }
+
+    /// List the config files recorded as used.
+    pub fn configs(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut stmt = self.conn().prepare("SELECT path FROM configs")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|path| path.map(PathBuf::from))
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+
+    /// Forget a config file that no longer exists.
+    pub fn remove_config(&self, path: &Path) -> Result<(), Error> {
+        self.conn().execute(
+            "DELETE FROM configs WHERE path = ?",
+            [path.to_string_lossy()],
+        )?;
+        Ok(())
+    }
+
     /// List all repos.
     pub fn repos(&self) -> Result<Vec<Repo>, Error> {
         let mut stmt = self.conn().prepare("SELECT repo, ref, path FROM repos")?;
@@ -136,6 +186,28 @@ impl Store {
             .collect::<Result<Vec<_>, Error>>()
     }
 
+    /// List all entries of the repos table, as `(repo, rev, path)`.
+    ///
+    /// Unlike [`Store::repos`], this includes local repos, and the repo name includes the
+    /// additional dependencies.
+    pub fn entries(&self) -> Result<Vec<(String, String, String)>, Error> {
+        let mut stmt = self.conn().prepare("SELECT repo, ref, path FROM repos")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+
+    /// Remove an entry of the repos table and its directory, it will be prepared again when needed.
+    pub fn remove_entry(&self, repo: &str, rev: &str, path: &Path) -> Result<(), Error> {
+        self.conn()
+            .execute("DELETE FROM repos WHERE repo = ? AND ref = ?", [repo, rev])?;
+        if path.try_exists()? {
+            fs_err::remove_dir_all(path)?;
+        }
+        Ok(())
+    }
+
     // Append dependencies to the repo name as the key.
     fn repo_name(repo: &str, deps: &[String]) -> String {
         let mut name = repo.to_string();
@@ -183,9 +255,6 @@ impl Store {
         deps: &[String],
         printer: Printer,
     ) -> Result<PathBuf, Error> {
-        const LOCAL_NAME: &str = "local";
-        const LOCAL_REV: &str = "1";
-
         if hook.language.environment_dir().is_none() {
             return Err(Error::LocalHookNoNeedEnv(hook.id.clone()));
         }
@@ -209,6 +278,33 @@ impl Store {
         Ok(PathBuf::from(path))
     }
 
+    /// The path of the local repo for `deps` if it's already prepared, without preparing it.
+    pub fn cached_local_repo(&self, deps: &[String]) -> Result<Option<PathBuf>, Error> {
+        self.cached_repo(LOCAL_NAME, LOCAL_REV, deps)
+    }
+
+    /// The path of a remote repo if it's already cloned into the store, without cloning it.
+    pub fn cached_remote_repo(
+        &self,
+        repo_config: &ConfigRemoteRepo,
+        deps: &[String],
+    ) -> Result<Option<PathBuf>, Error> {
+        self.cached_repo(repo_config.repo.as_str(), repo_config.rev.as_str(), deps)
+    }
+
+    fn cached_repo(
+        &self,
+        repo: &str,
+        rev: &str,
+        deps: &[String],
+    ) -> Result<Option<PathBuf>, Error> {
+        let Some((_, _, path)) = self.get_repo(repo, rev, deps)? else {
+            return Ok(None);
+        };
+        let path = PathBuf::from(path);
+        Ok(path.try_exists()?.then_some(path))
+    }
+
     /// Clone a remote repo into the store.
     pub async fn prepare_remote_repo(
         &self,
@@ -232,15 +328,11 @@ impl Store {
         let path = temp.path().to_string_lossy().to_string();
 
         if deps.is_empty() {
-            writeln!(
-                printer.stdout(),
-                "Cloning {}@{}",
-                repo_config.repo,
-                repo_config.rev
-            )?;
+            let url = git::mask_url(repo_config.repo.as_str());
+            writeln!(printer.stdout(), "Cloning {url}@{}", repo_config.rev)?;
             debug!(
                 target = path,
-                repo = format!("{}@{}", repo_config.repo, repo_config.rev),
+                repo = format!("{url}@{}", repo_config.rev),
                 "Cloning repo",
             );
             clone_repo(repo_config.repo.as_str(), &repo_config.rev, temp.path()).await?;
@@ -251,10 +343,10 @@ impl Store {
             let (_, _, base_repo_path) = self
                 .get_repo(repo_config.repo.as_str(), repo_config.rev.as_str(), &[])?
                 .expect("base repo should be cloned before");
+            let url = git::mask_url(repo_config.repo.as_str());
             writeln!(
                 printer.stdout(),
-                "Preparing {}@{} with dependencies {}",
-                repo_config.repo,
+                "Preparing {url}@{} with dependencies {}",
                 repo_config.rev,
                 deps.join(","),
             )?;
@@ -262,8 +354,7 @@ impl Store {
                 source = base_repo_path,
                 target = path,
                 deps = deps.join(","),
-                "Preparing {}@{} by copying",
-                repo_config.repo,
+                "Preparing {url}@{} by copying",
                 repo_config.rev,
             );
             copy_dir_all(base_repo_path, &path)?;
@@ -279,6 +370,37 @@ impl Store {
         Ok(PathBuf::from(path))
     }
 
+    /// Get the hooks that passed in the previous runs in the repo at `repo_path`.
+    pub fn passed_hooks(&self, repo_path: &Path) -> Result<HashSet<String>, Error> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT hook_id FROM run_results WHERE repo_path = ? AND passed = 1")?;
+        let rows = stmt
+            .query_map([repo_path.to_string_lossy()], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+
+    /// Record the outcomes of a run in the repo at `repo_path`, replacing the previous
+    /// outcomes of the same hooks.
+    pub fn save_run_results(
+        &self,
+        repo_path: &Path,
+        results: &[(String, bool)],
+    ) -> Result<(), Error> {
+        let mut stmt = self.conn().prepare(
+            "INSERT OR REPLACE INTO run_results (repo_path, hook_id, passed) VALUES (?, ?, ?)",
+        )?;
+        for (hook_id, passed) in results {
+            stmt.execute(rusqlite::params![
+                repo_path.to_string_lossy(),
+                hook_id,
+                passed
+            ])?;
+        }
+        Ok(())
+    }
+
     /// Lock the store.
     pub fn lock(&self) -> Result<LockedFile, std::io::Error> {
         LockedFile::acquire_blocking(self.path.join(".lock"), "store")
@@ -291,6 +413,26 @@ impl Store {
     pub fn uv_path(&self) -> PathBuf {
         self.path.join("tools").join("uv")
     }
+
+    /// The Node.js toolchains installed by prefligit, one directory per version.
+    pub fn node_path(&self) -> PathBuf {
+        self.path.join("tools").join("node")
+    }
+
+    /// The Python builds installed by prefligit, one directory per version.
+    pub fn python_path(&self) -> PathBuf {
+        self.path.join("tools").join("python")
+    }
+
+    /// The Ruby toolchains installed by prefligit, one directory per version.
+    pub fn ruby_path(&self) -> PathBuf {
+        self.path.join("tools").join("ruby")
+    }
+
+    /// The log of git hook runs skipped by `PREFLIGIT_ALLOW_SKIP`.
+    pub fn bypass_log_path(&self) -> PathBuf {
+        self.path.join("bypass.log")
+    }
 }
 
 // TODO
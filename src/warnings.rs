@@ -53,7 +53,9 @@ macro_rules! warn_user {
         if $crate::warnings::ENABLED.load(std::sync::atomic::Ordering::SeqCst) {
             let message = format!("{}", format_args!($($arg)*));
             let formatted = message.bold();
-            eprintln!("{}{} {formatted}", "warning".yellow().bold(), ":".bold());
+            $crate::printer::render(|| {
+                eprintln!("{}{} {formatted}", "warning".yellow().bold(), ":".bold());
+            });
         }
     };
 }
@@ -66,13 +68,15 @@ pub static WARNINGS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Mutex::def
 macro_rules! warn_user_once {
     ($($arg:tt)*) => {
         use $crate::warnings::anstream::eprintln;
-        use $crate::warnings::::owo_colors::OwoColorize;
+        use $crate::warnings::owo_colors::OwoColorize;
 
         if $crate::warnings::ENABLED.load(std::sync::atomic::Ordering::SeqCst) {
             if let Ok(mut states) = $crate::warnings::WARNINGS.lock() {
                 let message = format!("{}", format_args!($($arg)*));
                 if states.insert(message.clone()) {
-                    eprintln!("{}{} {}", "warning".yellow().bold(), ":".bold(), message.bold());
+                    $crate::printer::render(|| {
+                        eprintln!("{}{} {}", "warning".yellow().bold(), ":".bold(), message.bold());
+                    });
                 }
             }
         }
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use assert_fs::fixture::ChildPath;
+use assert_fs::prelude::*;
+use itertools::Itertools;
+
+use crate::common::{cmd_snapshot, git, TestContext};
+
+mod common;
+
+/// Create a hook repo with a commit for each of `versions`, tagged with the version.
+fn create_hook_repo(context: &TestContext, versions: &[(&str, &[&str])]) -> Result<ChildPath> {
+    let repo = context.workdir().child("hook-repo");
+    repo.create_dir_all()?;
+    git(&repo, &["init", "--initial-branch=master"]);
+
+    for (version, hooks) in versions {
+        let manifest = hooks
+            .iter()
+            .map(|id| format!("- id: {id}\n  name: {id}\n  language: system\n  entry: echo\n"))
+            .join("");
+        repo.child(".pre-commit-hooks.yaml").write_str(&manifest)?;
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "--allow-empty", "-m", version]);
+        git(&repo, &["tag", version]);
+    }
+
+    Ok(repo)
+}
+
+/// Only the `rev` scalar is rewritten, everything else is left untouched.
+#[test]
+fn preserve_config() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = create_hook_repo(
+        &context,
+        &[
+            ("v1.0.0", &["hook-a", "hook-b"]),
+            ("v1.1.0", &["hook-a", "hook-b"]),
+        ],
+    )?;
+
+    let config = indoc::formatdoc! {r#"
+        # Top comment.
+        default_stages: [pre-commit]  # trailing comment
+
+        repos:
+          # The hook repo.
+          - repo: file://{}
+            rev:   v1.0.0    # pinned, keep me
+            hooks:
+              - id: hook-a
+                args: [--foo, "--bar=baz"]   # keep args
+                additional_dependencies: ['dep==1.0']
+                # A comment between hooks.
+              - id: hook-b
+                exclude: ^docs/
+
+          - repo: local
+            hooks:
+              - id: local-hook
+                name: local
+                language: system
+                entry: echo
+    "#, repo.display()};
+    context.write_pre_commit_config(&config);
+
+    cmd_snapshot!(context.filters(), context.autoupdate(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [file://[TEMP_DIR]/hook-repo] updating v1.0.0 -> v1.1.0
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(
+        context.read(".pre-commit-config.yaml"),
+        config.replace(
+            "rev:   v1.0.0    # pinned, keep me",
+            "rev:   v1.1.0    # pinned, keep me"
+        )
+    );
+
+    cmd_snapshot!(context.filters(), context.autoupdate(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [file://[TEMP_DIR]/hook-repo] already up to date!
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+#[test]
+fn freeze_and_missing_hooks() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = create_hook_repo(
+        &context,
+        &[("v1.0.0", &["hook-a", "hook-b"]), ("v2.0.0", &["hook-a"])],
+    )?;
+
+    // The update target no longer has `hook-b`.
+    let config = indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: 'v1.0.0'
+            hooks:
+              - id: hook-b
+    ", repo.display()};
+    context.write_pre_commit_config(&config);
+
+    cmd_snapshot!(context.filters(), context.autoupdate(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    [file://[TEMP_DIR]/hook-repo] -> Cannot update because the update target is missing these hooks: hook-b
+
+    ----- stderr -----
+    ");
+    assert_eq!(context.read(".pre-commit-config.yaml"), config);
+
+    // Freeze the sha of the latest tag.
+    let config = indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: 'v1.0.0'
+            hooks:
+              - id: hook-a
+    ", repo.display()};
+    context.write_pre_commit_config(&config);
+
+    let mut filters = context.filters();
+    filters.push((r"[0-9a-f]{40}", "[SHA]"));
+
+    cmd_snapshot!(filters.clone(), context.autoupdate().arg("--freeze"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [file://[TEMP_DIR]/hook-repo] updating v1.0.0 -> v2.0.0 (frozen)
+
+    ----- stderr -----
+    ");
+
+    let updated = context.read(".pre-commit-config.yaml");
+    insta::with_settings!({ filters => filters }, {
+        insta::assert_snapshot!(updated, @r"
+        repos:
+          - repo: file://[TEMP_DIR]/hook-repo
+            rev: '[SHA]'  # frozen: v2.0.0
+            hooks:
+              - id: hook-a
+        ");
+    });
+
+    Ok(())
+}
+
+/// Prerelease tags are skipped, unless updating to the bleeding edge.
+#[test]
+fn skip_prerelease() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = create_hook_repo(
+        &context,
+        &[
+            ("v1.0.0", &["hook-a"]),
+            ("v1.1.0", &["hook-a"]),
+            ("v2.0.0-rc.1", &["hook-a"]),
+        ],
+    )?;
+
+    let config = indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: v1.0.0  # keep me
+            hooks:
+              - id: hook-a
+    ", repo.display()};
+    context.write_pre_commit_config(&config);
+
+    cmd_snapshot!(context.filters(), context.autoupdate(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [file://[TEMP_DIR]/hook-repo] updating v1.0.0 -> v1.1.0
+
+    ----- stderr -----
+    ");
+
+    let updated = context.read(".pre-commit-config.yaml");
+    insta::with_settings!({ filters => context.filters() }, {
+        insta::assert_snapshot!(updated, @r"
+        repos:
+          - repo: file://[TEMP_DIR]/hook-repo
+            rev: v1.1.0  # keep me
+            hooks:
+              - id: hook-a
+        ");
+    });
+
+    cmd_snapshot!(context.filters(), context.autoupdate().arg("--bleeding-edge"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [file://[TEMP_DIR]/hook-repo] updating v1.1.0 -> v2.0.0-rc.1
+
+    ----- stderr -----
+    ");
+
+    let updated = context.read(".pre-commit-config.yaml");
+    insta::with_settings!({ filters => context.filters() }, {
+        insta::assert_snapshot!(updated, @r"
+        repos:
+          - repo: file://[TEMP_DIR]/hook-repo
+            rev: v2.0.0-rc.1  # keep me
+            hooks:
+              - id: hook-a
+        ");
+    });
+
+    Ok(())
+}
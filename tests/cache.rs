@@ -0,0 +1,91 @@
+use anyhow::Result;
+use assert_cmd::assert::OutputAssertExt;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+/// `cache verify` reports a cached clone that is no longer a git repo, and `--repair` clones it
+/// again.
+#[test]
+fn verify_corrupt_repo() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.create_hook_repo(
+        "hook-repo",
+        indoc::indoc! {r"
+            - id: echo
+              name: echo
+              language: system
+              entry: echo
+        "},
+    );
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+    ", repo.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r"\[HOME\]/repo\w+", "[HOME]/[REPO]")])
+        .collect::<Vec<_>>();
+
+    // Clone the repo into the store.
+    context.run().assert().success();
+
+    cmd_snapshot!(filters.clone(), context.command().arg("cache").arg("verify"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Verified 1 repos and 0 environments, no corrupt entries found
+
+    ----- stderr -----
+    ");
+
+    // Corrupt the clone.
+    let clone = fs_err::read_dir(context.home_dir().path())?
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_name().to_string_lossy().starts_with("repo"))
+        .expect("the repo should be cloned")
+        .path();
+    fs_err::remove_dir_all(clone.join(".git"))?;
+
+    cmd_snapshot!(filters.clone(), context.command().arg("cache").arg("verify"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Corrupt repo `file://[TEMP_DIR]/hook-repo@master` at `[HOME]/[REPO]`: not a git repository
+    Found 1 corrupt entries, run with `--repair` to repair them
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(filters.clone(), context.command().arg("cache").arg("verify").arg("--repair"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Corrupt repo `file://[TEMP_DIR]/hook-repo@master` at `[HOME]/[REPO]`: not a git repository
+    Cloning file://[TEMP_DIR]/hook-repo@master
+    Repaired 1 corrupt entries
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(filters, context.command().arg("cache").arg("verify"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Verified 1 repos and 0 environments, no corrupt entries found
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
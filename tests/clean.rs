@@ -1,3 +1,4 @@
+use assert_cmd::assert::OutputAssertExt;
 use assert_fs::assert::PathAssert;
 use assert_fs::fixture::{PathChild, PathCreateDir};
 
@@ -25,3 +26,44 @@ fn clean() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// `clean` removes the store with the cloned repos and installed environments.
+#[test]
+fn clean_installed() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: node
+                name: node
+                language: node
+                entry: node -e ''
+                always_run: true
+    "});
+    context.git_add(".");
+    context.run().assert().success();
+    context.home_dir().assert(predicates::path::is_dir());
+
+    cmd_snapshot!(context.filters(), context.clean(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cleaned `[HOME]/`
+
+    ----- stderr -----
+    ");
+
+    context.home_dir().assert(predicates::path::missing());
+
+    cmd_snapshot!(context.filters(), context.clean(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Nothing to clean
+
+    ----- stderr -----
+    ");
+}
@@ -105,6 +105,11 @@ impl TestContext {
         patterns
     }
 
+    /// The `PRE_COMMIT_HOME` of the test, where the store is.
+    pub fn home_dir(&self) -> &ChildPath {
+        &self.home_dir
+    }
+
     /// Read a file in the temporary directory
     pub fn read(&self, file: impl AsRef<Path>) -> String {
         fs_err::read_to_string(self.temp_dir.join(&file))
@@ -131,6 +136,12 @@ impl TestContext {
         command
     }
 
+    pub fn gc(&self) -> Command {
+        let mut command = self.command();
+        command.arg("gc");
+        command
+    }
+
     pub fn validate_config(&self) -> Command {
         let mut command = self.command();
         command.arg("validate-config");
@@ -155,6 +166,18 @@ impl TestContext {
         command
     }
 
+    pub fn autoupdate(&self) -> Command {
+        let mut command = self.command();
+        command.arg("autoupdate");
+        command
+    }
+
+    pub fn env_info(&self) -> Command {
+        let mut command = self.command();
+        command.arg("env").arg("info");
+        command
+    }
+
     pub fn sample_config(&self) -> Command {
         let mut command = self.command();
         command.arg("sample-config");
@@ -187,6 +210,17 @@ impl TestContext {
             .success();
     }
 
+    /// Create a hook repo `name` with `manifest` as its `.pre-commit-hooks.yaml`, committed on
+    /// `master`.
+    pub fn create_hook_repo(&self, name: &str, manifest: &str) -> ChildPath {
+        let repo = self.temp_dir.child(name);
+        repo.child(".pre-commit-hooks.yaml")
+            .write_str(manifest)
+            .expect("Failed to write the hook manifest");
+        git_commit_all(&repo);
+        repo
+    }
+
     /// Configure git user and email.
     pub fn configure_git_author(&self) {
         Command::new("git")
@@ -235,6 +269,27 @@ impl TestContext {
     }
 }
 
+/// Run `git` in `dir`, as the test author.
+pub fn git(dir: impl AsRef<Path>, args: &[&str]) {
+    Command::new("git")
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "Prefligit Test")
+        .env("GIT_AUTHOR_EMAIL", "test@prefligit.dev")
+        .env("GIT_COMMITTER_NAME", "Prefligit Test")
+        .env("GIT_COMMITTER_EMAIL", "test@prefligit.dev")
+        .current_dir(dir)
+        .assert()
+        .success();
+}
+
+/// Initialize a git repo on `master` in `dir` and commit all its files.
+pub fn git_commit_all(dir: impl AsRef<Path>) {
+    let dir = dir.as_ref();
+    git(dir, &["init", "--initial-branch=master"]);
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-m", "Initial commit"]);
+}
+
 #[doc(hidden)] // Macro and test context only, don't use directly.
 pub const INSTA_FILTERS: &[(&str, &str)] = &[
     // File sizes
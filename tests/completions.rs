@@ -0,0 +1,39 @@
+use assert_cmd::assert::OutputAssertExt;
+
+use crate::common::TestContext;
+
+mod common;
+
+/// Each shell gets a completion script in its own syntax.
+#[test]
+fn completions() {
+    let context = TestContext::new();
+
+    for (shell, expected) in [
+        ("bash", "complete -F _prefligit"),
+        ("zsh", "#compdef prefligit"),
+        ("fish", "complete -c prefligit"),
+        ("powershell", "Register-ArgumentCompleter"),
+        ("elvish", "edit:completion:arg-completer[prefligit]"),
+    ] {
+        let output = context
+            .command()
+            .arg("completions")
+            .arg(shell)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let script = String::from_utf8(output).expect("completion script should be UTF-8");
+        assert!(
+            script.contains(expected),
+            "`{shell}` completion script should contain `{expected}`"
+        );
+        // Subcommands are completed.
+        assert!(
+            script.contains("autoupdate"),
+            "`{shell}` completion script should complete subcommands"
+        );
+    }
+}
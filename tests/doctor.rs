@@ -0,0 +1,138 @@
+use assert_fs::prelude::*;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+/// `doctor --fix` recreates a missing store.
+#[test]
+fn doctor_fix_missing_store() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+
+    fs_err::remove_dir_all(context.home_dir()).unwrap();
+
+    cmd_snapshot!(context.filters(), context.command().arg("doctor"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Problem the store `[HOME]/` does not exist
+    Ok config `.pre-commit-config.yaml`
+    Found 1 problem, run with `--fix` to fix the safe ones
+
+    ----- stderr -----
+    ");
+
+    // Declining the fix leaves the store missing.
+    cmd_snapshot!(context.filters(), context.command().arg("doctor").arg("--fix").pass_stdin("n\n"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Problem the store `[HOME]/` does not exist
+    Ok config `.pre-commit-config.yaml`
+    Fixed 0 of 1 problem, the others need to be fixed manually
+
+    ----- stderr -----
+    Create the store? [y/N]
+    ");
+    context.home_dir().assert(predicates::path::missing());
+
+    cmd_snapshot!(context.filters(), context.command().arg("doctor").arg("--fix").arg("--yes"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Problem the store `[HOME]/` does not exist
+    Fixed created the store `[HOME]/`
+    Ok config `.pre-commit-config.yaml`
+    Ok 0 installed environments
+    Fixed 1 problem
+
+    ----- stderr -----
+    ");
+    context.home_dir().assert(predicates::path::is_dir());
+
+    cmd_snapshot!(context.filters(), context.command().arg("doctor"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Ok store `[HOME]/`
+    Ok config `.pre-commit-config.yaml`
+    Ok 0 installed environments
+    No problems found
+
+    ----- stderr -----
+    ");
+}
+
+/// `doctor --fix` stages an unstaged config after a confirmation.
+#[test]
+fn doctor_fix_unstaged_config() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config("repos: []\n");
+    context.git_add(".");
+    context.write_pre_commit_config("repos: []\nfail_fast: true\n");
+
+    cmd_snapshot!(context.filters(), context.command().arg("doctor").arg("--fix").pass_stdin("y\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Ok store `[HOME]/`
+    Problem the config `.pre-commit-config.yaml` has unstaged changes
+    Fixed staged `.pre-commit-config.yaml`
+    Ok 0 installed environments
+    Fixed 1 problem
+
+    ----- stderr -----
+    Stage `.pre-commit-config.yaml`? [y/N]
+    ");
+
+    cmd_snapshot!(context.filters(), context.command().arg("doctor"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Ok store `[HOME]/`
+    Ok config `.pre-commit-config.yaml`
+    Ok 0 installed environments
+    No problems found
+
+    ----- stderr -----
+    ");
+}
+
+/// `doctor` checks the environments in the store without cloning the repos that aren't there.
+#[test]
+fn doctor_does_not_clone() {
+    let context = TestContext::new();
+    context.init_project();
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: file:///not/cloned/hook-repo
+            rev: v1.0.0
+            hooks:
+              - id: hook
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.command().arg("doctor"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Ok store `[HOME]/`
+    Ok config `.pre-commit-config.yaml`
+    Ok 0 installed environments
+    No problems found
+
+    ----- stderr -----
+    ");
+}
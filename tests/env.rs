@@ -0,0 +1,146 @@
+use assert_cmd::assert::OutputAssertExt;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+#[test]
+fn env_info_system() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.env_info().arg("echo"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    id: echo
+    repo: local
+    language: system
+    language_version: default
+    environment: none
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.env_info().arg("not-exist"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    No hook found for id `not-exist`
+    ");
+}
+
+/// Show the environment of an installed python hook.
+#[test]
+fn env_info_python() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: python
+                entry: pyecho Hello, world!
+                additional_dependencies: ["pyecho-cli"]
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    // Not installed yet.
+    let output = context.env_info().arg("local").assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).to_string();
+    assert!(stdout.contains("language: python"), "{stdout}");
+    assert!(stdout.contains("installed: no"), "{stdout}");
+
+    context.run().assert().success();
+
+    let output = context.env_info().arg("local").assert().success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).to_string();
+    assert!(stdout.contains("installed: yes"), "{stdout}");
+    assert!(stdout.contains("  - pyecho-cli\n"), "{stdout}");
+    assert!(stdout.contains("  - pyecho_cli=="), "{stdout}");
+    assert!(stdout.contains("health: ok"), "{stdout}");
+}
+
+/// `${VAR}` in `additional_dependencies` is expanded from the environment only when installing,
+/// it's never displayed or stored.
+#[test]
+fn additional_dependencies_env_var() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: python
+                entry: pyecho
+                additional_dependencies: ["pyecho-cli @ https://${INDEX_TOKEN}@example.com/pyecho-cli.tar.gz"]
+    "#});
+    context.git_add(".");
+
+    let mut filters = context.filters();
+    filters.push((r"environment: .*", "environment: [ENV_DIR]"));
+
+    cmd_snapshot!(filters.clone(), context.env_info().arg("local").env("INDEX_TOKEN", "secret"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo local
+    id: local
+    repo: local
+    language: python
+    language_version: python3
+    environment: [ENV_DIR]
+    additional_dependencies:
+      - pyecho-cli @ https://${INDEX_TOKEN}@example.com/pyecho-cli.tar.gz
+    installed: no
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(filters.clone(), context.env_info().arg("local").env_remove("INDEX_TOKEN"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    id: local
+    repo: local
+    language: python
+    language_version: python3
+    environment: [ENV_DIR]
+    additional_dependencies:
+      - pyecho-cli @ https://${INDEX_TOKEN}@example.com/pyecho-cli.tar.gz
+    installed: no
+
+    ----- stderr -----
+    ");
+
+    // The variable is only needed to install the environment.
+    cmd_snapshot!(filters, context.run().env_remove("INDEX_TOKEN"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Installing environment for local
+
+    ----- stderr -----
+    error: Hook `local` references undefined environment variable `INDEX_TOKEN` in additional dependency `pyecho-cli @ https://${INDEX_TOKEN}@example.com/pyecho-cli.tar.gz`
+    ");
+}
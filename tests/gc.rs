@@ -0,0 +1,109 @@
+use anyhow::Result;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::fixture::ChildPath;
+use assert_fs::prelude::*;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+/// Create a hook repo named `name` with an `echo` hook.
+fn create_hook_repo(context: &TestContext, name: &str) -> ChildPath {
+    context.create_hook_repo(
+        name,
+        indoc::indoc! {r"
+            - id: echo
+              name: echo
+              language: system
+              entry: echo
+        "},
+    )
+}
+
+/// The cached repos in the store.
+fn cached_repos(context: &TestContext) -> Result<Vec<String>> {
+    Ok(fs_err::read_dir(context.home_dir().path())?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("repo"))
+        .collect())
+}
+
+/// `gc` removes the repos no longer used by any config, and keeps the used ones.
+#[test]
+fn gc_unused_repos() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let used = create_hook_repo(&context, "used-repo");
+    let stale = create_hook_repo(&context, "stale-repo");
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+    ", used.display(), stale.display()});
+    context.git_add(".pre-commit-config.yaml");
+    context.run().assert().success();
+    assert_eq!(cached_repos(&context)?.len(), 2);
+
+    // The stale repo is no longer in the config.
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+    ", used.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    // A directory left behind by an interrupted clone.
+    context
+        .home_dir()
+        .child("repo-interrupted")
+        .child("file")
+        .write_str("stale")?;
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r"reclaimed \d+(\.\d)? (B|KiB|MiB)", "reclaimed [SIZE]")])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters.clone(), context.gc(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Removed 2 unused repos, reclaimed [SIZE]
+
+    ----- stderr -----
+    ");
+    assert_eq!(cached_repos(&context)?.len(), 1);
+
+    // The used repo is kept, so it's not cloned again.
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(filters, context.gc(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Removed 0 unused repos, reclaimed [SIZE]
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
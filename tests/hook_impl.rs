@@ -1,5 +1,8 @@
 use std::process::Command;
 
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+
 use common::TestContext;
 use indoc::indoc;
 
@@ -56,3 +59,370 @@ fn hook_impl() {
       .pre-commit-config.yaml
     "#);
 }
+
+/// `PREFLIGIT_ALLOW_SKIP` skips the hooks, and records the bypass in the store.
+#[test]
+fn allow_skip() {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: fail
+             name: fail
+             language: fail
+             entry: always fail
+             always_run: true
+    "});
+
+    context.git_add(".");
+    context.configure_git_author();
+    context.install().assert().success();
+
+    let mut commit = Command::new("git");
+    commit
+        .arg("commit")
+        .arg("--quiet")
+        .current_dir(context.workdir())
+        .arg("-m")
+        .arg("Initial commit")
+        .env("PRE_COMMIT_HOME", &**context.home_dir())
+        .env("PREFLIGIT_ALLOW_SKIP", "hotfix for incident 42");
+
+    cmd_snapshot!(context.filters(), commit, @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    Skipping pre-commit hooks: hotfix for incident 42
+    ");
+
+    let mut filters = context.filters();
+    filters.push((r"(?m)^\d+\t", "[TIMESTAMP]\t"));
+    let log = fs_err::read_to_string(context.home_dir().join("bypass.log")).unwrap();
+    insta::with_settings!({ filters => filters }, {
+        insta::assert_snapshot!(log, @"[TIMESTAMP]	Prefligit Test <test@prefligit.dev>	[TEMP_DIR]/	pre-commit	hotfix for incident 42");
+    });
+}
+
+/// `post-commit` hooks run on the files of the new commit, and cannot fail the commit.
+#[test]
+fn post_commit() {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    context.write_pre_commit_config(indoc! { r#"
+        repos:
+        - repo: local
+          hooks:
+           - id: committed-files
+             name: committed-files
+             language: system
+             entry: python3 -c "import sys; print(sys.argv[1:]); sys.exit(1)"
+             stages: [post-commit]
+           - id: notify
+             name: notify
+             language: system
+             entry: echo "notified"
+             pass_filenames: false
+             always_run: true
+             verbose: true
+             stages: [post-commit]
+           - id: python-only
+             name: python-only
+             language: system
+             entry: echo "never runs"
+             types: [python]
+             stages: [post-commit]
+    "#});
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("Hello\n")
+        .unwrap();
+
+    context.git_add(".");
+    context.configure_git_author();
+
+    cmd_snapshot!(context.filters(), context.install().arg("--hook-type").arg("post-commit"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at .git/hooks/post-commit
+
+    ----- stderr -----
+    ");
+
+    let mut commit = Command::new("git");
+    commit
+        .arg("commit")
+        .arg("--quiet")
+        .current_dir(context.workdir())
+        .arg("-m")
+        .arg("Initial commit")
+        .env("PRE_COMMIT_HOME", &**context.home_dir());
+
+    cmd_snapshot!(context.filters(), commit, @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    committed-files..........................................................Failed
+    - hook id: committed-files
+    - exit code: 1
+      ['.pre-commit-config.yaml', 'file.txt']
+    notify...................................................................Passed
+    - hook id: notify
+    - duration: [TIME]
+      notified
+    python-only..........................................(no files to check)Skipped
+    ");
+}
+
+/// The hook script passes its hook type explicitly, so it still runs the hooks of its stage
+/// when renamed.
+#[test]
+fn renamed_script() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: pre-commit
+             name: pre-commit
+             language: system
+             entry: echo
+             always_run: true
+             stages: [pre-commit]
+           - id: pre-push
+             name: pre-push
+             language: system
+             entry: echo
+             always_run: true
+             stages: [pre-push]
+    "});
+    context.git_add(".");
+
+    context.install().assert().success();
+    fs_err::rename(
+        context.workdir().join(".git/hooks/pre-commit"),
+        context.workdir().join(".git/hooks/renamed"),
+    )?;
+
+    let mut script = Command::new(context.workdir().join(".git/hooks/renamed"));
+    script
+        .current_dir(context.workdir())
+        .env("PRE_COMMIT_HOME", &**context.home_dir());
+
+    cmd_snapshot!(context.filters(), script, @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    pre-commit...............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `commit-msg` hooks get the message file, `pre-push` hooks the files of the pushed commits.
+#[test]
+fn hook_type_args() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc! { r"
+        repos:
+        - repo: local
+          hooks:
+           - id: commit-msg
+             name: commit-msg
+             language: system
+             entry: echo
+             verbose: true
+             stages: [commit-msg]
+           - id: pre-push
+             name: pre-push
+             language: system
+             entry: echo
+             verbose: true
+             stages: [pre-push]
+    "});
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    context.workdir().child("a.txt").write_str("a\n")?;
+    context.git_add(".");
+    context.git_commit("Add a.txt");
+
+    context.workdir().child("MSG").write_str("Message\n")?;
+    let mut commit_msg = context.command();
+    commit_msg.args([
+        "hook-impl",
+        "--hook-type",
+        "commit-msg",
+        "--hook-dir",
+        ".git/hooks",
+        "--",
+        "MSG",
+    ]);
+    cmd_snapshot!(context.filters(), commit_msg, @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    commit-msg...............................................................Passed
+    - hook id: commit-msg
+    - duration: [TIME]
+      MSG
+
+    ----- stderr -----
+    ");
+
+    let rev_parse = |rev: &str| -> anyhow::Result<String> {
+        let output = Command::new("git")
+            .arg("rev-parse")
+            .arg(rev)
+            .current_dir(context.workdir())
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    };
+    let head = rev_parse("HEAD")?;
+    let parent = rev_parse("HEAD~1")?;
+    let mut pre_push = context.command();
+    pre_push.args([
+        "hook-impl",
+        "--hook-type",
+        "pre-push",
+        "--hook-dir",
+        ".git/hooks",
+        "--",
+        "origin",
+        "https://example.com/repo.git",
+    ]);
+    cmd_snapshot!(
+        context.filters(),
+        pre_push.pass_stdin(format!("refs/heads/main {head} refs/heads/main {parent}\n")),
+        @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    pre-push.................................................................Passed
+    - hook id: pre-push
+    - duration: [TIME]
+      a.txt
+
+    ----- stderr -----
+    "
+    );
+
+    Ok(())
+}
+
+/// `pre-rebase` hooks get the upstream and the branch being rebased, and a failure aborts the rebase.
+#[test]
+fn pre_rebase() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc! { r#"
+        repos:
+        - repo: local
+          hooks:
+           - id: pre-rebase
+             name: pre-rebase
+             language: system
+             entry: python3 -c "import os; print(os.environ['PRE_COMMIT_PRE_REBASE_UPSTREAM'], os.environ.get('PRE_COMMIT_PRE_REBASE_BRANCH')); exit(1)"
+             pass_filenames: false
+             always_run: true
+             verbose: true
+             stages: [pre-rebase]
+    "#});
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    let mut pre_rebase = context.command();
+    pre_rebase.args([
+        "hook-impl",
+        "--hook-type",
+        "pre-rebase",
+        "--hook-dir",
+        ".git/hooks",
+        "--",
+        "main",
+        "topic",
+    ]);
+    cmd_snapshot!(context.filters(), pre_rebase, @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    pre-rebase...............................................................Failed
+    - hook id: pre-rebase
+    - duration: [TIME]
+    - exit code: 1
+      main topic
+
+    ----- stderr -----
+    ");
+
+    // Rebasing the current branch, git passes only the upstream.
+    context
+        .install()
+        .arg("--hook-type")
+        .arg("pre-rebase")
+        .assert()
+        .success();
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(context.workdir())
+            .assert()
+            .success();
+    };
+    git(&["branch", "upstream"]);
+    context.workdir().child("a.txt").write_str("a\n")?;
+    context.git_add(".");
+    context.git_commit("Add a.txt");
+    git(&["checkout", "--quiet", "upstream"]);
+    context.workdir().child("b.txt").write_str("b\n")?;
+    context.git_add(".");
+    context.git_commit("Add b.txt");
+    git(&["checkout", "--quiet", "-"]);
+
+    let mut rebase = Command::new("git");
+    rebase
+        .args(["rebase", "upstream"])
+        .current_dir(context.workdir())
+        .env("PRE_COMMIT_HOME", &**context.home_dir());
+    cmd_snapshot!(context.filters(), rebase, @r"
+    success: false
+    exit_code: 128
+    ----- stdout -----
+
+    ----- stderr -----
+    pre-rebase...............................................................Failed
+    - hook id: pre-rebase
+    - duration: [TIME]
+    - exit code: 1
+      upstream None
+    fatal: The pre-rebase hook refused to rebase.
+    ");
+
+    // The rebase was aborted.
+    assert!(!context.workdir().child("b.txt").exists());
+
+    Ok(())
+}
@@ -154,6 +154,44 @@ fn install() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// `install` without `--hook-type` installs the `default_install_hook_types` of the config.
+#[test]
+fn install_default_hook_types() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        default_install_hook_types: [pre-commit, commit-msg]
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+
+    cmd_snapshot!(context.filters(), context.install(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at .git/hooks/pre-commit
+    prefligit installed at .git/hooks/commit-msg
+
+    ----- stderr -----
+    ");
+
+    // `--hook-type` takes precedence over the config.
+    cmd_snapshot!(context.filters(), context.install().arg("--hook-type").arg("pre-push"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at .git/hooks/pre-push
+
+    ----- stderr -----
+    ");
+}
+
 #[test]
 fn uninstall() -> anyhow::Result<()> {
     let context = TestContext::new();
@@ -210,6 +248,14 @@ fn uninstall() -> anyhow::Result<()> {
 
     ----- stderr -----
     "#);
+    assert_eq!(
+        context.read(".git/hooks/pre-commit"),
+        "#!/bin/sh\necho 'pre-commit'\n"
+    );
+    context
+        .workdir()
+        .child(".git/hooks/pre-commit.legacy")
+        .assert(predicate::path::missing());
 
     // Uninstall multiple hooks.
     context
@@ -250,3 +296,92 @@ fn init_template_dir() {
     try `git config --global init.templateDir '.git'`?
     "#);
 }
+
+/// Warn about installed hook types without hooks, and hook stages that are not installed.
+#[test]
+fn install_stage_mismatch() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: echo
+                stages: [pre-commit]
+              - id: check-push
+                name: check-push
+                language: system
+                entry: echo
+                stages: [pre-push, manual]
+    "});
+
+    // No hook runs at `commit-msg`.
+    cmd_snapshot!(context.filters(), context.install().arg("--hook-type").arg("pre-commit").arg("--hook-type").arg("commit-msg"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at .git/hooks/pre-commit
+    prefligit installed at .git/hooks/commit-msg
+
+    ----- stderr -----
+    warning: No hook in the config runs at the `commit-msg` stage, the installed `commit-msg` hook will do nothing
+    warning: The `pre-push` hook is not installed, these hooks at the `pre-push` stage will not run: `check-push`
+    ");
+
+    // Nothing to warn about.
+    cmd_snapshot!(context.filters(), context.install().arg("--hook-type").arg("pre-commit").arg("--hook-type").arg("pre-push"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit installed at .git/hooks/pre-commit
+    prefligit installed at .git/hooks/pre-push
+
+    ----- stderr -----
+    ");
+}
+
+/// Installed hook scripts are executable, and `--overwrite` replaces an existing hook without
+/// keeping it as `.legacy`.
+#[test]
+#[cfg(unix)]
+fn install_executable_overwrite() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let context = TestContext::new();
+    context.init_project();
+
+    context
+        .workdir()
+        .child(".git/hooks/pre-push")
+        .write_str("#!/bin/sh\necho 'pre-push'\n")?;
+
+    cmd_snapshot!(context.filters(), context.install().arg("--overwrite").arg("--hook-type").arg("pre-push").arg("--hook-type").arg("commit-msg"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Overwriting existing hook at .git/hooks/pre-push
+    prefligit installed at .git/hooks/pre-push
+    prefligit installed at .git/hooks/commit-msg
+
+    ----- stderr -----
+    ");
+
+    for hook in ["pre-push", "commit-msg"] {
+        let path = context.workdir().child(".git/hooks").child(hook);
+        let mode = path.metadata()?.permissions().mode();
+        assert_eq!(mode & 0o111, 0o111, "{hook} is not executable");
+        assert!(context
+            .read(format!(".git/hooks/{hook}"))
+            .contains(&format!("ARGS=(hook-impl --hook-type={hook})")));
+    }
+    context
+        .workdir()
+        .child(".git/hooks/pre-push.legacy")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
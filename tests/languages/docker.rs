@@ -1,3 +1,6 @@
+use anyhow::Result;
+use assert_fs::prelude::*;
+
 use crate::common::{cmd_snapshot, TestContext};
 
 /// GitHub Action only has docker for linux hosted runners.
@@ -33,3 +36,118 @@ fn docker() {
     ----- stderr -----
     "#);
 }
+
+/// The `additional_dependencies` of a docker hook are passed as build args of its image.
+#[test]
+fn docker_build_args() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = greet_repo()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: greet
+              - id: greet
+                additional_dependencies: ['GREETING=hello']
+    ", repo.workdir().display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    let patterns = TestContext::path_patterns(repo.workdir());
+    let filters = patterns
+        .iter()
+        .map(|pattern| (pattern.as_str(), "[HOOK_REPO]/"))
+        .chain(context.filters())
+        .collect::<Vec<_>>();
+    cmd_snapshot!(filters, context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[HOOK_REPO]/@master
+    Preparing file://[HOOK_REPO]/@master with dependencies GREETING=hello
+    Installing environment for file://[HOOK_REPO]/@master
+    Installing environment for file://[HOOK_REPO]/@master
+    greet....................................................................Passed
+    - hook id: greet
+    - duration: [TIME]
+      greeting: default
+    greet....................................................................Passed
+    - hook id: greet
+    - duration: [TIME]
+      greeting: hello
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// Invalid build args are rejected before building the image.
+#[test]
+fn docker_invalid_build_args() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = greet_repo()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: greet
+                additional_dependencies: ['not a key']
+    ", repo.workdir().display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    let patterns = TestContext::path_patterns(repo.workdir());
+    let filters = patterns
+        .iter()
+        .map(|pattern| (pattern.as_str(), "[HOOK_REPO]/"))
+        .chain(context.filters())
+        .collect::<Vec<_>>();
+    cmd_snapshot!(filters, context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Cloning file://[HOOK_REPO]/@master
+    Preparing file://[HOOK_REPO]/@master with dependencies not a key
+    Installing environment for file://[HOOK_REPO]/@master
+
+    ----- stderr -----
+    error: Invalid build arg `not a key` in the `additional_dependencies` of hook `greet`, expected `KEY=VALUE` or `KEY`
+    ");
+
+    Ok(())
+}
+
+/// A repo with a `greet` docker hook, printing the `GREETING` build arg of its image.
+fn greet_repo() -> Result<TestContext> {
+    let repo = TestContext::new();
+    repo.init_project();
+    repo.configure_git_author();
+
+    repo.workdir()
+        .child("Dockerfile")
+        .write_str(indoc::indoc! {r#"
+        FROM alpine:3
+        ARG GREETING=default
+        RUN echo "greeting: $GREETING" > /greeting
+    "#})?;
+    repo.workdir()
+        .child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: greet
+          name: greet
+          language: docker
+          entry: cat /greeting
+          pass_filenames: false
+          always_run: true
+          verbose: true
+    "})?;
+    repo.git_add(".");
+    repo.git_commit("Initial commit");
+
+    Ok(repo)
+}
@@ -78,3 +78,91 @@ fn docker_image() -> Result<()> {
     "#);
     Ok(())
 }
+
+/// A local `docker_image` hook runs with the project root mounted writable, even when
+/// invoked from a subdirectory.
+#[test]
+fn local_fixer() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("sub/file.txt").write_str("Hello, world!\n")?;
+
+    Command::new("docker")
+        .args(["pull", "alpine:3.20"])
+        .assert()
+        .success();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: docker_image
+                entry: alpine:3.20 sh -c 'for f in "$@"; do echo fixed >> "$f"; done' --
+                files: \.txt$
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.child("sub")), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+
+    ----- stderr -----
+    "#);
+
+    assert_eq!(context.read("sub/file.txt"), "Hello, world!\nfixed\n");
+
+    Ok(())
+}
+
+/// `--docker-jobs` caps the number of containers run at once, so the files are split into
+/// at most that many batches.
+#[test]
+fn docker_jobs() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    for i in 0..16 {
+        cwd.child(format!("file{i:02}.txt")).write_str("Hello\n")?;
+    }
+
+    Command::new("docker")
+        .args(["pull", "alpine:3.20"])
+        .assert()
+        .success();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: count
+                name: count
+                language: docker_image
+                entry: alpine:3.20 sh -c 'echo "batch of $#"' --
+                files: \.txt$
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--docker-jobs").arg("1"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    count....................................................................Passed
+    - hook id: count
+    - duration: [TIME]
+      batch of 16
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
@@ -6,3 +6,8 @@ mod docker;
 #[cfg(all(feature = "docker", target_os = "linux"))]
 mod docker_image;
 mod fail;
+mod pygrep;
+#[cfg(unix)]
+mod python;
+#[cfg(unix)]
+mod remote_script;
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use assert_fs::prelude::*;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+/// Fail with the matched lines when a forbidden pattern is found.
+#[test]
+fn pygrep() -> Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("a.py").write_str("import os\nbreakpoint()\n")?;
+    cwd.child("b.py").write_str("import sys\n")?;
+    cwd.child("c.py")
+        .write_str("def f():\n    BREAKPOINT()\n    pass\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: no-breakpoint
+                name: no breakpoint
+                language: pygrep
+                entry: '\bbreakpoint\(\)'
+                types: [python]
+              - id: no-breakpoint-ignore-case
+                name: no breakpoint ignore case
+                language: pygrep
+                entry: '\bbreakpoint\(\)'
+                args: [-i]
+                types: [python]
+              - id: no-multiline-pass
+                name: no multiline pass
+                language: pygrep
+                entry: '\(\)\n\s+pass'
+                args: [--multiline, --ignore-case]
+                types: [python]
+    "});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    no breakpoint............................................................Failed
+    - hook id: no-breakpoint
+    - exit code: 1
+      a.py:2:breakpoint()
+    no breakpoint ignore case................................................Failed
+    - hook id: no-breakpoint-ignore-case
+    - exit code: 1
+      a.py:2:breakpoint()
+      c.py:2:    BREAKPOINT()
+    no multiline pass........................................................Failed
+    - hook id: no-multiline-pass
+    - exit code: 1
+      c.py:2:    BREAKPOINT()
+          pass
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// With `--negate`, fail for the files that don't match.
+#[test]
+fn pygrep_negate() -> Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("a.py")
+        .write_str("# Copyright 2024\nimport os\n")?;
+    cwd.child("b.py").write_str("import sys\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: copyright
+                name: copyright
+                language: pygrep
+                entry: '^# Copyright'
+                args: [--negate]
+                types: [python]
+    "});
+
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    copyright................................................................Failed
+    - hook id: copyright
+    - exit code: 1
+      b.py
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::os::unix::fs::PermissionsExt;
+
+use anyhow::Result;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+/// A fake `uv`, which records its subcommands and creates the venv with `venv`.
+const FAKE_UV: &str = r#"#!/bin/sh
+echo "$1" >> "$(dirname "$0")/uv.log"
+if [ "$1" = venv ]; then
+    exec python3 -m venv --without-pip "$2"
+fi
+"#;
+
+/// `uv` from `PATH` is used to create the environment when available.
+#[test]
+fn uv_installer() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let bin = context.home_dir().child("fake-bin");
+    let uv = bin.child("uv");
+    uv.write_str(FAKE_UV)?;
+    fs_err::set_permissions(&uv, std::fs::Permissions::from_mode(0o755))?;
+    let path = std::env::join_paths(std::iter::once(bin.to_path_buf()).chain(
+        std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default()),
+    ))?;
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: in-venv
+                name: in-venv
+                language: python
+                entry: python -c "import sys; print('in venv:', sys.prefix != sys.base_prefix)"
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("PATH", &path), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo in-venv
+    Installing environment for local
+    in-venv..................................................................Passed
+    - hook id: in-venv
+    - duration: [TIME]
+      in venv: True
+
+    ----- stderr -----
+    ");
+
+    assert_eq!(fs_err::read_to_string(bin.child("uv.log"))?, "venv\npip\n");
+
+    Ok(())
+}
+
+/// `cache verify` reports an environment that fails its health check, and `--repair` installs
+/// it again.
+#[test]
+fn cache_verify_unhealthy_env() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let bin = context.home_dir().child("fake-bin");
+    let uv = bin.child("uv");
+    uv.write_str(FAKE_UV)?;
+    fs_err::set_permissions(&uv, std::fs::Permissions::from_mode(0o755))?;
+    let path = std::env::join_paths(std::iter::once(bin.to_path_buf()).chain(
+        std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default()),
+    ))?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: noop
+                name: noop
+                language: python
+                entry: python -c pass
+                always_run: true
+    "});
+    context.git_add(".");
+    context.run().env("PATH", &path).assert().success();
+
+    // Pretend the environment was created by another Python version.
+    let cfg = fs_err::read_dir(context.home_dir().path())?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("repo"))
+        .flat_map(|repo| fs_err::read_dir(repo.path()).into_iter().flatten())
+        .filter_map(Result::ok)
+        .map(|env| env.path().join("pyvenv.cfg"))
+        .find(|cfg| cfg.exists())
+        .expect("the environment should be installed");
+    let mut content = fs_err::read_to_string(&cfg)?;
+    content.push_str("version_info = 2.7.18\n");
+    fs_err::write(&cfg, content)?;
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([
+            (r"\[HOME\]/repo\w+/py_env-[^`]+", "[HOME]/[REPO]/[ENV]"),
+            (r"to `[^`]+`", "to `[VERSION]`"),
+        ])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters.clone(), context.command().arg("cache").arg("verify").env("PATH", &path), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Corrupt environment of hook `noop` at `[HOME]/[REPO]/[ENV]`: Python version changed from `2.7.18` to `[VERSION]`, the environment needs to be reinstalled
+    Found 1 corrupt entries, run with `--repair` to repair them
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(filters.clone(), context.command().arg("cache").arg("verify").arg("--repair").env("PATH", &path), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Corrupt environment of hook `noop` at `[HOME]/[REPO]/[ENV]`: Python version changed from `2.7.18` to `[VERSION]`, the environment needs to be reinstalled
+    Installing environment for local
+    Repaired 1 corrupt entries
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(filters, context.command().arg("cache").arg("verify").env("PATH", &path), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Verified 1 repos and 1 environments, no corrupt entries found
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// Without a Python interpreter in `PATH`, a managed Python build is installed for the hook.
+#[test]
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn managed_python() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // A `PATH` with the tools needed besides Python.
+    let bin = context.home_dir().child("bin");
+    bin.create_dir_all()?;
+    for tool in ["git", "tar", "gzip", "uv"] {
+        if let Ok(path) = which::which(tool) {
+            std::os::unix::fs::symlink(path, bin.child(tool))?;
+        }
+    }
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: version
+                name: version
+                language: python
+                language_version: "3.11"
+                entry: python -c "import sys; print(sys.version_info[:2])"
+                always_run: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("PATH", &*bin), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo version
+    Installing environment for local
+    version..................................................................Passed
+    - hook id: version
+    - duration: [TIME]
+      (3, 11)
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `default_language_version` sets the interpreter of python hooks without `language_version`.
+#[test]
+fn default_language_version() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // A fake `uv` that records the interpreter of the venv, and a `python3.11` to find.
+    let bin = context.home_dir().child("fake-bin");
+    let uv = bin.child("uv");
+    uv.write_str(indoc::indoc! {r#"
+        #!/bin/sh
+        if [ "$1" = venv ]; then
+            echo "$4" > "$(dirname "$0")/python.log"
+            exec python3 -m venv --without-pip "$2"
+        fi
+    "#})?;
+    let python = bin.child("python3.11");
+    python.write_str("#!/bin/sh\nexec python3 \"$@\"\n")?;
+    for file in [&uv, &python] {
+        fs_err::set_permissions(file, std::fs::Permissions::from_mode(0o755))?;
+    }
+    let path = std::env::join_paths(std::iter::once(bin.to_path_buf()).chain(
+        std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default()),
+    ))?;
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        default_language_version:
+          python: python3.11
+        repos:
+          - repo: local
+            hooks:
+              - id: in-venv
+                name: in-venv
+                language: python
+                entry: python -c "print('in venv')"
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    context.run().env("PATH", &path).assert().success();
+
+    assert_eq!(
+        fs_err::read_to_string(bin.child("python.log"))?.trim(),
+        python.display().to_string()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::process::Command;
+
+use anyhow::Result;
+use assert_cmd::assert::OutputAssertExt;
+use assert_fs::prelude::*;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+const SCRIPT: &str = "#!/bin/sh\necho \"checked: $*\"\n";
+const CHECKSUM: &str = "3c25297b6ae9809db816fa96c68145aafd6dac0c6f6dcb13d228fb365f4ec0f2";
+
+/// Serve `SCRIPT` over http on a local port, returning its URL.
+fn serve_script() -> Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/check.sh", listener.local_addr()?);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            // Read the request headers.
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{SCRIPT}",
+                SCRIPT.len()
+            );
+        }
+    });
+
+    Ok(url)
+}
+
+#[test]
+fn remote_script() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+
+    let url = serve_script()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url} --flag
+                checksum: {CHECKSUM}
+                verbose: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo remote-script
+    Installing environment for local
+    remote-script............................................................Passed
+    - hook id: remote-script
+    - duration: [TIME]
+      checked: --flag .pre-commit-config.yaml file.txt
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// With `--output-format json`, installing the environment doesn't print to stdout,
+/// so it only contains the JSON report.
+#[test]
+fn remote_script_json_output() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let url = serve_script()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url}
+                checksum: {CHECKSUM}
+                always_run: true
+                pass_filenames: false
+    "});
+    context.git_add(".");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r#""duration_ms": \d+"#, r#""duration_ms": [DURATION]"#)])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters, context.run().arg("--output-format").arg("json"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [
+      {
+        "id": "remote-script",
+        "name": "remote-script",
+        "stage": "pre-commit",
+        "status": "passed",
+        "files": [
+          ".pre-commit-config.yaml"
+        ],
+        "exit_code": 0,
+        "duration_ms": [DURATION],
+        "files_modified": false,
+        "output": "checked: /n"
+      }
+    ]
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// With `--summary-only`, installing the environment doesn't print to stdout either.
+#[test]
+fn remote_script_summary_output() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let url = serve_script()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url}
+                checksum: {CHECKSUM}
+                always_run: true
+                pass_filenames: false
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--summary-only"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Passed: 1 passed, 0 failed, 0 skipped
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// The downloaded script must match the `checksum`, which is required.
+#[test]
+fn remote_script_checksum() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let url = serve_script()?;
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url}
+                checksum: {}
+    ", "0".repeat(64)});
+    context.git_add(".");
+
+    let mut filters = context.filters();
+    filters.push((r"127\.0\.0\.1:\d+", "[HOST]"));
+
+    cmd_snapshot!(filters.clone(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Preparing local repo remote-script
+    Installing environment for local
+
+    ----- stderr -----
+    error: Checksum mismatch for script `http://[HOST]/check.sh`, expected `0000000000000000000000000000000000000000000000000000000000000000`, got `3c25297b6ae9809db816fa96c68145aafd6dac0c6f6dcb13d228fb365f4ec0f2`
+    ");
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url}
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(filters.clone(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Hook `remote-script` uses language `remote_script`, but `checksum` is not set
+    ");
+
+    // The checksum names the environment directory, so it must be a digest.
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url}
+                checksum: ../../escape
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(filters.clone(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to parse `.pre-commit-config.yaml`
+      caused by: repos: Invalid local repo: Invalid checksum `../../escape`, expected a SHA-256 digest of 64 hex characters at line 2 column 3
+    ");
+
+    // Only loopback hosts are allowed over plain http.
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: http://example.com/check.sh
+                checksum: {CHECKSUM}
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(filters, context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Installing environment for local
+
+    ----- stderr -----
+    error: Unsupported script URL `http://example.com/check.sh`, only https is supported
+    ");
+
+    Ok(())
+}
+
+/// Environments are keyed on the hook, not on the project, so projects sharing a config share
+/// the installed environments.
+#[test]
+fn shared_environment() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let url = serve_script()?;
+    let config = indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: remote-script
+                name: remote-script
+                language: remote_script
+                entry: {url}
+                checksum: {CHECKSUM}
+                always_run: true
+                pass_filenames: false
+    "};
+    context.write_pre_commit_config(&config);
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo remote-script
+    Installing environment for local
+    remote-script............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    // Another project, using the same store.
+    let other = context.workdir().child("other");
+    other.create_dir_all()?;
+    Command::new("git")
+        .arg("init")
+        .current_dir(&other)
+        .assert()
+        .success();
+    other.child(".pre-commit-config.yaml").write_str(&config)?;
+    Command::new("git")
+        .args(["add", "."])
+        .current_dir(&other)
+        .assert()
+        .success();
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(&other), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    remote-script............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+/// `list` shows the hooks with their descriptions, if any.
+#[test]
+fn list() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trim trailing whitespace
+                description: Trims trailing whitespace.
+                language: system
+                entry: echo
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+
+    cmd_snapshot!(context.filters(), context.command().arg("list"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trailing-whitespace (trim trailing whitespace)
+      Trims trailing whitespace.
+    echo
+
+    ----- stderr -----
+    ");
+}
+
+/// Hooks that require a newer pre-commit are flagged, but still listed.
+#[test]
+fn list_unsupported_version() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: future
+                name: future
+                language: system
+                entry: echo
+                minimum_pre_commit_version: '999.0.0'
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                minimum_pre_commit_version: '2.0.0'
+    "});
+
+    cmd_snapshot!(context.filters(), context.command().arg("list"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    future
+      warning: requires pre-commit version `999.0.0`, but prefligit is compatible with pre-commit version `4.0.1`
+    echo
+
+    ----- stderr -----
+    ");
+}
+
+/// `list --format json` includes the resolved filters, without cloning the remote repos.
+#[test]
+fn list_json() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        default_language_version:
+          python: python3.12
+        default_stages: [pre-commit, pre-push]
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            rev: v5.0.0
+            hooks:
+              - id: trailing-whitespace
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: python
+                entry: lint
+                files: ^src/
+                exclude: [^src/vendor/, \.min\.py$]
+                types: [python]
+                exclude_types: [markdown]
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                types_or: [yaml, json]
+                stages: [manual]
+          - repo: meta
+            hooks:
+              - id: identity
+    "});
+
+    cmd_snapshot!(context.filters(), context.command().arg("list").arg("--format").arg("json"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [
+      {
+        "status": "unresolved",
+        "id": "trailing-whitespace",
+        "repo": "https://github.com/pre-commit/pre-commit-hooks@v5.0.0"
+      },
+      {
+        "status": "resolved",
+        "id": "lint",
+        "name": "lint",
+        "repo": "local",
+        "language": "python",
+        "configured_language_version": "python3.12",
+        "stages": [
+          "pre-commit",
+          "pre-push"
+        ],
+        "files": "^src/",
+        "exclude": [
+          "^src/vendor/",
+          "\\.min\\.py$"
+        ],
+        "files_glob": false,
+        "exclude_glob": false,
+        "files_prefix": null,
+        "types": [
+          "python"
+        ],
+        "types_or": [],
+        "exclude_types": [
+          "markdown"
+        ]
+      },
+      {
+        "status": "resolved",
+        "id": "echo",
+        "name": "echo",
+        "repo": "local",
+        "language": "system",
+        "configured_language_version": "default",
+        "stages": [
+          "manual"
+        ],
+        "files": null,
+        "exclude": [],
+        "files_glob": false,
+        "exclude_glob": false,
+        "files_prefix": null,
+        "types": [
+          "file"
+        ],
+        "types_or": [
+          "yaml",
+          "json"
+        ],
+        "exclude_types": []
+      },
+      {
+        "status": "resolved",
+        "id": "identity",
+        "name": "identity",
+        "repo": "meta",
+        "language": "system",
+        "configured_language_version": "default",
+        "stages": [
+          "pre-commit",
+          "pre-push"
+        ],
+        "files": null,
+        "exclude": [],
+        "files_glob": false,
+        "exclude_glob": false,
+        "files_prefix": null,
+        "types": [
+          "file"
+        ],
+        "types_or": [],
+        "exclude_types": []
+      }
+    ]
+
+    ----- stderr -----
+    "#);
+}
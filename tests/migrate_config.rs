@@ -0,0 +1,82 @@
+use insta::assert_snapshot;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+/// A legacy config is migrated, keeping its comments and ordering.
+#[test]
+fn migrate_config() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        # A legacy config.
+
+        -   repo: https://github.com/pre-commit/pre-commit-hooks
+            sha: v5.0.0  # Pinned.
+            hooks:
+            -   id: trailing-whitespace
+                stages: [commit, 'push', manual]
+            -   id: end-of-file-fixer
+                stages:
+                -   commit
+                # A comment in the list.
+                -   merge-commit
+        -   repo: local
+            hooks:
+            -   id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+
+    cmd_snapshot!(context.filters(), context.command().arg("migrate-config"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Configuration has been migrated.
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(context.read(".pre-commit-config.yaml"), @r"
+    # A legacy config.
+
+    repos:
+    -   repo: https://github.com/pre-commit/pre-commit-hooks
+        rev: v5.0.0  # Pinned.
+        hooks:
+        -   id: trailing-whitespace
+            stages: [pre-commit, 'pre-push', manual]
+        -   id: end-of-file-fixer
+            stages:
+            -   pre-commit
+            # A comment in the list.
+            -   pre-merge-commit
+    -   repo: local
+        hooks:
+        -   id: echo
+            name: echo
+            language: system
+            entry: echo
+    ");
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg(".pre-commit-config.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    // Migrating again changes nothing.
+    cmd_snapshot!(context.filters(), context.command().arg("migrate-config"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Configuration is already migrated.
+
+    ----- stderr -----
+    ");
+}
@@ -1,3 +1,4 @@
+use std::fmt::Write as _;
 use std::process::Command;
 
 use anyhow::Result;
@@ -5,7 +6,7 @@ use assert_cmd::assert::OutputAssertExt;
 use assert_fs::prelude::*;
 use insta::assert_snapshot;
 
-use crate::common::{cmd_snapshot, TestContext};
+use crate::common::{cmd_snapshot, git_commit_all, TestContext};
 
 mod common;
 
@@ -140,6 +141,275 @@ fn local_need_install() {
     "#);
 }
 
+/// A local `node` hook installs its `additional_dependencies` into the environment.
+#[test]
+fn local_node_hook() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // A local package, so that it installs without network access.
+    let package = context.home_dir().child("hello-node");
+    package.child("package.json").write_str(indoc::indoc! {r#"
+        {
+          "name": "hello-node",
+          "version": "1.0.0",
+          "bin": { "hello-node": "cli.js" }
+        }
+    "#})?;
+    package.child("cli.js").write_str(indoc::indoc! {r"
+        #!/usr/bin/env node
+        console.log(`Hello from node, ${process.argv.length - 2} files`);
+    "})?;
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: node
+                entry: hello-node
+                additional_dependencies: ['{}']
+                verbose: true
+    ", package.display()});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo local
+    Installing environment for local
+    local....................................................................Passed
+    - hook id: local
+    - duration: [TIME]
+      Hello from node, 1 files
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A local `ruby` hook gets an isolated environment and runs its entry with the environment ruby.
+#[test]
+fn local_ruby_hook() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: ruby
+                entry: ruby -e 'puts "Hello from ruby, #{ARGV.length} files, GEM_HOME=#{File.basename(ENV["GEM_HOME"])}"'
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo local
+    Installing environment for local
+    local....................................................................Passed
+    - hook id: local
+    - duration: [TIME]
+      Hello from ruby, 1 files, GEM_HOME=ruby_env-default
+
+    ----- stderr -----
+    ");
+}
+
+/// In `python path/to/script.py`, `python` is the interpreter of the environment, and the script
+/// is resolved relative to the hook repo.
+#[test]
+fn python_script_entry() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.workdir().child("hook-repo");
+    repo.child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: check
+          name: check
+          language: python
+          entry: python scripts/check.py
+    "})?;
+    repo.child("setup.py")
+        .write_str("from setuptools import setup\nsetup(name='check')\n")?;
+    repo.child("scripts/check.py").write_str(indoc::indoc! {r"
+        import sys
+        print('in venv:', sys.prefix != sys.base_prefix, sys.argv[1:])
+    "})?;
+    git_commit_all(&repo);
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: check
+                files: \.txt$
+                verbose: true
+    ", repo.display()});
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add(".pre-commit-config.yaml");
+    context.git_add("file.txt");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/hook-repo@master
+    Installing environment for file://[TEMP_DIR]/hook-repo@master
+    check....................................................................Passed
+    - hook id: check
+    - duration: [TIME]
+      in venv: True ['file.txt']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A remote repo with `path` reads the manifest from that subdirectory of the clone.
+#[test]
+fn remote_repo_path() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.workdir().child("hook-repo");
+    repo.child("hooks/.pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: subdir-hook
+          name: subdir-hook
+          language: system
+          entry: echo from subdir
+          always_run: true
+          pass_filenames: false
+          verbose: true
+    "})?;
+    git_commit_all(&repo);
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            path: hooks
+            hooks:
+              - id: subdir-hook
+    ", repo.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/hook-repo@master
+    subdir-hook..............................................................Passed
+    - hook id: subdir-hook
+    - duration: [TIME]
+      from subdir
+
+    ----- stderr -----
+    ");
+
+    // The path must stay inside the repo.
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            path: ../hooks
+            hooks:
+              - id: subdir-hook
+    ", repo.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to parse `.pre-commit-config.yaml`
+      caused by: repos: Invalid remote repo: `path` must be a relative path inside the repo, got `../hooks` at line 2 column 3
+    ");
+
+    Ok(())
+}
+
+/// `args` in the config replace the `args` of the manifest, unless `append_args` is set.
+#[test]
+fn override_args() {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.create_hook_repo(
+        "hook-repo",
+        indoc::indoc! {r"
+            - id: echo
+              name: echo
+              language: system
+              entry: echo
+              args: [--from-manifest]
+              always_run: true
+              pass_filenames: false
+              verbose: true
+        "},
+    );
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+                args: [--from-config]
+    ", repo.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/hook-repo@master
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      --from-config
+
+    ----- stderr -----
+    ");
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: echo
+                args: [--from-config]
+                append_args: true
+    ", repo.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      --from-manifest --from-config
+
+    ----- stderr -----
+    ");
+}
+
 #[test]
 fn invalid_hook_id() {
     let context = TestContext::new();
@@ -383,6 +653,164 @@ fn files_and_exclude() -> Result<()> {
     Ok(())
 }
 
+/// Test `files_glob`, which interprets `files` and `exclude` as gitignore-style globs.
+#[test]
+fn files_glob() -> Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("main.py").write_str("print('main')")?;
+    cwd.child("src/lib.py").write_str("print('lib')")?;
+    cwd.child("src/data.json").write_str("{}")?;
+    cwd.child("tests/test_lib.py").write_str("print('test')")?;
+
+    // A glob and the equivalent regex select the same files.
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: regex
+                name: regex
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: \.py$
+                exclude: ^tests/
+                verbose: true
+              - id: glob
+                name: glob
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: "*.py"
+                exclude: /tests/
+                files_glob: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    regex....................................................................Passed
+    - hook id: regex
+    - duration: [TIME]
+      ['main.py', 'src/lib.py']
+    glob.....................................................................Passed
+    - hook id: glob
+    - duration: [TIME]
+      ['main.py', 'src/lib.py']
+
+    ----- stderr -----
+    "#);
+
+    // The top-level `files_glob` applies to the top-level patterns and is the default for hooks,
+    // a hook-level `files_glob` takes precedence.
+    context.write_pre_commit_config(indoc::indoc! {r"
+        files_glob: true
+        exclude: |
+          tests/
+          !tests/test_lib.py
+        repos:
+          - repo: local
+            hooks:
+              - id: glob
+                name: glob
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: src/*
+                verbose: true
+              - id: regex
+                name: regex
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: ^src/.*\.json$
+                files_glob: false
+                verbose: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    glob.....................................................................Passed
+    - hook id: glob
+    - duration: [TIME]
+      ['src/data.json', 'src/lib.py']
+    regex....................................................................Passed
+    - hook id: regex
+    - duration: [TIME]
+      ['src/data.json']
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// The top-level `files_glob` doesn't change how the regexes of a remote manifest are read, only
+/// the patterns set in the config.
+#[test]
+fn files_glob_remote_manifest() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.create_hook_repo(
+        "hook-repo",
+        indoc::indoc! {r"
+            - id: manifest
+              name: manifest
+              language: system
+              entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+              files: \.py$
+              exclude: ^tests/
+              verbose: true
+        "},
+    );
+
+    let cwd = context.workdir();
+    cwd.child("main.py").write_str("print('main')")?;
+    cwd.child("src/lib.py").write_str("print('lib')")?;
+    cwd.child("tests/test_lib.py").write_str("print('test')")?;
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        files_glob: true
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: manifest
+              - id: manifest
+                alias: override
+                exclude: /src/
+    ", repo.display()});
+    context.git_add("main.py");
+    context.git_add("src");
+    context.git_add("tests");
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/hook-repo@master
+    manifest.................................................................Passed
+    - hook id: manifest
+    - duration: [TIME]
+      ['main.py', 'src/lib.py']
+    manifest.................................................................Passed
+    - hook id: manifest
+    - duration: [TIME]
+      ['main.py', 'tests/test_lib.py']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
 /// Test selecting files by type, `types`, `types_or`, and `exclude_types`.
 #[test]
 fn file_types() -> Result<()> {
@@ -453,6 +881,57 @@ fn file_types() -> Result<()> {
     Ok(())
 }
 
+/// Extensionless scripts are identified by their shebang, executable or not.
+#[test]
+fn shebang_types() -> Result<()> {
+    let context = TestContext::new();
+
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("build").write_str("#!/bin/sh\necho building\n")?;
+    cwd.child("tool")
+        .write_str("#!/usr/bin/env python3\nprint('tool')\n")?;
+    cwd.child("README").write_str("Not a script\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: shell
+                name: shell
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1:])'
+                types: [shell]
+                verbose: true
+              - id: python
+                name: python
+                language: system
+                entry: python3 -c 'import sys; print(sys.argv[1:])'
+                types: [python]
+                verbose: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    shell....................................................................Passed
+    - hook id: shell
+    - duration: [TIME]
+      ['build']
+    python...................................................................Passed
+    - hook id: python
+    - duration: [TIME]
+      ['tool']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
 /// Abort the run if a hook fails.
 #[test]
 fn fail_fast() {
@@ -664,38 +1143,113 @@ fn staged_files_only() -> Result<()> {
     Ok(())
 }
 
-#[cfg(unix)]
+/// Hooks only see the staged hunks of a partially staged file, as with `git add -p`.
 #[test]
-fn restore_on_interrupt() -> Result<()> {
+fn partially_staged() -> Result<()> {
     let context = TestContext::new();
     context.init_project();
-    // The hook will sleep for 3 seconds.
-    context.write_pre_commit_config(indoc::indoc! {r#"
+    context.configure_git_author();
+    context.write_pre_commit_config(indoc::indoc! {r"
         repos:
           - repo: local
             hooks:
-              - id: trailing-whitespace
-                name: trailing-whitespace
+              - id: show
+                name: show
                 language: system
-                entry: python3 -c 'import time; open("out.txt", "wt").write(open("file.txt", "rt").read()); time.sleep(10)'
+                entry: python3 -c 'import sys; print(open(sys.argv[1]).read())'
                 verbose: true
-                types: [text]
-   "#});
+   "});
 
-    context
-        .workdir()
-        .child("file.txt")
-        .write_str("Hello, world!")?;
+    let file = context.workdir().child("file.txt");
+    file.write_str("one\ntwo\nthree\nfour\nfive\n")?;
     context.git_add(".");
+    context.git_commit("Initial commit");
 
-    // Non-staged files should be stashed and restored.
-    context
-        .workdir()
-        .child("file.txt")
-        .write_str("Hello world again!")?;
+    // Stage the first hunk only, leave the second one unstaged.
+    file.write_str("ONE\ntwo\nthree\nfour\nfive\n")?;
+    context.git_add("file.txt");
+    file.write_str("ONE\ntwo\nthree\nfour\nFIVE\n")?;
 
-    let mut child = context.run().spawn()?;
-    let child_id = child.id();
+    let filters: Vec<_> = context
+        .filters()
+        .into_iter()
+        .chain([(r"/\d+-\d+.patch", "/[TIME]-[PID].patch")])
+        .collect();
+
+    cmd_snapshot!(filters, context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    show.....................................................................Passed
+    - hook id: show
+    - duration: [TIME]
+      ONE
+      two
+      three
+      four
+      five
+
+    ----- stderr -----
+    Non-staged changes detected, saving to `[HOME]/[TIME]-[PID].patch`
+
+    Restored working tree changes from `[HOME]/[TIME]-[PID].patch`
+    ");
+
+    // Both the staged and the unstaged hunks are kept.
+    assert_snapshot!(context.read("file.txt"), @r"
+    ONE
+    two
+    three
+    four
+    FIVE
+    ");
+    let staged = Command::new("git")
+        .args(["show", ":file.txt"])
+        .current_dir(context.workdir())
+        .output()?;
+    assert_snapshot!(String::from_utf8_lossy(&staged.stdout), @r"
+    ONE
+    two
+    three
+    four
+    five
+    ");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn restore_on_interrupt() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    // The hook will sleep for 3 seconds.
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c 'import time; open("out.txt", "wt").write(open("file.txt", "rt").read()); time.sleep(10)'
+                verbose: true
+                types: [text]
+   "#});
+
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("Hello, world!")?;
+    context.git_add(".");
+
+    // Non-staged files should be stashed and restored.
+    context
+        .workdir()
+        .child("file.txt")
+        .write_str("Hello world again!")?;
+
+    let mut child = context.run().spawn()?;
+    let child_id = child.id();
 
     // Send an interrupt signal to the process.
     let handle = std::thread::spawn(move || {
@@ -797,3 +1351,3472 @@ fn merge_conflicts() -> Result<()> {
 
     Ok(())
 }
+
+/// `--color`, `--no-color` and `PRE_COMMIT_COLOR` take precedence over `NO_COLOR`, which takes
+/// precedence over `FORCE_COLOR`, which takes precedence over terminal detection.
+#[test]
+fn color_precedence() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: local
+                name: local
+                language: system
+                entry: python3 -V
+                always_run: true
+    "});
+    context.git_add(".");
+
+    let colored = |args: &[&str], envs: &[(&str, &str)]| -> Result<bool> {
+        let output = context
+            .run()
+            .env_remove("NO_COLOR")
+            .env_remove("FORCE_COLOR")
+            .env_remove("CLICOLOR_FORCE")
+            .env_remove("PRE_COMMIT_COLOR")
+            .args(args)
+            .envs(envs.iter().copied())
+            .output()?;
+        assert!(output.status.success());
+        Ok(output.stdout.contains(&0x1b))
+    };
+
+    // Stdout is not a terminal.
+    assert!(!colored(&[], &[])?);
+
+    assert!(colored(&[], &[("FORCE_COLOR", "1")])?);
+    assert!(!colored(&[], &[("NO_COLOR", "1")])?);
+    assert!(!colored(&[], &[("NO_COLOR", "1"), ("FORCE_COLOR", "1")])?);
+    // Empty values are ignored.
+    assert!(!colored(&[], &[("FORCE_COLOR", "")])?);
+
+    assert!(colored(&["--color", "always"], &[("NO_COLOR", "1")])?);
+    assert!(!colored(&["--color", "never"], &[("FORCE_COLOR", "1")])?);
+    assert!(!colored(&["--no-color"], &[("FORCE_COLOR", "1")])?);
+    assert!(!colored(&["--color", "always", "--no-color"], &[])?);
+    assert!(colored(
+        &[],
+        &[("PRE_COMMIT_COLOR", "always"), ("NO_COLOR", "1")]
+    )?);
+    assert!(!colored(
+        &["--no-color"],
+        &[("PRE_COMMIT_COLOR", "always")]
+    )?);
+
+    Ok(())
+}
+
+/// The error text follows `--color` too, even when stderr is piped.
+#[test]
+fn color_error_text() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config("repos: [\n");
+    context.git_add(".");
+
+    let colored = |args: &[&str]| -> Result<bool> {
+        let output = context
+            .run()
+            .env_remove("NO_COLOR")
+            .env_remove("FORCE_COLOR")
+            .env_remove("CLICOLOR_FORCE")
+            .env_remove("PRE_COMMIT_COLOR")
+            .args(args)
+            .output()?;
+        assert!(!output.status.success());
+        Ok(output.stderr.contains(&0x1b))
+    };
+
+    assert!(!colored(&[])?);
+    assert!(colored(&["--color", "always"])?);
+    assert!(!colored(&["--color", "never"])?);
+
+    Ok(())
+}
+
+/// A hook that modifies files but exits 0 is still reported as failed.
+#[test]
+fn modify_with_zero_exit() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: system
+                entry: python3 -c 'import sys; [open(f, "a").write("fixed\n") for f in sys.argv[1:]]'
+                files: \.txt$
+    "#});
+
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("Hello, world!\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+
+    ----- stderr -----
+    "#);
+
+    // Untracked files are not visible to `git diff`, but modifications are still detected.
+    cwd.child("untracked.txt").write_str("Hello, world!\n")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("untracked.txt"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+
+    ----- stderr -----
+    "#);
+
+    assert_eq!(context.read("untracked.txt"), "Hello, world!\nfixed\n");
+
+    Ok(())
+}
+
+/// Files larger than `--max-file-size` or `max_file_size` are excluded from hook input.
+#[test]
+fn max_file_size() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        max_file_size: 1024
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: \.txt$
+                verbose: true
+    "});
+
+    let cwd = context.workdir();
+    cwd.child("small.txt").write_str("Hello, world!\n")?;
+    cwd.child("large.txt").write_str(&"a".repeat(2048))?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      ['small.txt']
+
+    ----- stderr -----
+    warning: Skipped 1 file larger than 1024 bytes:
+      large.txt
+    "#);
+
+    // The command line option overrides the config.
+    cmd_snapshot!(context.filters(), context.run().arg("--max-file-size").arg("4096"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      ['large.txt', 'small.txt']
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// A local `system` hook can use tools installed in the environment of another hook.
+#[test]
+fn use_env_of() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: pyecho
+                name: pyecho
+                language: python
+                entry: pyecho Hello, world!
+                additional_dependencies: ["pyecho-cli"]
+                always_run: true
+              - id: system-pyecho
+                name: system-pyecho
+                language: system
+                entry: pyecho Hello from system!
+                use_env_of: pyecho
+                always_run: true
+                verbose: true
+    "#});
+
+    context.git_add(".");
+
+    // The referenced environment is installed even if the hook itself doesn't run.
+    cmd_snapshot!(context.filters(), context.run().arg("system-pyecho"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo pyecho
+    Installing environment for local
+    system-pyecho............................................................Passed
+    - hook id: system-pyecho
+    - duration: [TIME]
+      Hello from system!
+
+    ----- stderr -----
+    "#);
+}
+
+/// `use_env_of` must reference a hook with an environment.
+#[test]
+fn use_env_of_invalid() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                use_env_of: missing
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Hook `echo` uses the environment of `missing`, but no hook with that id is found
+    "#);
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+              - id: echo2
+                name: echo2
+                language: system
+                entry: echo
+                use_env_of: echo
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r#"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Hook `echo2` uses the environment of `echo`, but `echo` has no environment
+    "#);
+}
+
+/// Compute the files to run `pre-push` hooks on from a file of pre-push ref lines.
+#[test]
+fn push_refs_file() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: show-files
+                name: show-files
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: \.txt$
+                verbose: true
+    "});
+
+    let rev_parse = || -> Result<String> {
+        let output = Command::new("git")
+            .arg("rev-parse")
+            .arg("HEAD")
+            .current_dir(context.workdir())
+            .output()?;
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    };
+
+    let cwd = context.workdir();
+    cwd.child("a.txt").write_str("a\n")?;
+    context.git_add(".");
+    context.git_commit("first");
+    let first = rev_parse()?;
+
+    cwd.child("b.txt").write_str("b\n")?;
+    context.git_add(".");
+    context.git_commit("second");
+    let second = rev_parse()?;
+
+    let zero = "0".repeat(40);
+
+    // The remote has the first commit, only the changes since then are checked.
+    cwd.child("refs.txt").write_str(&format!(
+        "refs/heads/master {second} refs/heads/master {first}\n"
+    ))?;
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-push").arg("--push-refs-file").arg("refs.txt"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    show-files...............................................................Passed
+    - hook id: show-files
+    - duration: [TIME]
+      ['b.txt']
+
+    ----- stderr -----
+    "#);
+
+    // A new branch with the whole history, all files are checked.
+    cwd.child("refs.txt").write_str(&format!(
+        "refs/heads/master {second} refs/heads/master {zero}\n"
+    ))?;
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-push").arg("--push-refs-file").arg("refs.txt"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    show-files...............................................................Passed
+    - hook id: show-files
+    - duration: [TIME]
+      ['a.txt', 'b.txt']
+
+    ----- stderr -----
+    "#);
+
+    // Deleting a remote branch, nothing to check.
+    cwd.child("refs.txt")
+        .write_str(&format!("(delete) {zero} refs/heads/master {first}\n"))?;
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-push").arg("--push-refs-file").arg("refs.txt"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Git errors include what was being done and the git stderr.
+#[test]
+fn clone_failure() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: file:///non-existent/repo
+            rev: v1.0.0
+            hooks:
+              - id: some-hook
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Cloning file:///non-existent/repo@v1.0.0
+
+    ----- stderr -----
+    error: Failed to clone `file:///non-existent/repo` at `v1.0.0`
+      caused by: failed to fetch all refs (status: exit status: 128)
+      caused by: fatal: '/non-existent/repo' does not appear to be a git repository
+    fatal: Could not read from remote repository.
+
+    Please make sure you have the correct access rights
+    and the repository exists.
+    ");
+}
+
+/// `--hide-skipped` omits skipped hooks from the output, `--show-skipped` keeps them.
+#[test]
+fn hide_skipped() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("Hello, world!\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: trailing-whitespace
+                name: trailing-whitespace
+                language: system
+                entry: python3 -c "exit(1)"
+              - id: end-of-file-fixer
+                name: fix end of files
+                language: system
+                entry: python3 -c "exit(0)"
+              - id: check-json
+                name: check json
+                language: system
+                entry: python3 -c "exit(1)"
+                types: [json]
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("SKIP", "trailing-whitespace"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trailing-whitespace.....................................................Skipped
+    fix end of files.........................................................Passed
+    check json...........................................(no files to check)Skipped
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().env("SKIP", "trailing-whitespace").arg("--hide-skipped"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    fix end of files.........................................................Passed
+
+    ----- stderr -----
+    ");
+
+    // The last flag wins.
+    cmd_snapshot!(context.filters(), context.run().env("SKIP", "trailing-whitespace").arg("--hide-skipped").arg("--show-skipped"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    trailing-whitespace.....................................................Skipped
+    fix end of files.........................................................Passed
+    check json...........................................(no files to check)Skipped
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// On Windows, an entry resolves to a `.cmd` shim via `PATHEXT`.
+#[cfg(windows)]
+#[test]
+fn entry_cmd_shim() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("bin/my-tool.cmd")
+        .write_str("@echo off\r\necho my-tool %*\r\n")?;
+    cwd.child("file.txt").write_str("Hello, world!\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: my-tool
+                name: my-tool
+                language: system
+                entry: my-tool
+                files: \.txt$
+                verbose: true
+    "});
+    context.git_add(".");
+
+    let path = std::env::join_paths(std::iter::once(cwd.child("bin").to_path_buf()).chain(
+        std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default()),
+    ))?;
+
+    cmd_snapshot!(context.filters(), context.run().env("PATH", path), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    my-tool..................................................................Passed
+    - hook id: my-tool
+    - duration: [TIME]
+      my-tool file.txt
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// Without a terminal, a line is printed for each environment being installed.
+#[test]
+fn install_environment_output() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: node-hook
+                name: node-hook
+                language: node
+                entry: node -e ''
+                always_run: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Preparing local repo node-hook
+    Installing environment for local
+    node-hook................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    // Installed environments are reused.
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    node-hook................................................................Passed
+
+    ----- stderr -----
+    ");
+}
+
+/// `--retry-failed` only runs the hooks that didn't pass in the last run.
+#[test]
+fn retry_failed() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: always-pass
+                name: always-pass
+                language: system
+                entry: python3 -c "exit(0)"
+                always_run: true
+                pass_filenames: false
+              - id: need-marker
+                name: need-marker
+                language: system
+                entry: python3 -c "import os; exit(0 if os.path.exists('marker') else 1)"
+                always_run: true
+                pass_filenames: false
+    "#});
+    context.git_add(".");
+
+    // Without a previous run, all hooks are run.
+    cmd_snapshot!(context.filters(), context.run().arg("--retry-failed"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    always-pass..............................................................Passed
+    need-marker..............................................................Failed
+    - hook id: need-marker
+    - exit code: 1
+
+    ----- stderr -----
+    ");
+
+    context.workdir().child("marker").touch()?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--retry-failed"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    need-marker..............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    // All hooks passed.
+    cmd_snapshot!(context.filters(), context.run().arg("--retry-failed"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    // `--rerun` runs all hooks regardless of the previous results.
+    cmd_snapshot!(context.filters(), context.run().arg("--retry-failed").arg("--rerun"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    always-pass..............................................................Passed
+    need-marker..............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--install-hooks-first` installs all environments up-front, and reports a broken environment
+/// as a setup failure rather than a hook failure.
+#[test]
+fn install_hooks_first() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                always_run: true
+              - id: broken
+                name: broken
+                language: python
+                language_version: python0.0
+                entry: python -c "exit(0)"
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    let mut filters = context.filters();
+    filters.push((r"(?s)\n  caused by: .*", "\n  caused by: [ERROR]\n"));
+
+    // The broken environment is installed even though only `echo` is selected.
+    cmd_snapshot!(filters, context.run().arg("--install-hooks-first").arg("echo"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Preparing local repo broken
+    Installing environment for local
+
+    ----- stderr -----
+    error: Failed to install hook environments
+      caused by: [ERROR]
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().arg("echo"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    ");
+}
+
+/// A `manual` hook selected by id runs on the given files, even if nothing is staged.
+#[test]
+fn manual_stage_with_files() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: format
+                name: format
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:])"
+                files: \.py$
+                stages: [manual]
+                verbose: true
+              - id: lint
+                name: lint
+                language: system
+                entry: python3 -c "exit(1)"
+                stages: [manual]
+    "#});
+    context.git_add(".");
+    context.configure_git_author();
+    context.git_commit("Initial commit");
+
+    let cwd = context.workdir();
+    cwd.child("src/a.py").write_str("print('a')\n")?;
+    cwd.child("src/b.py").write_str("print('b')\n")?;
+    cwd.child("src/c.txt").write_str("c\n")?;
+
+    cmd_snapshot!(context.filters(), context.run()
+        .args(["--hook-stage", "manual", "format"])
+        .args(["--files", "src/a.py", "--files", "src/c.txt"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    format...................................................................Passed
+    - hook id: format
+    - duration: [TIME]
+      ['src/a.py']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--fixer-passes` re-runs the hooks that may modify files until they stop, and still fails.
+#[test]
+fn fixer_passes() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    // `y-to-z` runs first, and has more work to do after `x-to-y` ran.
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: y-to-z
+                name: y-to-z
+                language: system
+                entry: python3 -c "import sys, pathlib; [pathlib.Path(f).write_text(pathlib.Path(f).read_text().replace('y', 'z')) for f in sys.argv[1:]]"
+                files: ^file\.txt$
+              - id: x-to-y
+                name: x-to-y
+                language: system
+                entry: python3 -c "import sys, pathlib; [pathlib.Path(f).write_text(pathlib.Path(f).read_text().replace('x', 'y')) for f in sys.argv[1:]]"
+                files: ^file\.txt$
+    "#});
+    context.git_add(".");
+
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("xy\n")?;
+
+    // Not converged within the pass cap.
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("file.txt").arg("--fixer-passes").arg("2"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    y-to-z...................................................................Failed
+    - hook id: y-to-z
+    - files were modified by this hook
+    x-to-y...................................................................Failed
+    - hook id: x-to-y
+    - files were modified by this hook
+    Files were modified by hooks, re-running the hooks that may modify files (pass 2/2)
+    y-to-z...................................................................Failed
+    - hook id: y-to-z
+    - files were modified by this hook
+    x-to-y...................................................................Passed
+    Files were still modified by hooks after 2 passes
+
+    ----- stderr -----
+    ");
+    assert_eq!(context.read("file.txt"), "zz\n");
+
+    cwd.child("file.txt").write_str("xy\n")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("file.txt").arg("--fixer-passes").arg("5"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    y-to-z...................................................................Failed
+    - hook id: y-to-z
+    - files were modified by this hook
+    x-to-y...................................................................Failed
+    - hook id: x-to-y
+    - files were modified by this hook
+    Files were modified by hooks, re-running the hooks that may modify files (pass 2/5)
+    y-to-z...................................................................Failed
+    - hook id: y-to-z
+    - files were modified by this hook
+    x-to-y...................................................................Passed
+    Files were modified by hooks, re-running the hooks that may modify files (pass 3/5)
+    y-to-z...................................................................Passed
+    x-to-y...................................................................Passed
+
+    ----- stderr -----
+    ");
+    assert_eq!(context.read("file.txt"), "zz\n");
+
+    // `y-to-z` has nothing to do in the first pass, and is re-run for the output of `x-to-y`.
+    cwd.child("file.txt").write_str("x\n")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg("file.txt").arg("--fixer-passes").arg("5"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    y-to-z...................................................................Passed
+    x-to-y...................................................................Failed
+    - hook id: x-to-y
+    - files were modified by this hook
+    Files were modified by hooks, re-running the hooks that may modify files (pass 2/5)
+    y-to-z...................................................................Failed
+    - hook id: y-to-z
+    - files were modified by this hook
+    x-to-y...................................................................Passed
+    Files were modified by hooks, re-running the hooks that may modify files (pass 3/5)
+    y-to-z...................................................................Passed
+    x-to-y...................................................................Passed
+
+    ----- stderr -----
+    ");
+    assert_eq!(context.read("file.txt"), "z\n");
+
+    Ok(())
+}
+
+/// Symlinks are tagged `symlink`, and only passed to hooks that ask for them.
+#[cfg(unix)]
+#[test]
+fn symlink_types() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: files
+                name: files
+                language: system
+                entry: python3 -c "import sys; print(sorted(sys.argv[1:]))"
+                verbose: true
+              - id: symlinks
+                name: symlinks
+                language: system
+                entry: python3 -c "import sys; print(sorted(sys.argv[1:]))"
+                types: [symlink]
+                verbose: true
+    "#});
+
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("Hello\n")?;
+    std::os::unix::fs::symlink("file.txt", cwd.child("link.txt"))?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    files....................................................................Passed
+    - hook id: files
+    - duration: [TIME]
+      ['.pre-commit-config.yaml', 'file.txt']
+    symlinks.................................................................Passed
+    - hook id: symlinks
+    - duration: [TIME]
+      ['link.txt']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// Files passed several times are passed to hooks once, sorted by path.
+#[test]
+fn files_dedup_and_sorted() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: args
+                name: args
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:])"
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    let cwd = context.workdir();
+    cwd.child("b.txt").write_str("b\n")?;
+    cwd.child("a.txt").write_str("a\n")?;
+    cwd.child("dir/c.txt").write_str("c\n")?;
+
+    cmd_snapshot!(context.filters(), context.run()
+        .args(["--files", "dir/c.txt", "--files", "b.txt", "--files", "a.txt"])
+        .args(["--files", "b.txt", "--files", "./a.txt"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    args.....................................................................Passed
+    - hook id: args
+    - duration: [TIME]
+      ['a.txt', 'b.txt', 'dir/c.txt']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `exclude` can be a list of patterns, at both the config and the hook level.
+#[test]
+fn exclude_list() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        exclude:
+          - ^vendor/
+          - \.lock$
+        repos:
+          - repo: local
+            hooks:
+              - id: args
+                name: args
+                language: system
+                entry: python3 -c "import sys; print(sorted(sys.argv[1:]))"
+                exclude: [^docs/, ^\.pre-commit]
+                verbose: true
+    "#});
+
+    let cwd = context.workdir();
+    cwd.child("main.py").touch()?;
+    cwd.child("uv.lock").touch()?;
+    cwd.child("vendor/lib.py").touch()?;
+    cwd.child("docs/index.md").touch()?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    args.....................................................................Passed
+    - hook id: args
+    - duration: [TIME]
+      ['main.py']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// With `--no-fail-on-modified`, a hook that modifies files but exits 0 passes.
+#[test]
+fn no_fail_on_modified() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: system
+                entry: python3 -c "import sys, pathlib; [pathlib.Path(f).write_text(pathlib.Path(f).read_text().upper()) for f in sys.argv[1:]]"
+                files: \.txt$
+              - id: failing
+                name: failing
+                language: system
+                entry: python3 -c "import sys, pathlib; [pathlib.Path(f).write_text('fixed\n') for f in sys.argv[1:]]; sys.exit(1)"
+                files: ^other\.txt$
+    "#});
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("hello\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--no-fail-on-modified"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    fixer....................................................................Passed
+    failing..............................................(no files to check)Skipped
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(context.read("file.txt"), @"HELLO");
+
+    // A hook that also exits with an error still fails.
+    cwd.child("file.txt").write_str("hello\n")?;
+    cwd.child("other.txt").write_str("other\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--no-fail-on-modified"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Passed
+    failing..................................................................Failed
+    - hook id: failing
+    - exit code: 1
+    - files were modified by this hook
+
+    ----- stderr -----
+    ");
+
+    // The last flag wins.
+    cwd.child("file.txt").write_str("hello\n")?;
+    cwd.child("other.txt").write_str("other\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--no-fail-on-modified").arg("--fail-on-modified").arg("fixer"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--show-diff-on-failure` prints the changes of the hooks that modified files, only if asked.
+#[test]
+fn show_diff_on_failure() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: system
+                entry: python3 -c "import sys, pathlib; [pathlib.Path(f).write_text(pathlib.Path(f).read_text().upper()) for f in sys.argv[1:]]"
+                files: \.txt$
+    "#});
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("hello\n")?;
+    context.git_add(".");
+
+    let mut filters = context.filters();
+    filters.push((r"index \w+\.\.\w+ \d+", "index [HASH]..[HASH] [MODE]"));
+
+    cmd_snapshot!(filters.clone(), context.run().args(["--show-diff-on-failure", "--color", "never"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+    All changes made by hooks:
+    diff --git a/file.txt b/file.txt
+    index [HASH]..[HASH] [MODE]
+    --- a/file.txt
+    +++ b/file.txt
+    @@ -1 +1 @@
+    -hello
+    +HELLO
+
+    ----- stderr -----
+    ");
+
+    // Without the flag, no diff is shown.
+    cwd.child("file.txt").write_str("hello again\n")?;
+    context.git_add(".");
+    cmd_snapshot!(filters.clone(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+
+    ----- stderr -----
+    ");
+
+    // A failing hook that doesn't modify files shows no diff.
+    context.git_add(".");
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: fail
+                name: fail
+                language: fail
+                entry: always fails
+                files: \.txt$
+    "});
+    context.git_add(".");
+    cmd_snapshot!(filters, context.run().args(["--show-diff-on-failure", "--color", "never"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      always fails
+
+      file.txt
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--diff-context` sets the number of context lines of `--show-diff-on-failure`.
+#[test]
+fn show_diff_context() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fixer
+                name: fixer
+                language: system
+                entry: python3 -c "import sys, pathlib; [pathlib.Path(f).write_text(pathlib.Path(f).read_text().replace('5', 'five')) for f in sys.argv[1:]]"
+                files: \.txt$
+    "#});
+    let cwd = context.workdir();
+    cwd.child("file.txt")
+        .write_str("1\n2\n3\n4\n5\n6\n7\n8\n9\n")?;
+    context.git_add(".");
+
+    let mut filters = context.filters();
+    filters.push((r"index \w+\.\.\w+ \d+", "index [HASH]..[HASH] [MODE]"));
+
+    cmd_snapshot!(filters, context.run()
+        .args(["--show-diff-on-failure", "--diff-context", "1", "--diff-color", "never"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fixer....................................................................Failed
+    - hook id: fixer
+    - files were modified by this hook
+    All changes made by hooks:
+    diff --git a/file.txt b/file.txt
+    index [HASH]..[HASH] [MODE]
+    --- a/file.txt
+    +++ b/file.txt
+    @@ -4,3 +4,3 @@
+     4
+    -5
+    +five
+     6
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A hook with `verbose: true` still shows its output under `--quiet`, other hooks stay quiet.
+#[test]
+fn quiet_with_verbose_hook() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: quiet
+                name: quiet
+                language: system
+                entry: echo "quiet hook output"
+                pass_filenames: false
+              - id: loud
+                name: loud
+                language: system
+                entry: echo "loud hook output"
+                pass_filenames: false
+                verbose: true
+              - id: failing
+                name: failing
+                language: system
+                entry: python3 -c "import sys; print('failing hook output'); sys.exit(1)"
+                pass_filenames: false
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--quiet"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    loud.....................................................................Passed
+    - hook id: loud
+    - duration: [TIME]
+      loud hook output
+
+    ----- stderr -----
+    ");
+}
+
+/// `files` and `exclude` patterns match paths relative to the repo root, regardless of cwd.
+#[test]
+fn subdirectory_files_pattern() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: args
+                name: args
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:])"
+                files: ^foo/bar/
+                exclude: ^foo/bar/excluded\.txt$
+                verbose: true
+    "#});
+
+    let cwd = context.workdir();
+    let child = cwd.child("foo/bar");
+    child.create_dir_all()?;
+    cwd.child("a.txt").write_str("a\n")?;
+    child.child("a.txt").write_str("a\n")?;
+    child.child("b.txt").write_str("b\n")?;
+    child.child("excluded.txt").write_str("excluded\n")?;
+    context.git_add(".");
+
+    // Staged files are matched by their root-relative paths.
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    args.....................................................................Passed
+    - hook id: args
+    - duration: [TIME]
+      ['foo/bar/a.txt', 'foo/bar/b.txt']
+
+    ----- stderr -----
+    ");
+
+    // Paths given on the command line are relative to cwd, and normalized to root-relative paths.
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child)
+        .args(["--files", "a.txt", "--files", "./b.txt", "--files", "../bar/excluded.txt"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    args.....................................................................Passed
+    - hook id: args
+    - duration: [TIME]
+      ['foo/bar/a.txt', 'foo/bar/b.txt']
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(&child).arg("--all-files"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    args.....................................................................Passed
+    - hook id: args
+    - duration: [TIME]
+      ['foo/bar/a.txt', 'foo/bar/b.txt']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--output-format github` reports failed hooks, and their `file:line` diagnostics,
+/// as GitHub Actions annotations.
+#[test]
+fn output_format_github() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: python3 lint
+                files: \.py$
+              - id: ok
+                name: ok
+                language: system
+                entry: "true"
+    "#});
+
+    let cwd = context.workdir();
+    cwd.child("a.py").write_str("a = 1\n")?;
+    cwd.child("lint").write_str(indoc::indoc! {r"
+        import sys
+        print('a.py:3:5: E001 bad, really')
+        print('other.py:1: not a file passed to the hook')
+        print('summary: 1 error')
+        sys.exit(1)
+    "})?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--output-format").arg("github"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    lint.....................................................................Failed
+    - hook id: lint
+    - exit code: 1
+      a.py:3:5: E001 bad, really
+      other.py:1: not a file passed to the hook
+      summary: 1 error
+    ::error title=lint::Hook `lint` failed: exit code 1
+    ::error file=a.py,line=3,col=5,title=lint::E001 bad, really
+    ok.......................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--output-format json` prints the results of the hooks as JSON instead.
+#[test]
+fn output_format_json() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fail
+                name: fail
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:]); sys.exit(2)"
+                files: \.py$
+                verbose: true
+              - id: fix
+                name: fix
+                language: system
+                entry: python3 -c "import sys; open(sys.argv[1], 'a').write('b = 2\\n')"
+                files: \.py$
+              - id: no-files
+                name: no-files
+                language: system
+                entry: "true"
+                files: \.rs$
+    "#});
+
+    let cwd = context.workdir();
+    cwd.child("a.py").write_str("a = 1\n")?;
+    context.git_add(".");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r#""duration_ms": \d+"#, r#""duration_ms": [DURATION]"#)])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters, context.run().arg("--output-format").arg("json"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    [
+      {
+        "id": "fail",
+        "name": "fail",
+        "stage": "pre-commit",
+        "status": "failed",
+        "files": [
+          "a.py"
+        ],
+        "exit_code": 2,
+        "duration_ms": [DURATION],
+        "files_modified": false,
+        "output": "['a.py']/n"
+      },
+      {
+        "id": "fix",
+        "name": "fix",
+        "stage": "pre-commit",
+        "status": "failed",
+        "files": [
+          "a.py"
+        ],
+        "exit_code": 0,
+        "duration_ms": [DURATION],
+        "files_modified": true,
+        "output": ""
+      },
+      {
+        "id": "no-files",
+        "name": "no-files",
+        "stage": "pre-commit",
+        "status": "skipped",
+        "files": [],
+        "exit_code": null,
+        "duration_ms": [DURATION],
+        "files_modified": false,
+        "output": ""
+      }
+    ]
+
+    ----- stderr -----
+    "#);
+
+    Ok(())
+}
+
+/// `--summary-only` prints only the counts of the hook results and the failed hooks.
+#[test]
+fn summary_only() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: pass
+                name: pass
+                language: system
+                entry: "true"
+                always_run: true
+                verbose: true
+              - id: fail-1
+                name: fail-1
+                language: fail
+                entry: first failure
+                always_run: true
+              - id: no-files
+                name: no-files
+                language: system
+                entry: "true"
+                files: \.rs$
+              - id: fail-2
+                name: fail-2
+                language: fail
+                entry: second failure
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--summary-only"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Failed: 1 passed, 2 failed, 1 skipped
+    Failed hooks: fail-1, fail-2
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--summary-only").arg("pass"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Passed: 1 passed, 0 failed, 0 skipped
+
+    ----- stderr -----
+    ");
+}
+
+/// `--bail-after N` stops running hooks once N hooks have failed.
+#[test]
+fn bail_after() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: fail-1
+                name: fail-1
+                language: fail
+                entry: first failure
+                always_run: true
+              - id: pass
+                name: pass
+                language: system
+                entry: 'true'
+                always_run: true
+              - id: fail-2
+                name: fail-2
+                language: fail
+                entry: second failure
+                always_run: true
+              - id: fail-3
+                name: fail-3
+                language: fail
+                entry: third failure
+                always_run: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--bail-after").arg("2"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail-1...................................................................Failed
+    - hook id: fail-1
+    - exit code: 1
+      first failure
+
+      .pre-commit-config.yaml
+    pass.....................................................................Passed
+    fail-2...................................................................Failed
+    - hook id: fail-2
+    - exit code: 1
+      second failure
+
+      .pre-commit-config.yaml
+    Stopped after 2 failed hooks
+
+    ----- stderr -----
+    ");
+
+    // Zero is not a valid limit.
+    cmd_snapshot!(context.filters(), context.run().arg("--bail-after").arg("0"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: invalid value '0' for '--bail-after <N>': number would be zero for non-zero type
+
+    For more information, try '--help'.
+    ");
+}
+
+/// With `--keep-going-per-repo`, a failed `fail_fast` hook only stops the hooks of its own repo.
+#[test]
+fn keep_going_per_repo() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        fail_fast: true
+        repos:
+          - repo: local
+            hooks:
+              - id: fail
+                name: fail
+                language: fail
+                entry: failed
+                always_run: true
+              - id: same-repo
+                name: same-repo
+                language: system
+                entry: 'true'
+                always_run: true
+          - repo: local
+            hooks:
+              - id: other-repo
+                name: other-repo
+                language: system
+                entry: 'true'
+                always_run: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--keep-going-per-repo"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      failed
+
+      .pre-commit-config.yaml
+    other-repo...............................................................Passed
+
+    ----- stderr -----
+    ");
+
+    // Without the flag, the top-level `fail_fast` stops the whole run.
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+      failed
+
+      .pre-commit-config.yaml
+
+    ----- stderr -----
+    ");
+}
+
+/// `--check-only` sets `PRE_COMMIT_CHECK_ONLY=1` for the hooks.
+#[test]
+fn check_only() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: check-only
+                name: check-only
+                language: system
+                entry: sh -c 'echo "PRE_COMMIT_CHECK_ONLY=${PRE_COMMIT_CHECK_ONLY:-unset}"'
+                always_run: true
+                pass_filenames: false
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--check-only"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    check-only...............................................................Passed
+    - hook id: check-only
+    - duration: [TIME]
+      PRE_COMMIT_CHECK_ONLY=1
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    check-only...............................................................Passed
+    - hook id: check-only
+    - duration: [TIME]
+      PRE_COMMIT_CHECK_ONLY=unset
+
+    ----- stderr -----
+    ");
+}
+
+/// `-C` runs against the repo in another directory, resolving relative paths from there.
+#[test]
+fn working_dir() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                verbose: true
+    "});
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.workdir().child("other.txt").write_str("Hello\n")?;
+    context.git_add(".");
+
+    let outside = context.home_dir().child("outside");
+    outside.create_dir_all()?;
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(&outside).arg("-C").arg(&**context.workdir()), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      .pre-commit-config.yaml file.txt other.txt
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(&outside).arg("-C").arg(&**context.workdir()).arg("--files").arg("file.txt"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      file.txt
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().arg("-C").arg("non-existent"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to change directory to `non-existent`
+      caused by: No such file or directory (os error 2)
+    ");
+
+    Ok(())
+}
+
+/// In a shallow clone without the base ref, `--from-ref` suggests fetching it.
+#[test]
+fn shallow_clone_missing_ref() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+    context.git_commit("Initial commit");
+    Command::new("git")
+        .arg("checkout")
+        .arg("-b")
+        .arg("feature")
+        .current_dir(context.workdir())
+        .assert()
+        .success();
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add(".");
+    context.git_commit("Add file");
+
+    // A CI-like checkout of only the tip of the feature branch.
+    let clone = context.home_dir().child("clone");
+    Command::new("git")
+        .arg("clone")
+        .arg("--depth=1")
+        .arg("--single-branch")
+        .arg("--branch=feature")
+        .arg(format!("file://{}", context.workdir().display()))
+        .arg(&*clone)
+        .assert()
+        .success();
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(&clone).arg("--from-ref").arg("origin/master").arg("--to-ref").arg("HEAD"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Ref `origin/master` is not found in this shallow clone, fetch it first, or fetch the full history with `git fetch --unshallow`
+    ");
+
+    Ok(())
+}
+
+/// A `repo_wide` hook runs exactly once, at the repo root and without filenames.
+#[test]
+fn repo_wide() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: repo-wide
+                name: repo-wide
+                language: system
+                entry: sh -c 'echo "invoked in $(pwd) with $# args"' --
+                files: \.txt$
+                repo_wide: true
+                verbose: true
+    "#});
+    let sub = context.workdir().child("sub");
+    for i in 0..20 {
+        sub.child(format!("file{i}.txt")).write_str("Hello\n")?;
+    }
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().current_dir(&sub), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    repo-wide................................................................Passed
+    - hook id: repo-wide
+    - duration: [TIME]
+      invoked in [TEMP_DIR]/ with 0 args
+
+    ----- stderr -----
+    ");
+
+    // It still runs when no files match.
+    cmd_snapshot!(context.filters(), context.run().arg("--files").arg(".pre-commit-config.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    repo-wide................................................................Passed
+    - hook id: repo-wide
+    - duration: [TIME]
+      invoked in [TEMP_DIR]/ with 0 args
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--only-changed-hooks` only runs the hooks changed since the last commit of the config.
+#[test]
+fn only_changed_hooks() {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    let config = |args: &str| {
+        indoc::formatdoc! {r#"
+            repos:
+              - repo: local
+                hooks:
+                  - id: first
+                    name: first
+                    language: system
+                    entry: echo first
+                    pass_filenames: false
+                    verbose: true
+                  - id: second
+                    name: second
+                    language: system
+                    entry: echo second
+                    args: [{args}]
+                    pass_filenames: false
+                    verbose: true
+        "#}
+    };
+
+    context.write_pre_commit_config(&config("--old"));
+    context.git_add(".");
+
+    // Not committed yet, so all hooks are changed.
+    cmd_snapshot!(context.filters(), context.run().arg("--only-changed-hooks"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    first....................................................................Passed
+    - hook id: first
+    - duration: [TIME]
+      first
+    second...................................................................Passed
+    - hook id: second
+    - duration: [TIME]
+      second --old
+
+    ----- stderr -----
+    ");
+
+    context.git_commit("Initial commit");
+    context.write_pre_commit_config(&config("--new"));
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--only-changed-hooks"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    second...................................................................Passed
+    - hook id: second
+    - duration: [TIME]
+      second --new
+
+    ----- stderr -----
+    ");
+
+    // A top-level setting applies to all hooks.
+    context.write_pre_commit_config(&format!("fail_fast: true\n{}", config("--old")));
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--only-changed-hooks"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    first....................................................................Passed
+    - hook id: first
+    - duration: [TIME]
+      first
+    second...................................................................Passed
+    - hook id: second
+    - duration: [TIME]
+      second --old
+
+    ----- stderr -----
+    ");
+}
+
+/// `--only-changed-hooks` tells apart hooks with the same id by their position in the config.
+#[test]
+fn only_changed_hooks_same_id() {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    let config = |args: &str| {
+        indoc::formatdoc! {r#"
+            repos:
+              - repo: local
+                hooks:
+                  - id: echo
+                    name: echo
+                    language: system
+                    entry: echo
+                    args: [first]
+                    pass_filenames: false
+                    verbose: true
+                  - id: echo
+                    name: echo
+                    language: system
+                    entry: echo
+                    args: [second, {args}]
+                    pass_filenames: false
+                    verbose: true
+        "#}
+    };
+
+    context.write_pre_commit_config(&config("--old"));
+    context.git_add(".");
+    context.git_commit("Initial commit");
+
+    context.write_pre_commit_config(&config("--new"));
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--only-changed-hooks"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      second --new
+
+    ----- stderr -----
+    ");
+}
+
+/// `--profile` writes the timings of the run as JSON.
+#[test]
+fn profile() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: first
+                name: first
+                language: system
+                entry: 'true'
+              - id: second
+                name: second
+                language: system
+                entry: 'true'
+                pass_filenames: false
+    "});
+    let cwd = context.workdir();
+    cwd.child("a.txt").write_str("a\n")?;
+    cwd.child("b.txt").write_str("b\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--profile").arg("profile.json"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    first....................................................................Passed
+    second...................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    let profile: serde_json::Value = serde_json::from_str(&context.read("profile.json"))?;
+    for section in ["total", "clone", "install", "files", "hooks"] {
+        assert!(
+            profile.get(section).is_some(),
+            "missing `{section}` in {profile}"
+        );
+    }
+    assert_eq!(profile["files"]["count"], 3);
+    let hooks = profile["hooks"].as_array().unwrap();
+    assert_eq!(hooks.len(), 2);
+    assert_eq!(hooks[0]["id"], "first");
+    assert_eq!(hooks[1]["id"], "second");
+    let batch_files = |hook: &serde_json::Value| {
+        hook["batches"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|batch| batch["files"].as_u64().unwrap())
+            .sum::<u64>()
+    };
+    assert_eq!(batch_files(&hooks[0]), 3);
+    assert_eq!(batch_files(&hooks[1]), 0);
+
+    Ok(())
+}
+
+/// `--experimental-no-git` runs hooks in a directory that is not a git repo.
+#[test]
+fn no_git() -> Result<()> {
+    let context = TestContext::new();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                verbose: true
+    "});
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context
+        .workdir()
+        .child("src/main.py")
+        .write_str("print()\n")?;
+    context
+        .workdir()
+        .child("src/lib.py")
+        .write_str("print()\n")?;
+
+    cmd_snapshot!(context.filters(), context.run().arg("--experimental-no-git"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      .pre-commit-config.yaml file.txt src/lib.py src/main.py
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--experimental-no-git").arg("--files").arg("src"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      src/lib.py src/main.py
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A hook `env` sets environment variables for the hook, expanding `${VAR}` references.
+#[test]
+fn hook_env() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: env
+                name: env
+                language: system
+                entry: sh -c 'echo "$GREETING, $TARGET"'
+                env:
+                  GREETING: Hello
+                  TARGET: ${PREFLIGIT_TEST_TARGET}!
+                always_run: true
+                pass_filenames: false
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("PREFLIGIT_TEST_TARGET", "world"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    env......................................................................Passed
+    - hook id: env
+    - duration: [TIME]
+      Hello, world!
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    env......................................................................Failed
+    - hook id: env
+    - error: Hook `env` references undefined environment variable `PREFLIGIT_TEST_TARGET` in env `TARGET`
+
+    ----- stderr -----
+    ");
+}
+
+/// The `env` of a hook is expanded when it runs, so an undefined variable doesn't fail the run if
+/// the hook is skipped or not selected.
+#[test]
+fn hook_env_not_run() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: env
+                name: env
+                language: system
+                entry: sh -c 'echo "$TARGET"'
+                env:
+                  TARGET: ${PREFLIGIT_TEST_TARGET}
+                always_run: true
+                pass_filenames: false
+              - id: other
+                name: other
+                language: system
+                entry: echo other
+                always_run: true
+                pass_filenames: false
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().env("SKIP", "env"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    env.....................................................................Skipped
+    other....................................................................Passed
+    - hook id: other
+    - duration: [TIME]
+      other
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().arg("other"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    other....................................................................Passed
+    - hook id: other
+    - duration: [TIME]
+      other
+
+    ----- stderr -----
+    ");
+}
+
+/// Hooks can share fields with YAML merge keys.
+#[test]
+fn yaml_merge_key() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - &common
+                id: first
+                name: first
+                language: system
+                entry: echo
+                args: [shared]
+                always_run: true
+                pass_filenames: false
+                verbose: true
+              - <<: *common
+                id: second
+                name: second
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    first....................................................................Passed
+    - hook id: first
+    - duration: [TIME]
+      shared
+    second...................................................................Passed
+    - hook id: second
+    - duration: [TIME]
+      shared
+
+    ----- stderr -----
+    ");
+}
+
+/// Legacy stage names still work, with a warning.
+#[test]
+fn deprecated_stage_alias() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: commit
+                name: commit
+                language: system
+                entry: echo
+                stages: [commit]
+                always_run: true
+              - id: push
+                name: push
+                language: system
+                entry: echo
+                stages: [push, commit]
+                always_run: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "pre-commit"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    commit...................................................................Passed
+    push.....................................................................Passed
+
+    ----- stderr -----
+    warning: Stage `commit` is deprecated, use `pre-commit` instead
+    warning: Stage `push` is deprecated, use `pre-push` instead
+    ");
+}
+
+/// Hooks of the `manual` stage are skipped unless selected with `--hook-stage`.
+#[test]
+fn manual_stage_skipped_by_default() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: default
+                name: default
+                language: system
+                entry: echo
+                always_run: true
+              - id: manual
+                name: manual
+                language: system
+                entry: echo
+                stages: [manual]
+                always_run: true
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    default..................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "manual"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    default..................................................................Passed
+    manual...................................................................Passed
+
+    ----- stderr -----
+    ");
+}
+
+/// Stages with git hook arguments require them when run manually, unless running on all files.
+#[test]
+fn stage_required_args() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: checkout
+                name: checkout
+                language: system
+                entry: echo
+                always_run: true
+                stages: [post-checkout]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "post-checkout"]), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: `--hook-stage post-checkout` requires `--from-ref`, `--to-ref`, `--checkout-type`, or `--all-files`
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "post-checkout", "--from-ref", "HEAD", "--to-ref", "HEAD", "--checkout-type", "1"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    checkout.................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "post-checkout", "--all-files"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    checkout.................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "post-rewrite"]), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: `--hook-stage post-rewrite` requires `--rewrite-command`, or `--all-files`
+    ");
+}
+
+/// Hooks without `stages` run at the `default_stages`.
+#[test]
+fn default_stages() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        default_stages: [pre-push]
+        repos:
+          - repo: local
+            hooks:
+              - id: default
+                name: default
+                language: system
+                entry: echo
+                always_run: true
+              - id: explicit
+                name: explicit
+                language: system
+                entry: echo
+                always_run: true
+                stages: [pre-commit]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    explicit.................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--hook-stage", "pre-push"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    default..................................................................Passed
+
+    ----- stderr -----
+    ");
+}
+
+/// Environment variables are expanded in repo URLs when fetching, and credentials are masked in the output.
+#[test]
+fn repo_url_token() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: http://${PREFLIGIT_TEST_TOKEN}@127.0.0.1:9/hooks.git
+            rev: v1.0.0
+            hooks:
+              - id: hook
+    "});
+    context.git_add(".");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(r"after \d+ ms", "after [TIME] ms")])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters, context.run().env("PREFLIGIT_TEST_TOKEN", "secret-token"), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Cloning http://***@127.0.0.1:9/hooks.git@v1.0.0
+
+    ----- stderr -----
+    error: Failed to clone `http://***@127.0.0.1:9/hooks.git` at `v1.0.0`
+      caused by: failed to fetch all refs (status: exit status: 128)
+      caused by: fatal: unable to access 'http://127.0.0.1:9/hooks.git/': Failed to connect to 127.0.0.1 port 9 after [TIME] ms: Couldn't connect to server
+    ");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+    Cloning http://***@127.0.0.1:9/hooks.git@v1.0.0
+
+    ----- stderr -----
+    error: Failed to clone `http://***@127.0.0.1:9/hooks.git` at `v1.0.0`
+      caused by: Repo `http://${PREFLIGIT_TEST_TOKEN}@127.0.0.1:9/hooks.git` references undefined environment variable `PREFLIGIT_TEST_TOKEN`
+    ");
+
+    // The git commands logged with `-vv` mask the token too.
+    let output = context
+        .run()
+        .arg("-vv")
+        .env("PREFLIGIT_TEST_TOKEN", "secret-token")
+        .output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("http://***@127.0.0.1:9/hooks.git"));
+    assert!(!stderr.contains("secret-token"));
+
+    Ok(())
+}
+
+/// The unexpanded repo URL is what's stored, so the clone never contains the secret.
+#[test]
+fn repo_url_token_not_stored() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.create_hook_repo(
+        "hook-repo",
+        indoc::indoc! {r"
+            - id: echo
+              name: echo
+              language: system
+              entry: echo
+        "},
+    );
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}/${{PREFLIGIT_TEST_REPO}}
+            rev: master
+            hooks:
+              - id: echo
+                always_run: true
+    ", context.workdir().display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    cmd_snapshot!(context.filters(), context.run().env("PREFLIGIT_TEST_REPO", "hook-repo"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/${PREFLIGIT_TEST_REPO}@master
+    echo.....................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    let clone = fs_err::read_dir(context.home_dir().path())?
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_name().to_string_lossy().starts_with("repo"))
+        .expect("the repo should be cloned")
+        .path();
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(&clone)
+        .output()?;
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        format!(
+            "file://{}/${{PREFLIGIT_TEST_REPO}}",
+            context.workdir().display()
+        )
+    );
+
+    Ok(())
+}
+
+/// Fail before running any hooks if a newer pre-commit version is required.
+#[test]
+fn minimum_pre_commit_version() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        minimum_pre_commit_version: '999.0.0'
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: `.pre-commit-config.yaml` requires pre-commit version `999.0.0`, but prefligit is compatible with pre-commit version `4.0.1`
+    ");
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: echo
+                name: echo
+                language: system
+                entry: echo
+                minimum_pre_commit_version: '999.0.0'
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Hook `echo` requires pre-commit version `999.0.0`, but prefligit is compatible with pre-commit version `4.0.1`
+    ");
+}
+
+/// Pushing a new branch diffs against its merge base with the remote default branch.
+#[test]
+fn pre_push_new_branch() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: show-files
+                name: show-files
+                language: system
+                entry: python3 -c 'import sys; print(sorted(sys.argv[1:]))'
+                files: \.txt$
+                verbose: true
+    "});
+
+    let git = |args: &[&str]| -> Result<()> {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(context.workdir())
+            .output()?
+            .status;
+        anyhow::ensure!(status.success(), "git {args:?} failed");
+        Ok(())
+    };
+
+    let cwd = context.workdir();
+    cwd.child("a.txt").write_str("a\n")?;
+    context.git_add(".");
+    context.git_commit("first");
+
+    let remote = context.home_dir().child("remote.git");
+    let remote = remote.to_str().unwrap();
+    git(&["init", "--bare", remote])?;
+    git(&["remote", "add", "origin", remote])?;
+    git(&["push", "origin", "HEAD:main"])?;
+    git(&["remote", "set-head", "origin", "main"])?;
+
+    git(&["checkout", "-b", "feature"])?;
+    cwd.child("b.txt").write_str("b\n")?;
+    context.git_add(".");
+    context.git_commit("second");
+
+    let zero = "0".repeat(40);
+
+    // Only the commits since the remote default branch are checked.
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-push").arg("--from-ref").arg(&zero).arg("--to-ref").arg("HEAD"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    show-files...............................................................Passed
+    - hook id: show-files
+    - duration: [TIME]
+      ['b.txt']
+
+    ----- stderr -----
+    ");
+
+    // Without a remote default branch, all files are checked.
+    git(&["remote", "remove", "origin"])?;
+    cmd_snapshot!(context.filters(), context.run().arg("--hook-stage").arg("pre-push").arg("--from-ref").arg(&zero).arg("--to-ref").arg("HEAD"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    show-files...............................................................Passed
+    - hook id: show-files
+    - duration: [TIME]
+      ['a.txt', 'b.txt']
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `repo: meta` hooks run in-process, without cloning anything.
+#[test]
+fn meta_hooks() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("a.txt").write_str("a\n")?;
+    cwd.child("b.py").write_str("b\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        exclude: ^nonexistent/
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+              - id: check-useless-excludes
+              - id: check-hooks-apply
+          - repo: local
+            hooks:
+              - id: python
+                name: python
+                language: system
+                entry: echo
+                types: [python]
+                exclude: ^b\.py$
+              - id: rust
+                name: rust
+                language: system
+                entry: echo
+                files: \.rs$
+                exclude: ^target/
+              - id: markdown
+                name: markdown
+                language: system
+                entry: echo
+                files: \.md$
+                stages: [manual]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      .pre-commit-config.yaml
+      a.txt
+      b.py
+    Check for useless excludes...............................................Failed
+    - hook id: check-useless-excludes
+    - exit code: 1
+      The global exclude pattern `^nonexistent/` does not match any files
+      The exclude pattern `^target/` for `rust` does not match any files
+    Check hooks apply to the repository......................................Failed
+    - hook id: check-hooks-apply
+    - exit code: 1
+      python does not apply to this repository
+      rust does not apply to this repository
+      markdown does not apply to this repository
+    python...............................................(no files to check)Skipped
+    rust.................................................(no files to check)Skipped
+
+    ----- stderr -----
+    ");
+
+    // All the hooks of the config are checked, not only the hooks selected for the run.
+    cmd_snapshot!(context.filters(), context.run().arg("check-hooks-apply"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Check hooks apply to the repository......................................Failed
+    - hook id: check-hooks-apply
+    - exit code: 1
+      python does not apply to this repository
+      rust does not apply to this repository
+      markdown does not apply to this repository
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--print-resolved-revs` prints the commit each remote `rev` resolves to, without running hooks.
+#[test]
+fn print_resolved_revs() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.create_hook_repo(
+        "hook-repo",
+        indoc::indoc! {r"
+            - id: fail
+              name: fail
+              language: fail
+              entry: should not run
+        "},
+    );
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(&repo)
+        .output()?;
+    let sha = String::from_utf8(output.stdout)?.trim().to_string();
+
+    context.write_pre_commit_config(&indoc::formatdoc! {r"
+        repos:
+          - repo: file://{}
+            rev: master
+            hooks:
+              - id: fail
+                always_run: true
+    ", repo.display()});
+    context.git_add(".pre-commit-config.yaml");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([(sha.as_str(), "[SHA]")])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters.clone(), context.run().arg("--print-resolved-revs"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/hook-repo@master
+    file://[TEMP_DIR]/hook-repo: master -> [SHA]
+
+    ----- stderr -----
+    ");
+
+    // The cached clone is used the second time.
+    cmd_snapshot!(filters.clone(), context.run().arg("--print-resolved-revs"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    file://[TEMP_DIR]/hook-repo: master -> [SHA]
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--explain` traces how a hook is selected, without running it.
+#[test]
+fn explain() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("main.py").write_str("print(1)\n")?;
+    cwd.child("test_main.py").write_str("print(2)\n")?;
+    cwd.child("setup.sh").write_str("echo\n")?;
+    cwd.child("README.md").write_str("# Readme\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: lint
+                name: lint
+                language: system
+                entry: 'false'
+                args: [--check]
+                files: \.(py|sh)$
+                exclude: ^test_
+                types: [python]
+              - id: push-only
+                name: push-only
+                language: system
+                entry: 'false'
+                stages: [pre-push]
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--explain").arg("lint"), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Hook `lint` (lint) from local
+    - stage: selected for `pre-commit`
+    - candidates: 5 files: .pre-commit-config.yaml, README.md, main.py, setup.sh, test_main.py
+    - files (regex `\.(py|sh)$`): 3 files: main.py, setup.sh, test_main.py
+    - exclude (regex `^test_`): 2 files: main.py, setup.sh
+    - types ["python"], types_or [], exclude_types []: 1 file: main.py
+    - pass_filenames: the matched files are passed
+    - result: runs `false --check main.py` with the `system` language
+
+    ----- stderr -----
+    "#);
+
+    cmd_snapshot!(context.filters(), context.run().arg("--explain").arg("push-only"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Hook `push-only` (push-only) from local
+    - stage: not selected, the hook runs at `pre-push`, not at `pre-commit`
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// With `files_relative`, the patterns of a subdirectory config are relative to its directory.
+#[test]
+fn files_relative() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("src/root.txt").write_str("root")?;
+    cwd.child("sub/src/a.txt").write_str("a")?;
+    cwd.child("sub/src/b.txt").write_str("b")?;
+    cwd.child("sub/docs/c.txt").write_str("c")?;
+    cwd.child("sub/.pre-commit-config.yaml")
+        .write_str(indoc::indoc! {r"
+            files_relative: true
+            exclude: ^src/b
+            repos:
+              - repo: local
+                hooks:
+                  - id: echo
+                    name: echo
+                    language: system
+                    entry: echo
+                    files: ^src/
+                    verbose: true
+        "})?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").arg("-c").arg("sub/.pre-commit-config.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      sub/src/a.txt
+
+    ----- stderr -----
+    ");
+
+    // Without it, the patterns are relative to the repository root.
+    cwd.child("sub/.pre-commit-config.yaml")
+        .write_str(indoc::indoc! {r"
+            exclude: ^src/b
+            repos:
+              - repo: local
+                hooks:
+                  - id: echo
+                    name: echo
+                    language: system
+                    entry: echo
+                    files: ^src/
+                    verbose: true
+        "})?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--all-files").arg("-c").arg("sub/.pre-commit-config.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    echo.....................................................................Passed
+    - hook id: echo
+    - duration: [TIME]
+      src/root.txt
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// Consecutive `read_only` hooks run concurrently, with their output in declaration order.
+#[test]
+fn read_only_hooks_run_concurrently() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: slow-1
+                name: slow-1
+                language: system
+                entry: python3 -c "import time; time.sleep(1); print('slow-1')"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+                verbose: true
+              - id: slow-2
+                name: slow-2
+                language: system
+                entry: python3 -c "import time; time.sleep(1); print('slow-2')"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+                verbose: true
+              - id: slow-3
+                name: slow-3
+                language: system
+                entry: python3 -c "import time; time.sleep(1); print('slow-3')"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+                verbose: true
+              - id: slow-4
+                name: slow-4
+                language: system
+                entry: python3 -c "import time; time.sleep(0.5); print('slow-4')"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+                verbose: true
+    "#});
+    context.git_add(".");
+
+    let start = std::time::Instant::now();
+    cmd_snapshot!(context.filters(), context.run().args(["--jobs", "4"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    slow-1...................................................................Passed
+    - hook id: slow-1
+    - duration: [TIME]
+      slow-1
+    slow-2...................................................................Passed
+    - hook id: slow-2
+    - duration: [TIME]
+      slow-2
+    slow-3...................................................................Passed
+    - hook id: slow-3
+    - duration: [TIME]
+      slow-3
+    slow-4...................................................................Passed
+    - hook id: slow-4
+    - duration: [TIME]
+      slow-4
+
+    ----- stderr -----
+    ");
+    // Serially, the hooks take 3.5 seconds.
+    assert!(
+        start.elapsed() < std::time::Duration::from_millis(2500),
+        "{:?}",
+        start.elapsed()
+    );
+
+    let start = std::time::Instant::now();
+    context.run().args(["--jobs", "1"]).assert().success();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(3500));
+}
+
+/// A failing `fail_fast` hook stops the concurrent hooks after it from starting.
+#[test]
+fn read_only_hooks_fail_fast() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fail
+                name: fail
+                language: system
+                entry: python3 -c "import sys; sys.exit(1)"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+                fail_fast: true
+              - id: slow
+                name: slow
+                language: system
+                entry: python3 -c "import time; time.sleep(1)"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+              - id: marker
+                name: marker
+                language: system
+                entry: python3 -c "open('marker', 'w')"
+                always_run: true
+                pass_filenames: false
+                read_only: true
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--jobs", "2"]), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+
+    ----- stderr -----
+    ");
+
+    assert!(!context.workdir().child("marker").exists());
+}
+
+/// A `require_serial` hook is invoked once with all its files, even past the batch size limit.
+#[test]
+fn require_serial() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    for i in 0..200 {
+        cwd.child(format!(
+            "a-rather-long-file-name-to-fill-the-command-line-{i}.txt"
+        ))
+        .write_str("")?;
+    }
+
+    // Record the number of files of each invocation, outside the working tree.
+    let config = |require_serial: bool| {
+        indoc::formatdoc! {r#"
+            repos:
+              - repo: local
+                hooks:
+                  - id: count
+                    name: count
+                    language: system
+                    entry: python3 -c "import sys; open('.git/invocations', 'a').write(f'{{len(sys.argv) - 1}}\n')"
+                    require_serial: {require_serial}
+        "#}
+    };
+
+    context.write_pre_commit_config(&config(true));
+    context.git_add(".");
+    context
+        .run()
+        .env("PREFLIGIT_MAX_CLI_LENGTH", "4096")
+        .assert()
+        .success();
+    assert_snapshot!(context.read(".git/invocations"), @"201");
+
+    // Without it, the files are split into batches.
+    fs_err::remove_file(cwd.join(".git/invocations"))?;
+    context.write_pre_commit_config(&config(false));
+    context.git_add(".");
+    context
+        .run()
+        .env("PREFLIGIT_MAX_CLI_LENGTH", "4096")
+        .assert()
+        .success();
+    let invocations = context.read(".git/invocations");
+    assert!(invocations.lines().count() > 1, "{invocations}");
+    assert_eq!(
+        invocations
+            .lines()
+            .map(|line| line.parse::<usize>().unwrap())
+            .sum::<usize>(),
+        201
+    );
+
+    Ok(())
+}
+
+/// The files of a hook are split into batches that fit the command line, except without
+/// `pass_filenames`.
+#[test]
+fn batches() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    for i in 0..20 {
+        cwd.child(format!("file-{i:02}.txt")).write_str("")?;
+    }
+
+    // Record the files of each invocation, outside the working tree.
+    let config = |pass_filenames: bool| {
+        indoc::formatdoc! {r#"
+            repos:
+              - repo: local
+                hooks:
+                  - id: record
+                    name: record
+                    language: system
+                    entry: python3 -c "import sys; open('.git/invocations', 'a').write(' '.join(sys.argv[1:]) + '\n')"
+                    files: \.txt$
+                    require_serial: false
+                    pass_filenames: {pass_filenames}
+        "#}
+    };
+
+    context.write_pre_commit_config(&config(true));
+    context.git_add(".");
+    context
+        .run()
+        .env("PREFLIGIT_MAX_CLI_LENGTH", "200")
+        .assert()
+        .success();
+    let invocations = context.read(".git/invocations");
+    assert!(invocations.lines().count() > 1, "{invocations}");
+    let mut files = invocations
+        .split_whitespace()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(
+        files,
+        (0..20)
+            .map(|i| format!("file-{i:02}.txt"))
+            .collect::<Vec<_>>()
+    );
+
+    // Without filenames, the hook runs once.
+    fs_err::remove_file(cwd.join(".git/invocations"))?;
+    context.write_pre_commit_config(&config(false));
+    context.git_add(".");
+    context
+        .run()
+        .env("PREFLIGIT_MAX_CLI_LENGTH", "200")
+        .assert()
+        .success();
+    assert_eq!(context.read(".git/invocations"), "\n");
+
+    Ok(())
+}
+
+/// Without `pass_filenames`, the hook gets only its `args`, but still runs only if files match.
+#[test]
+fn no_pass_filenames() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("file.txt").write_str("Hello, world!\n")?;
+    cwd.child("other.txt").write_str("Hello, world!\n")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: argv
+                name: argv
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:])"
+                args: [--flag]
+                files: \.txt$
+                pass_filenames: false
+                verbose: true
+              - id: no-match
+                name: no-match
+                language: system
+                entry: python3 -c "import sys; print(sys.argv[1:])"
+                files: \.py$
+                pass_filenames: false
+    "#});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    argv.....................................................................Passed
+    - hook id: argv
+    - duration: [TIME]
+      ['--flag']
+    no-match.............................................(no files to check)Skipped
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// Globs in `--files` are expanded by prefligit, against the files of the working tree.
+#[test]
+fn files_glob_expansion() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("src/a.py").write_str("")?;
+    cwd.child("src/b.py").write_str("")?;
+    cwd.child("src/sub/c.py").write_str("")?;
+    cwd.child("src/d.txt").write_str("")?;
+    cwd.child("e.py").write_str("")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--files", "src/*.py", "--files", "./src/**/c.py", "--files", "*.md"]), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      src/a.py
+      src/b.py
+      src/sub/c.py
+
+    ----- stderr -----
+    warning: The pattern `*.md` in `--files` does not match any files
+    ");
+
+    Ok(())
+}
+
+/// `--files -` reads the files from stdin.
+#[test]
+fn files_from_stdin() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    cwd.child("a.txt").write_str("")?;
+    cwd.child("b.txt").write_str("")?;
+    cwd.child("c.txt").write_str("")?;
+    cwd.child("sub/d.txt").write_str("")?;
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+    "});
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().args(["--files", "-"]).pass_stdin("a.txt\r\n\nc.txt\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      a.txt
+      c.txt
+
+    ----- stderr -----
+    ");
+
+    // From a subdirectory, the paths are relative to it.
+    cmd_snapshot!(context.filters(), context.run().current_dir(cwd.join("sub")).args(["--files", "-"]).pass_stdin("d.txt\n../a.txt\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      a.txt
+      sub/d.txt
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// A hook that can't be spawned errors, which stops the run with `--fail-fast-on-error`,
+/// unlike a hook that runs and fails.
+#[test]
+fn fail_fast_on_error() {
+    let context = TestContext::new();
+    context.init_project();
+
+    context.write_pre_commit_config(indoc::indoc! {r#"
+        repos:
+          - repo: local
+            hooks:
+              - id: fail
+                name: fail
+                language: system
+                entry: python3 -c "exit(1)"
+                always_run: true
+              - id: missing
+                name: missing
+                language: system
+                entry: prefligit-missing-executable
+                always_run: true
+              - id: pass
+                name: pass
+                language: system
+                entry: python3 -c "exit(0)"
+                always_run: true
+    "#});
+    context.git_add(".");
+
+    // Without the flag, the errored hook is reported like a failure and the run continues.
+    cmd_snapshot!(context.filters(), context.run(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+    missing..................................................................Failed
+    - hook id: missing
+    - error: failed to run system command: No such file or directory (os error 2)
+    pass.....................................................................Passed
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--fail-fast-on-error"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    fail.....................................................................Failed
+    - hook id: fail
+    - exit code: 1
+    missing..................................................................Failed
+    - hook id: missing
+    - error: failed to run system command: No such file or directory (os error 2)
+    Stopped because hook `missing` errored
+
+    ----- stderr -----
+    ");
+}
+
+/// `--from-ref` and `--to-ref` run on the files changed between the refs: deleted files are
+/// left out, renamed files are passed with their new path.
+#[test]
+fn from_ref_to_ref() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+    context.configure_git_author();
+
+    context.write_pre_commit_config(indoc::indoc! {r"
+        repos:
+          - repo: meta
+            hooks:
+              - id: identity
+    "});
+    let cwd = context.workdir();
+    cwd.child("modified.txt").write_str("Hello\n")?;
+    cwd.child("deleted.txt").write_str("Hello\n")?;
+    cwd.child("old.txt")
+        .write_str("A file long enough to be detected as renamed\n")?;
+    cwd.child("unchanged.txt").write_str("Hello\n")?;
+    context.git_add(".");
+    context.git_commit("First commit");
+
+    cwd.child("modified.txt").write_str("Hello, world!\n")?;
+    fs_err::remove_file(cwd.join("deleted.txt"))?;
+    fs_err::rename(cwd.join("old.txt"), cwd.join("new.txt"))?;
+    context.git_add(".");
+    context.git_commit("Second commit");
+
+    // A staged change that is not in the refs is not run on.
+    cwd.child("staged.txt").write_str("Hello\n")?;
+    context.git_add(".");
+
+    cmd_snapshot!(context.filters(), context.run().arg("--from-ref").arg("HEAD~1").arg("--to-ref").arg("HEAD"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    identity.................................................................Passed
+    - hook id: identity
+    - duration: [TIME]
+      modified.txt
+      new.txt
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// Select the files of `run --all-files` on a synthetic tree of 50k files, where most hooks share
+/// their filter with another hook.
+///
+/// Ignored as a benchmark, time it with
+/// `cargo test --release --test run match_files_benchmark -- --ignored`.
+#[test]
+#[ignore = "benchmark"]
+fn match_files_benchmark() -> Result<()> {
+    const DIRS: usize = 500;
+    const FILES_PER_DIR: usize = 100;
+
+    let context = TestContext::new();
+    context.init_project();
+
+    let cwd = context.workdir();
+    let extensions = ["py", "rs", "md", "json", "txt"];
+    for dir in 0..DIRS {
+        for file in 0..FILES_PER_DIR {
+            let ext = extensions[file % extensions.len()];
+            cwd.child(format!("dir{dir}/file{file}.{ext}"))
+                .write_str("content\n")?;
+        }
+    }
+
+    // Each hook appends the number of files of each of its batches to a file of its own.
+    let mut config = String::from("repos:\n  - repo: local\n    hooks:\n");
+    let filters = [
+        r"files: \.py$",
+        r"files: \.py$",
+        r"files: \.rs$",
+        r"files: \.rs$",
+        "types: [markdown]",
+        "types: [markdown]",
+        "types_or: [json, markdown]",
+        r"exclude: ^dir1\d\d/",
+    ];
+    for (i, filter) in filters.iter().enumerate() {
+        let count = context.home_dir().child(format!("hook-{i}.count"));
+        writeln!(
+            config,
+            "      - id: hook-{i}\n        name: hook-{i}\n        language: system\n        entry: python3 -c 'import sys; open(r\"{}\", \"a\").write(\"%d\\n\" % (len(sys.argv) - 1))'\n        require_serial: true\n        {filter}",
+            count.display()
+        )?;
+    }
+    context.write_pre_commit_config(&config);
+    context.git_add(".");
+
+    context.run().arg("--all-files").assert().success();
+
+    let counts = (0..filters.len())
+        .map(|i| {
+            fs_err::read_to_string(context.home_dir().child(format!("hook-{i}.count")))
+                .unwrap()
+                .lines()
+                .map(|count| count.parse::<usize>().unwrap())
+                .sum::<usize>()
+        })
+        .collect::<Vec<_>>();
+    // A fifth of the files per extension, and the config is only left out by `exclude`.
+    assert_eq!(
+        counts,
+        [10_000, 10_000, 10_000, 10_000, 10_000, 10_000, 20_000, 40_001]
+    );
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use assert_fs::prelude::*;
+
 use crate::common::{cmd_snapshot, TestContext};
 
 mod common;
@@ -25,3 +27,84 @@ fn sample_config() {
     ----- stderr -----
     "##);
 }
+
+/// The sample config is a valid config.
+#[test]
+fn sample_config_parses() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    let output = context.sample_config().output()?;
+    assert!(output.status.success());
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("-").pass_stdin(output.stdout), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
+
+/// `--file` writes the sample config to a file, without overwriting an existing one.
+#[test]
+fn sample_config_file() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    cmd_snapshot!(context.filters(), context.sample_config().arg("--file").arg(".pre-commit-config.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Sample config written to `.pre-commit-config.yaml`
+
+    ----- stderr -----
+    ");
+
+    let written = context.read(".pre-commit-config.yaml");
+    let stdout = context.sample_config().output()?.stdout;
+    assert_eq!(written, String::from_utf8(stdout)?);
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg(".pre-commit-config.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    cmd_snapshot!(context.filters(), context.sample_config().arg("--file").arg(".pre-commit-config.yaml"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    `.pre-commit-config.yaml` already exists, not overwriting it
+    ");
+
+    Ok(())
+}
+
+/// `--file` is relative to the directory prefligit is run from, not the repo root.
+#[test]
+fn sample_config_file_subdirectory() -> anyhow::Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let sub = context.workdir().child("sub");
+    fs_err::create_dir_all(&sub)?;
+
+    cmd_snapshot!(context.filters(), context.sample_config().current_dir(&sub).arg("--file").arg("sample.yaml"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Sample config written to `sub/sample.yaml`
+
+    ----- stderr -----
+    ");
+
+    assert!(sub.child("sample.yaml").exists());
+    assert!(!context.workdir().child("sample.yaml").exists());
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use assert_fs::prelude::*;
+
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+/// `try-repo` runs the hooks of a local repo, including its uncommitted changes.
+#[test]
+fn try_local_repo() -> Result<()> {
+    let context = TestContext::new();
+    context.init_project();
+
+    let repo = context.create_hook_repo(
+        "hook-repo",
+        indoc::indoc! {r"
+            - id: hello
+              name: hello
+              language: system
+              entry: echo Hello
+              verbose: true
+            - id: bye
+              name: bye
+              language: system
+              entry: echo Bye
+              verbose: true
+        "},
+    );
+
+    context.workdir().child("file.txt").write_str("Hello\n")?;
+    context.git_add("file.txt");
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([
+            (r"[0-9a-f]{40}", "[SHA]"),
+            (r"file://\S+/shadow-repo", "file://[SHADOW_REPO]"),
+        ])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters.clone(), context.command().arg("try-repo").arg("hook-repo").arg("--files").arg("file.txt"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[TEMP_DIR]/hook-repo@[SHA]
+    ===============================================================================
+    Using config:
+    ===============================================================================
+    repos:
+    - repo: file://[TEMP_DIR]/hook-repo
+      rev: [SHA]
+      hooks:
+      - id: hello
+      - id: bye
+    ===============================================================================
+    hello....................................................................Passed
+    - hook id: hello
+    - duration: [TIME]
+      Hello file.txt
+    bye......................................................................Passed
+    - hook id: bye
+    - duration: [TIME]
+      Bye file.txt
+
+    ----- stderr -----
+    ");
+
+    // Uncommitted changes of the repo are tried, and a single hook can be selected.
+    repo.child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+        - id: hello
+          name: hello
+          language: system
+          entry: echo Hello again
+          verbose: true
+    "})?;
+
+    cmd_snapshot!(filters.clone(), context.command().arg("try-repo").arg(&*repo).arg("hello").arg("--all-files"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Cloning file://[SHADOW_REPO]@[SHA]
+    ===============================================================================
+    Using config:
+    ===============================================================================
+    repos:
+    - repo: file://[SHADOW_REPO]
+      rev: [SHA]
+      hooks:
+      - id: hello
+    ===============================================================================
+    hello....................................................................Passed
+    - hook id: hello
+    - duration: [TIME]
+      Hello again file.txt
+
+    ----- stderr -----
+    ");
+
+    Ok(())
+}
@@ -57,6 +57,83 @@ fn validate_config() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Read the config from stdin with `-`.
+#[test]
+fn validate_config_stdin() {
+    let context = TestContext::new();
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("-").pass_stdin(indoc::indoc! {r"
+        repos:
+          - repo: https://github.com/pre-commit/pre-commit-hooks
+            hooks:
+              - id: trailing-whitespace
+    "}), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to parse `<stdin>`
+      caused by: repos: Invalid remote repo: missing field `rev` at line 2 column 3
+    ");
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("-").pass_stdin(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks: []
+    "}), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+}
+
+/// `default_language_version` only accepts known languages.
+#[test]
+fn validate_default_language_version() {
+    let context = TestContext::new();
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("-").pass_stdin(indoc::indoc! {r"
+        default_language_version:
+          pythn: python3.11
+        repos: []
+    "}), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to parse `<stdin>`
+      caused by: default_language_version: unknown variant `pythn`, expected one of `conda`, `coursier`, `dart`, `docker`, `docker_image`, `dotnet`, `fail`, `golang`, `haskell`, `lua`, `node`, `perl`, `python`, `r`, `ruby`, `rust`, `swift`, `pygrep`, `remote_script`, `script`, `system` at line 2 column 3
+    ");
+}
+
+/// Hooks that require a newer pre-commit are warned about, but the config is valid.
+#[test]
+fn validate_unsupported_version() {
+    let context = TestContext::new();
+
+    cmd_snapshot!(context.filters(), context.validate_config().arg("-").pass_stdin(indoc::indoc! {r"
+        repos:
+          - repo: local
+            hooks:
+              - id: future
+                name: future
+                language: system
+                entry: echo
+                minimum_pre_commit_version: '999.0.0'
+    "}), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    warning: Hook `future` requires pre-commit version `999.0.0`, but prefligit is compatible with pre-commit version `4.0.1`
+    ");
+}
+
 #[test]
 fn validate_manifest() -> anyhow::Result<()> {
     let context = TestContext::new();
@@ -116,3 +193,36 @@ fn validate_manifest() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Errors in the optional fields of a manifest hook are reported with their location.
+#[test]
+fn validate_manifest_invalid_field() -> anyhow::Result<()> {
+    let context = TestContext::new();
+
+    context
+        .workdir()
+        .child(".pre-commit-hooks.yaml")
+        .write_str(indoc::indoc! {r"
+            -   id: trailing-whitespace
+                name: trim trailing whitespace
+                entry: trailing-whitespace-fixer
+                language: python
+            -   id: end-of-file-fixer
+                name: fix end of files
+                entry: end-of-file-fixer
+                language: python
+                stages: [pre-commit, pre-pull]
+        "})?;
+
+    cmd_snapshot!(context.filters(), context.validate_manifest().arg(".pre-commit-hooks.yaml"), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: Failed to parse `.pre-commit-hooks.yaml`
+      caused by: .[1].stages: unknown variant `pre-pull`, expected one of `manual`, `commit-msg`, `post-checkout`, `post-commit`, `post-merge`, `post-rewrite`, `pre-commit`, `pre-merge-commit`, `pre-push`, `pre-rebase`, `prepare-commit-msg` at line 9 column 13
+    ");
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use crate::common::{cmd_snapshot, TestContext};
+
+mod common;
+
+#[test]
+fn version() {
+    let context = TestContext::new();
+    context.init_project();
+
+    let filters = context
+        .filters()
+        .into_iter()
+        .chain([
+            (r"prefligit(:?) \d+\.\d+\.\d+", "prefligit$1 [VERSION]"),
+            (r"commit: \S+", "commit: [COMMIT]"),
+            (r"target: \S+", "target: [TARGET]"),
+        ])
+        .collect::<Vec<_>>();
+
+    cmd_snapshot!(filters.clone(), context.command().arg("version"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit [VERSION]
+
+    ----- stderr -----
+    ");
+
+    // Verbose output includes build and store information.
+    cmd_snapshot!(filters, context.command().arg("version").arg("--verbose"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    prefligit [VERSION]
+    commit: [COMMIT]
+    target: [TARGET]
+    store: [HOME]/
+
+    ----- stderr -----
+    DEBUG prefligit: [VERSION]
+    DEBUG Git root: [TEMP_DIR]/
+    DEBUG Loading store from PRE_COMMIT_HOME path=[HOME]/
+    ");
+}